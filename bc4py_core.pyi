@@ -17,6 +17,8 @@ class PyAddress:
         """20 bytes"""
     def binary(self) -> bytes:
         """21 bytes"""
+    def to_lock_address(self) -> PyAddress:
+        """fund-lock address for FlkPos staking, unlocked after **10080** blocks"""
 
 
 """
@@ -205,6 +207,90 @@ class PySignature:
     def add_from_binary(self, binary: bytes) -> None: ...
     def get_size(self) -> int: ...
     def len(self) -> int: ...
+    @staticmethod
+    def get_cache_stats() -> Tuple[int, int, int, int]:
+        """verified signature cache's (hits, misses, length, capacity)"""
+    @staticmethod
+    def set_cache_capacity(capacity: int) -> None: ...
+    @staticmethod
+    def clear_cache() -> None:
+        """remove all and reset counters"""
+
+class PyMuSigSession:
+    """n of n signing session of a signer, rounds: commitment -> nonce -> partial -> combine"""
+    apk: bytes
+    pubkeys: Tuple[bytes, ...]
+    message: bytes
+
+    def __init__(self, pubkeys: Sequence[bytes], my_pk: bytes, message: bytes) -> None: ...
+    @staticmethod
+    def aggregate_public_keys(pubkeys: Sequence[bytes]) -> bytes: ...
+    @staticmethod
+    def get_aggregate_address(ver: int, pubkeys: Sequence[bytes]) -> PyAddress: ...
+    @classmethod
+    def from_bytes(cls, binary: bytes) -> PyMuSigSession: ...
+    def to_bytes(self) -> bytes:
+        """secret nonce isn't included, a restored session can't sign"""
+    def get_address(self, ver: int) -> PyAddress: ...
+    def get_commitment(self) -> bytes: ...
+    def add_commitment(self, pk: bytes, commitment: bytes) -> None: ...
+    def get_nonce(self) -> bytes:
+        """reveal after received all commitments"""
+    def add_nonce(self, pk: bytes, nonce: bytes) -> None: ...
+    def sign_partial(self, sk: bytes) -> bytes:
+        """nonce is consumed, sign only once"""
+    def add_partial(self, pk: bytes, s: bytes) -> None:
+        """partial signature is verified"""
+    def combine(self) -> PySignature: ...
+
+class PyThresholdKeyGen:
+    """distributed key generation of n of m members (Pedersen DKG with Feldman VSS)"""
+    threshold: int
+    index: int
+    members: Sequence[int]
+    is_nonce: bool
+
+    def __init__(self, threshold: int, index: int, members: Sequence[int]) -> None:
+        """member index is 1 to 255"""
+    @staticmethod
+    def new_nonce(threshold: int, index: int, members: Sequence[int]) -> PyThresholdKeyGen:
+        """run among signers to generate the one-time nonce"""
+    @classmethod
+    def from_bytes(cls, binary: bytes) -> PyThresholdKeyGen: ...
+    def to_bytes(self) -> bytes:
+        """nonce generation isn't serializable"""
+    def get_commitments(self) -> Tuple[bytes, ...]:
+        """broadcast to all members"""
+    def get_share(self, to: int) -> bytes:
+        """send to the member by a secure channel"""
+    def add_commitments(self, from_: int, commitments: Sequence[bytes]) -> None: ...
+    def add_share(self, from_: int, share: bytes) -> None:
+        """share is verified by the commitments"""
+    def is_finished(self) -> bool: ...
+    def finish(self) -> PyThresholdShare: ...
+
+class PyThresholdShare:
+    """a member's share of the shared key or the one-time nonce"""
+    threshold: int
+    index: int
+    members: Sequence[int]
+    public_key: bytes
+    is_nonce: bool
+    has_secret: bool
+
+    @classmethod
+    def from_bytes(cls, binary: bytes) -> PyThresholdShare: ...
+    def to_bytes(self) -> bytes:
+        """nonce's secret isn't included"""
+    def to_public(self) -> PyThresholdShare:
+        """copy without the secret for a combiner"""
+    def get_address(self, ver: int) -> PyAddress: ...
+    def get_public_share(self, index: int) -> bytes: ...
+    def sign_partial(self, nonce: PyThresholdShare, message: bytes) -> bytes:
+        """nonce is consumed, sign only once"""
+    def verify_partial(self, nonce: PyThresholdShare, index: int, gamma: bytes, message: bytes) -> bool: ...
+    def combine(self, nonce: PyThresholdShare, partials: Sequence[Tuple[int, bytes]], message: bytes) -> PySignature:
+        """combine `threshold` verified partial signatures"""
 
 
 """
@@ -260,6 +346,8 @@ Chain
 
 class PyChain:
     is_closed: bool
+    is_wallet_locked: bool
+    is_wallet_encrypted: bool
 
     def __init__(
             self,
@@ -269,26 +357,129 @@ class PyChain:
             tx_index: bool,
             addr_index: bool
     ) -> None: ...
+    @classmethod
+    def from_mnemonic(
+            cls,
+            root_dir: str,
+            mnemonic: str,
+            passphrase: Optional[str],
+            coin_type: int,
+            deadline: int,
+            tx_index: bool,
+            addr_index: bool
+    ) -> PyChain:
+        """restore by BIP39 mnemonic, secret is derived to **m/44'/CoinType'**"""
+    @staticmethod
+    def generate_mnemonic(words: int) -> str:
+        """english mnemonic of 12, 15, 18, 21 or 24 words"""
+    @staticmethod
+    def validate_mnemonic(mnemonic: str) -> bool: ...
     def push_new_block(self, block: PyBlock) -> None: ...
     def push_unconfirmed(self, tx: PyTx) -> None: ...
     def get_block(self, hash: bytes) -> Optional[PyBlock]: ...
     def get_full_block(self, hash: bytes) -> Optional[PyBlock]: ...
+    def get_block_filter(self, hash: bytes) -> Optional[bytes]:
+        """compact block filter over output addresses and spent inputs
+
+        note: filter of block stored by older version is built on first access
+        """
+    @staticmethod
+    def match_block_filter(hash: bytes, filter: bytes, addrs: Sequence[PyAddress]) -> bool:
+        """**true** means the block may include the addresses (false positive rate is 1/784931)"""
+    def match_account_filter(self, hash: bytes) -> bool:
+        """check the block may include account tx by the block filter"""
     def get_best_block(self, full: bool) -> PyBlock: ...
     def get_tx(self, hash: bytes) -> Optional[PyTx]: ...
+    def get_tx_proof(self, hash: bytes) -> Optional[Tuple[bytes, Sequence[bytes], int]]:
+        """(header, branch, index) of tx inclusion, **None** if not indexed or unconfirmed"""
+    @staticmethod
+    def verify_merkle_branch(root: bytes, hash: bytes, branch: Sequence[bytes], index: int) -> bool: ...
     def get_account_balance(self, account_id: int, confirm: int) -> PyAccount: ...
     def get_account_address(self, account_id: int, new: bool) -> PyAddress:
         """get unused address by id, **new** flag force return addr not generated by user yet"""
+    def add_watch_only_account(self, account_id: int, xpub: bytes) -> None:
+        """listen account by 65 bytes xpub **m/44'/CoinType'/account'**, signing is always error"""
+    def is_watch_only_account(self, account_id: int) -> bool: ...
+    def encrypt_wallet(self, passphrase: str) -> None:
+        """encrypt secret by passphrase (scrypt + ChaCha20Poly1305) and lock, also use to change passphrase"""
+    def unlock_wallet(self, passphrase: str, timeout: int) -> None:
+        """signing is enabled for **timeout** seconds, 0 is no timeout"""
+    def lock_wallet(self) -> None: ...
     def get_account_addr_path(self, addr: PyAddress) -> Tuple[int, int, int]:
         """find address derive path **m/44'/CoinType'/account'/is_inner/index**"""
+    def rescan_accounts(self, from_height: int) -> int:
+        """find account txs from finalized blocks and return the count, skip already recoded movement"""
     def calc_unspent_by_amount(self, balances: PyBalance) -> Sequence[PyUnspent]: ...
+    def build_tx(
+            self,
+            version: int,
+            time: int,
+            deadline: int,
+            destinations: Sequence[Tuple[PyAddress, int, int]],
+            gas_price: int,
+            change_account: int,
+            message: Optional[Tuple[int, bytes]],
+            dust_limit: int
+    ) -> PyTx:
+        """signed transfer tx, inputs are selected by branch-and-bound (largest-first fallback)
+        and the change goes to a new address of **change_account**,
+        unspent of used coins less than or equal to **dust_limit** are also spent (0 is disabled)"""
+    def propose_consolidation(
+            self,
+            version: int,
+            time: int,
+            deadline: int,
+            destinations: Sequence[Tuple[PyAddress, int, int]],
+            gas_price: int,
+            change_account: int,
+            message: Optional[Tuple[int, bytes]],
+            dust_limit: int
+    ) -> Sequence[PyTx]:
+        """merge txs to push in order before **build_tx** if the payment require over 255 inputs"""
+    def build_consolidation(
+            self,
+            account_id: int,
+            coin_id: int,
+            max_inputs: int,
+            min_amount: int,
+            version: int,
+            time: int,
+            deadline: int,
+            gas_price: int
+    ) -> Sequence[PyTx]:
+        """merge unspent less than **min_amount** to outputs of **min_amount** or more (for staking)"""
+    def build_sweep(
+            self,
+            source: Union[int, Sequence[PyAddress]],
+            target: PyAddress,
+            version: int,
+            time: int,
+            deadline: int,
+            gas_price: int
+    ) -> Sequence[PyTx]:
+        """move all funds of account_id or addresses to **target**, fee is subtracted from coinId 0"""
+    def sign_partial_tx(self, partial: PyPartialTx) -> int:
+        """fill derivation paths and sign account's inputs, return new signatures count"""
     def list_unspent_by_addr(self, addrs: Sequence[PyAddress], page: int, size: int) -> Sequence[PyUnspent]: ...
     def list_unspent_for_staking(self, mature_height: int, limit: int)-> Tuple[Sequence[PyUnspent], int]: ...
     def list_account_movement(self, page: int, size: int) -> Sequence[PyMovement]: ...
+    def get_mint_coin(self, coin_id: int) -> Optional[dict]:
+        """newest coin params, setting and supply on best_chain"""
+    def get_mint_history(self, coin_id: int) -> Sequence[Tuple[Optional[int], bytes, int]]:
+        """supply history (height, txhash, amount), height is **None** if not finalized"""
+    @staticmethod
+    def encode_mint_message(
+            coin_id: int,
+            owner: Optional[PyAddress],
+            params: Optional[Tuple[str, str, int, str]],
+            setting: Optional[Tuple[bool, bool]]
+    ) -> bytes:
+        """mint tx's Byte message, params is (name, unit, digit, description) and setting is (additional_issue, change_params)"""
     def close(self) -> None: ...
 
 
 BlockTimeParams = Sequence[Tuple[int, int, int, int]]
-"""diff param (flag, T, N, K)"""
+"""diff param (flag, T, N, K), FlkPos(3) is enabled only when given such as `(3, 120, 60, 3660)`"""
 
 class PyValidate:
     def __init__(self, chain: PyChain, total_supply: int, params: BlockTimeParams) -> None:
@@ -299,6 +490,10 @@ class PyValidate:
         """this method is designed for PoS coinbase tx's input check"""
     def is_unused_inputs(self, inputs: PyTxInputs, except_hash: bytes, best_block: Optional[PyBlock]) -> bool:
         """check inputs already used on both unconfirmed and confirmed status, return **true** means valid"""
+    def check_block(self, block: PyBlock) -> Optional[str]:
+        """full consensus check of Block(n+1), return **None** means valid or rejected reason"""
+    def check_tx(self, tx: PyTx) -> Optional[str]:
+        """standalone check of unconfirmed tx, return **None** means valid or rejected reason"""
     def is_orphan_block(self, block: PyBlock) -> bool:
         """check the block included by best_chain or recoded to tables and indexed"""
     def clear_old_unconfirmed(self, deadline: int) -> int:
@@ -315,7 +510,111 @@ class PyValidate:
     def target_to_diff(target: bytes) -> float: ...
     def calc_block_reward(self, height: int) -> int: ...
     def calc_total_supply(self, height: int) -> int: ...
-    def push_pow_worker(self, flag: int, power_limit: int, block_ver: int, tx_ver: int) -> None: ...
+    def push_pow_worker(
+            self, flag: int, power_limit: int, block_ver: int, tx_ver: int, threads: Optional[int] = None
+    ) -> None:
+        """each thread hash disjoint nonce range and respect power_limit (1~255)"""
     def push_pos_worker(self) -> None: ...
+    def push_flk_worker(self) -> None: ...
     def push_poc_worker(self, dirs: Sequence[str]) -> None: ...
     def remove_worker(self, flag: int) -> None: ...
+    def get_worker_info(self) -> Sequence[str]: ...
+    def update_workers(self, maxsize: int, time: int, deadline: int) -> None:
+        """update workers by best block and size limited unconfirmed txs"""
+    def start_generate_pool(self) -> None:
+        """keep generating on background threads, start after next `update_workers`"""
+    def cancel_generate_pool(self) -> None:
+        """pause background threads until next `update_workers`"""
+    def stop_generate_pool(self) -> None:
+        """shutdown background threads and return workers to `generate_work`"""
+    def get_pool_result(self) -> Optional[PyBlock]:
+        """non-blocking, return mined block or **None** if not found yet"""
+    def get_block_template(
+            self,
+            flag: int,
+            maxsize: int,
+            time: int,
+            deadline: int,
+            block_ver: int,
+            tx_ver: int,
+    ) -> dict:
+        """PoW block template (flag, height, header, coinbase, txs, bits, bias, reward, branch) for external miners"""
+    def submit_block(self, header: bytes, coinbase: bytes) -> PyBlock:
+        """rebuild block from the last template, raise ValueError if rejected"""
+    def start_stratum(self, addr: str, share_bits: int) -> str:
+        """start JSON-over-TCP job server for external PoW miners and return bound address"""
+    def update_stratum_job(self, flag: int) -> None:
+        """notify new jobs from the flag's PoW worker, call after `update_workers`
+
+        raise ValueError if pooled worker hasn't received the update yet, retry later
+        """
+    def get_stratum_result(self) -> Optional[PyBlock]:
+        """non-blocking, return block from winning share or **None**, poll periodically"""
+    def get_stratum_info(self) -> Optional[str]:
+        """stratum server info or **None** if not started"""
+    def stop_stratum(self) -> None:
+        """disconnect clients and stop stratum server"""
+    def generate_work(self) -> Optional[PyBlock]:
+        """wait for workers generation and return mined block with txs or **None** if not found work"""
+    def throw_generate_task(self) -> None:
+        """non-blocking version of `generate_work`, error if already thrown"""
+    def is_generate_finished(self) -> bool:
+        """poll the task thrown by `throw_generate_task`"""
+    def get_generate_result(self) -> Optional[PyBlock]:
+        """get mined block of the thrown task, wait for finish if not finished yet"""
+
+
+"""
+SPV
+"""
+
+class PyHeaderChain:
+    """header-only chain for light clients"""
+    def __init__(self, params: BlockTimeParams) -> None: ...
+    @classmethod
+    def from_bytes(cls, params: BlockTimeParams, binary: bytes) -> PyHeaderChain:
+        """restore from `to_bytes`, headers are verified again"""
+    def to_bytes(self) -> bytes: ...
+    def push_header(
+            self,
+            header: bytes,
+            flag: int,
+            bias: float,
+            stake: Optional[Tuple[PyTx, PyAddress, int, Sequence[bytes]]] = None,
+    ) -> bytes:
+        """verify bits, bias and work, PoS require stake (coinbase, address, amount, coinbase's branch)
+
+        note: PoS header is unverified because the stake isn't proved, it don't add score and confirmations
+        """
+    def get_best_hash(self) -> Optional[bytes]: ...
+    def get_best_height(self) -> Optional[int]: ...
+    def get_hash_by_height(self, height: int) -> Optional[bytes]: ...
+    def get_header(self, hash: bytes) -> Optional[Tuple[bytes, int, int, float]]:
+        """(header, height, flag, bias) include forks"""
+    def is_verified(self, hash: bytes) -> bool:
+        """header's work is proved (not PoS header)"""
+    def is_main_chain(self, hash: bytes) -> bool: ...
+    def verify_tx(self, blockhash: bytes, hash: bytes, branch: Sequence[bytes], index: int) -> int:
+        """verify tx inclusion by main chain's verified block and return verified confirmations"""
+
+
+"""
+Partially signed tx
+"""
+
+class PyPartialTx:
+    """partially signed tx for multi-party and offline signing"""
+    is_complete: bool
+    def __init__(self, tx: PyTx) -> None:
+        """signature and inputs_cache are copied from tx if exist"""
+    @classmethod
+    def from_bytes(cls, binary: bytes) -> PyPartialTx: ...
+    def to_bytes(self) -> bytes: ...
+    @classmethod
+    def from_json(cls, string: str) -> PyPartialTx: ...
+    def to_json(self) -> str: ...
+    def merge(self, other: PyPartialTx) -> None:
+        """merge signatures, inputs_cache and paths of the same tx"""
+    def finalize(self) -> PyTx:
+        """verify all signatures and output signed tx"""
+    def inspect(self) -> dict: ...
@@ -67,7 +67,7 @@ pub enum BlockFlag {
     Genesis, // genesis tx
     CoinPos, // coin stake
     CapPos,  // capacity stake
-    FlkPos,  // found lock stake (unimplemented)
+    FlkPos,  // fund lock stake
     YesPow,  // yespower work
     X11Pow,  // X11 work
     X16sPow, // X16S work
@@ -99,7 +99,7 @@ impl BlockFlag {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct Block {
     // meta
     pub work_hash: U256,
@@ -46,6 +46,23 @@ static MAX_SEARCH_BLOCKS: usize = 1000;
 /// block time params (BlockFlag, T, N, K)
 pub type BlockTimeParams = Vec<(BlockFlag, u32, u32, u32)>;
 
+/// FlkPos block time params, T=120 N=60 and K=T*(N+1)/2
+///
+/// note: not included by default because adding a flag changes bias of others,
+/// FlkPos is activated when the node append this to params
+pub const FLK_POS_PARAMS: (BlockFlag, u32, u32, u32) = (BlockFlag::FlkPos, 120, 60, 120 * (60 + 1) / 2);
+
+/// header source to calc difficulty (full tables or header-only chain)
+pub trait HeaderReader {
+    fn read_header(&self, hash: &U256) -> Result<Option<Block>, String>;
+}
+
+impl HeaderReader for Tables {
+    fn read_header(&self, hash: &U256) -> Result<Option<Block>, String> {
+        self.read_block(hash)
+    }
+}
+
 /// BlockHeader with meta info
 #[derive(PartialEq)]
 struct MetaHeader {
@@ -69,18 +86,23 @@ impl DifficultyBuilder {
         }
     }
 
-    pub fn calc_next_bits(
+    pub fn calc_next_bits<T: HeaderReader>(
         &mut self,
         previous_hash: &U256,
         flag: &BlockFlag,
-        tables: &Tables,
+        tables: &T,
     ) -> Result<u32, String> {
         if *previous_hash == *GENESIS_PREVIOUS_HASH {
             return Ok(MAX_BITS);
         }
 
         // block difficulty params
-        let (_flag, _T, N, K) = self.params.iter().find(|p| &p.0 == flag).unwrap();
+        // note: FlkPos etc.. is disabled if the params isn't defined (see FLK_POS_PARAMS)
+        let (_flag, _T, N, K) = self
+            .params
+            .iter()
+            .find(|p| &p.0 == flag)
+            .ok_or(format!("not found block time params of {:?}", flag))?;
         let (mut N, K) = (*N, *K);
 
         // Loop through N most recent blocks.  "< height", not "<=".
@@ -161,11 +183,11 @@ impl DifficultyBuilder {
         Ok(target_to_bits(&new_target))
     }
 
-    pub fn calc_next_bias(
+    pub fn calc_next_bias<T: HeaderReader>(
         &mut self,
         previous_hash: &U256,
         flag: &BlockFlag,
-        tables: &Tables,
+        tables: &T,
     ) -> Result<f32, String> {
         let N = 30u32; // target blocks
 
@@ -179,6 +201,11 @@ impl DifficultyBuilder {
             return Ok(1.0);
         }
 
+        // check params defined
+        if self.params.iter().all(|p| &p.0 != flag) {
+            return Err(format!("not found block time params of {:?}", flag));
+        }
+
         // calc
         let mut target_sum = U512::from(0u32);
         let mut target_cnt = 0;
@@ -239,11 +266,15 @@ impl DifficultyBuilder {
         }
     }
 
-    fn get_header_ref(&mut self, hash: &U256, tables: &Tables) -> Result<Option<&MetaHeader>, String> {
+    fn get_header_ref<T: HeaderReader>(
+        &mut self,
+        hash: &U256,
+        tables: &T,
+    ) -> Result<Option<&MetaHeader>, String> {
         // note: err occur by tables exception
         if !self.cache.contains_key(hash) {
             // need to insert new header
-            match tables.read_block(hash)? {
+            match tables.read_header(hash)? {
                 Some(block) => {
                     let header = MetaHeader {
                         height: block.height,
@@ -1,17 +1,20 @@
 use crate::block::{bits_to_target, get_pos_hash, Block, BlockFlag, BlockHeader, DifficultyBuilder};
 use crate::chain::unconfirmed::UnconfirmedTxs;
 use crate::chain::Chain;
-use crate::tx::{TxBody, TxMessage, TxOutput, TxType, TxVerifiable};
+use crate::tx::{TxBody, TxMessage, TxOutput, TxType, TxVerifiable, LOCK_MATURE_HEIGHT};
 use crate::utils::*;
 use bc4py_hash::plotfile::{PlotFile, PlotFlag};
 use bc4py_hash::seekfile::seek_file;
 use bc4py_hash::{get_x11_hash, get_x16s_hash, get_yespower_hash};
 use bigint::U256;
-use std::cmp::max;
+use std::cmp::{max, min};
 use std::collections::{HashMap, VecDeque};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// mining worker duck typing
 type WorkerType = Box<dyn WorkerTrait + Send>;
@@ -34,7 +37,9 @@ pub enum WorkerResult {
 /// general mining & staking trait
 pub trait WorkerTrait {
     /// manually throw task and may return in one second
-    fn generate(&mut self) -> WorkerResult;
+    ///
+    /// note: return `NotFoundWork` soon after `cancel` flag is set
+    fn generate(&mut self, cancel: &AtomicBool) -> WorkerResult;
 
     /// update mining info by new accepted block or reverted block
     fn update_by_new_block(
@@ -67,6 +72,67 @@ pub trait WorkerTrait {
 
     /// get block flag
     fn get_flag(&self) -> &BlockFlag;
+
+    /// mining header & coinbase template for external miners
+    ///
+    /// note: only PoW worker has a template
+    fn get_template(&self) -> Option<(BlockHeader, TxBody)> {
+        None
+    }
+}
+
+/// hashing job of a nonce range thread (header, start, count, target)
+type RangeJob = ([u8; 80], u64, u64, U256);
+
+/// hashing result of a nonce range thread (index, found (work, header), next nonce)
+type RangeResult = (usize, Option<(U256, [u8; 80])>, u64);
+
+/// long-lived thread hashing a nonce range
+struct RangeThread {
+    sender: Sender<RangeJob>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl RangeThread {
+    /// spawn hashing thread, exit when the job sender dropped
+    fn spawn(
+        index: usize,
+        hash_func: fn(&[u8]) -> Vec<u8>,
+        power_limit: f64,
+        abort: Arc<AtomicBool>,
+        result_sender: Sender<RangeResult>,
+    ) -> Self {
+        let (sender, receiver) = channel::<RangeJob>();
+        let thread = thread::spawn(move || {
+            while let Ok((mut bytes, mut nonce, count, target)) = receiver.recv() {
+                let now = Instant::now();
+                let mut found = None;
+                for _ in 0..count {
+                    if abort.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    bytes[76..76 + 4].clone_from_slice(&(nonce as u32).to_le_bytes());
+                    let output = hash_func(bytes.as_ref());
+                    nonce += 1;
+                    // check enough work
+                    let work = U256::from(output.as_slice());
+                    if work < target {
+                        found = Some((work, bytes));
+                        break;
+                    }
+                }
+                // respect power_limit by sleep
+                let busy = now.elapsed();
+                if power_limit < 255.0 && !abort.load(Ordering::Relaxed) {
+                    thread::sleep(busy.mul_f64((255.0 - power_limit) / power_limit));
+                }
+                if result_sender.send((index, found, nonce)).is_err() {
+                    break;
+                }
+            }
+        });
+        RangeThread { sender, thread }
+    }
 }
 
 /// proof of work
@@ -74,17 +140,27 @@ pub struct PowWorker {
     flag: BlockFlag,
     header: BlockHeader,
     coinbase: TxBody,
-    /// pow hash function (input 80bytes slice and output 32bytes vec)
-    hash_func: fn(&[u8]) -> Vec<u8>,
-    /// CPU occupancy (power_limit / 255 * 100 %)
+    /// unconfirmed txs to calc merkleroot when extra-nonce changed
+    txs: Vec<U256>,
+    /// CPU occupancy of each thread (power_limit / 255 * 100 %)
     power_limit: f64,
+    /// next nonce of each thread's disjoint range
+    nonces: Vec<u64>,
+    /// hashing threads living with the worker
+    threads: Vec<RangeThread>,
+    /// stop hashing threads' running job
+    abort: Arc<AtomicBool>,
+    result_receiver: Receiver<RangeResult>,
+    /// coinbase's extra-nonce incremented when nonce range run out
+    extra_nonce: u32,
     /// store generation span to fix best count number
     span: VecDeque<(usize, f64)>, // (count, time)
 }
 
 impl PowWorker {
-    pub fn new(flag: &BlockFlag, power_limit: u8, block_ver: u32, tx_ver: u32) -> WorkerType {
+    pub fn new(flag: &BlockFlag, power_limit: u8, block_ver: u32, tx_ver: u32, threads: usize) -> WorkerType {
         assert!(0 < power_limit, "power_limit's range is 1~255");
+        assert!(0 < threads && threads <= 256, "threads's range is 1~256");
         // note: need to init before mining
         let flag = flag.clone();
         let header = BlockHeader {
@@ -112,30 +188,100 @@ impl PowWorker {
             BlockFlag::X16sPow => |x| get_x16s_hash(x),
             _ => unreachable!(),
         };
-        Box::new(PowWorker {
+        let abort = Arc::new(AtomicBool::new(false));
+        let (result_sender, result_receiver) = channel();
+        let threads = (0..threads)
+            .map(|index| {
+                RangeThread::spawn(
+                    index,
+                    hash_func,
+                    power_limit as f64,
+                    abort.clone(),
+                    result_sender.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut worker = PowWorker {
             flag,
             header,
             coinbase,
-            hash_func,
+            txs: vec![],
             power_limit: power_limit as f64,
+            nonces: vec![0; threads.len()],
+            threads,
+            abort,
+            result_receiver,
+            extra_nonce: 0,
             span: VecDeque::new(),
-        })
+        };
+        worker.reset_nonces();
+        Box::new(worker)
+    }
+
+    /// nonce range of the thread [start, end)
+    fn nonce_range(&self, index: usize) -> (u64, u64) {
+        nonce_range(index, self.nonces.len())
+    }
+
+    /// private reset nonces to the start of each range
+    fn reset_nonces(&mut self) {
+        for index in 0..self.nonces.len() {
+            self.nonces[index] = self.nonce_range(index).0;
+        }
+    }
+
+    /// private set extra-nonce to coinbase and update merkleroot
+    fn set_extra_nonce(&mut self, extra_nonce: u32) {
+        // note: extra-nonce zero is same as no message
+        self.extra_nonce = extra_nonce;
+        self.coinbase.message = if extra_nonce == 0 {
+            TxMessage::Nothing
+        } else {
+            TxMessage::Byte(extra_nonce.to_le_bytes().to_vec())
+        };
+        self.update_merkleroot();
+        self.reset_nonces();
     }
 
     /// private update merkleroot
-    fn update_merkleroot(&mut self, unconfirmed: &UnconfirmedTxs) {
-        let mut hashs = Vec::with_capacity(1 + unconfirmed.txs.len());
+    fn update_merkleroot(&mut self) {
+        let mut hashs = Vec::with_capacity(1 + self.txs.len());
         hashs.push(U256::from(self.coinbase.hash().as_slice()));
-        hashs.extend_from_slice(&unconfirmed.txs);
+        hashs.extend_from_slice(&self.txs);
         self.header.merkleroot = calc_merkleroot_hash(hashs);
     }
 }
 
+impl Drop for PowWorker {
+    fn drop(&mut self) {
+        // note: the thread exit after the job sender dropped
+        self.abort.store(true, Ordering::Relaxed);
+        for range in self.threads.drain(..) {
+            drop(range.sender);
+            range.thread.join().ok();
+        }
+    }
+}
+
+/// nonce range of the thread [start, end)
+///
+/// note: the last range take leftover nonces of `2^32 % threads`
+fn nonce_range(index: usize, threads: usize) -> (u64, u64) {
+    let all = u32::MAX as u64 + 1;
+    let size = all / threads as u64;
+    let start = size * index as u64;
+    if index + 1 == threads {
+        (start, all)
+    } else {
+        (start, start + size)
+    }
+}
+
 impl WorkerTrait for PowWorker {
-    fn generate(&mut self) -> WorkerResult {
+    fn generate(&mut self, cancel: &AtomicBool) -> WorkerResult {
         let now = Instant::now();
 
-        // find good count number
+        // find good count number (each thread)
         let count = if 10 < self.span.len() {
             let mut fixed = 0.0;
             let mut real = 0.0;
@@ -154,28 +300,68 @@ impl WorkerTrait for PowWorker {
             100 // default 1oo loop
         };
 
-        // prepare initial params
-        let mut bytes = self.header.to_bytes();
-        let mut nonce = bytes_to_u32(&bytes[76..76 + 4]);
+        // note: skip throwing if already canceled
+        if cancel.load(Ordering::Relaxed) {
+            return WorkerResult::NotFoundWork;
+        }
+
+        // throw disjoint nonce range to threads
         let target = bits_to_target(self.header.bits).unwrap();
+        let bytes = self.header.to_bytes();
+        for (index, thread) in self.threads.iter().enumerate() {
+            let start = self.nonces[index];
+            let (_, end) = self.nonce_range(index);
+            let count = min(count as u64, end - start);
+            thread
+                .sender
+                .send((bytes, start, count, target))
+                .expect("nonce range thread is dead?");
+        }
 
-        // hash generation loop
-        for _ in 0..count {
-            let output = (self.hash_func)(bytes.as_ref());
-            // check enough work and replace old
-            let work = U256::from(output.as_slice());
-            if work < target {
-                return WorkerResult::PoW((work, self.coinbase.clone(), bytes));
+        // collect results
+        // note: abort others when found or canceled
+        let mut mined = None;
+        let mut remain = self.threads.len();
+        while 0 < remain {
+            match self.result_receiver.recv_timeout(Duration::from_millis(10)) {
+                Ok((index, found, nonce)) => {
+                    remain -= 1;
+                    self.nonces[index] = nonce;
+                    if mined.is_none() && found.is_some() {
+                        mined = found;
+                        self.abort.store(true, Ordering::Relaxed);
+                    }
+                },
+                Err(RecvTimeoutError::Timeout) => {
+                    if cancel.load(Ordering::Relaxed) {
+                        self.abort.store(true, Ordering::Relaxed);
+                    }
+                },
+                Err(RecvTimeoutError::Disconnected) => panic!("nonce range thread is dead?"),
             }
-            // prepare next input
-            nonce = nonce.checked_add(1).unwrap_or(0);
-            bytes[76..76 + 4].clone_from_slice(&nonce.to_le_bytes());
+        }
+        let canceled = self.abort.swap(false, Ordering::Relaxed) && mined.is_none();
+        if let Some((work, bytes)) = mined {
+            return WorkerResult::PoW((work, self.coinbase.clone(), bytes));
+        }
+        if canceled {
+            // note: don't record span because the round is interrupted
+            return WorkerResult::NotFoundWork;
+        }
+
+        // change extra-nonce if nonce range run out
+        let exhausted = self
+            .nonces
+            .iter()
+            .enumerate()
+            .any(|(index, nonce)| self.nonce_range(index).1 <= *nonce);
+        if exhausted {
+            self.set_extra_nonce(self.extra_nonce.wrapping_add(1));
         }
 
         // recode span info
-        let real_span = now.elapsed().as_secs_f64();
-        let virtual_span = real_span * 255.0 / self.power_limit;
-        self.span.push_back((count, virtual_span));
+        // note: the span include sleep time by power_limit
+        self.span.push_back((count, now.elapsed().as_secs_f64()));
 
         // limit span queue size
         if 100 < self.span.len() {
@@ -204,7 +390,8 @@ impl WorkerTrait for PowWorker {
         self.header.previous_hash = new_block.header.hash();
         self.header.bits = new_bits;
         self.coinbase.outputs = vec![TxOutput(addr, 0, block_reward + txs_reward)];
-        self.update_merkleroot(unconfirmed);
+        self.txs = unconfirmed.txs.clone();
+        self.set_extra_nonce(0);
     }
 
     fn update_time_and_deadline(&mut self, time: u32, deadline: u32, unconfirmed: &UnconfirmedTxs) {
@@ -214,18 +401,20 @@ impl WorkerTrait for PowWorker {
         self.header.time = time;
         self.coinbase.time = time;
         self.coinbase.deadline = deadline;
-        self.update_merkleroot(unconfirmed);
+        self.txs = unconfirmed.txs.clone();
+        self.set_extra_nonce(0);
     }
 
     fn get_hashrate(&self) -> Option<usize> {
+        // combined hashrate of all threads
         if 5 < self.span.len() {
-            let mut sum = 0;
-            let mut len = 0;
-            for (count, _time) in self.span.iter() {
-                sum += *count;
-                len += 1;
+            let mut count = 0;
+            let mut time = 0.0;
+            for (_count, _time) in self.span.iter() {
+                count += *_count;
+                time += *_time;
             }
-            let hashrate = sum as f64 / len as f64;
+            let hashrate = (count * self.nonces.len()) as f64 / time;
             Some(hashrate as usize)
         } else {
             None // unknown hashrate
@@ -235,16 +424,21 @@ impl WorkerTrait for PowWorker {
     fn get_info(&self) -> String {
         let hashrate = self.get_hashrate();
         let power = self.power_limit / 255.0 * 100.0;
+        let threads = self.nonces.len();
         if let Some(rate) = hashrate {
-            format!("<PoW {:?} {}hash/s {:.2}%>", self.flag, rate, power)
+            format!("<PoW {:?} {}hash/s {:.2}%x{}>", self.flag, rate, power, threads)
         } else {
-            format!("<PoW {:?} ... {:.2}%>", self.flag, power)
+            format!("<PoW {:?} ... {:.2}%x{}>", self.flag, power, threads)
         }
     }
 
     fn get_flag(&self) -> &BlockFlag {
         &self.flag
     }
+
+    fn get_template(&self) -> Option<(BlockHeader, TxBody)> {
+        Some((self.header.clone(), self.coinbase.clone()))
+    }
 }
 
 /// proof of stake
@@ -271,7 +465,7 @@ impl PosWorker {
 }
 
 impl WorkerTrait for PosWorker {
-    fn generate(&mut self) -> WorkerResult {
+    fn generate(&mut self, cancel: &AtomicBool) -> WorkerResult {
         let now = Instant::now();
         let target = bits_to_target(self.bits).unwrap();
         let mut total = 0;
@@ -290,8 +484,8 @@ impl WorkerTrait for PosWorker {
                 };
                 return WorkerResult::PoS((work, coinbase.clone(), header, *amount));
             }
-            // skip if over a second
-            if index % 10 == 0 && 1.0 <= now.elapsed().as_secs_f32() {
+            // skip if over a second or canceled
+            if index % 10 == 0 && (1.0 <= now.elapsed().as_secs_f32() || cancel.load(Ordering::Relaxed)) {
                 break;
             }
         }
@@ -401,6 +595,157 @@ impl WorkerTrait for PosWorker {
     }
 }
 
+/// proof of fund-lock stake
+pub struct FlkPosWorker {
+    flag: BlockFlag,
+    /// Vec<(coinbase, lock amount)>
+    coinbase: Vec<(TxBody, u64)>,
+    previous_hash: U256,
+    bits: u32,
+    /// newest hashrate info (hash, sec)
+    generate_info: Option<(usize, f64)>,
+}
+
+impl FlkPosWorker {
+    pub fn new() -> WorkerType {
+        Box::new(FlkPosWorker {
+            flag: BlockFlag::FlkPos,
+            coinbase: vec![],
+            previous_hash: U256::zero(),
+            bits: 0,
+            generate_info: None,
+        })
+    }
+}
+
+impl WorkerTrait for FlkPosWorker {
+    fn generate(&mut self, cancel: &AtomicBool) -> WorkerResult {
+        let now = Instant::now();
+        let target = bits_to_target(self.bits).unwrap();
+        let mut total = 0;
+
+        for (index, (coinbase, amount)) in self.coinbase.iter().enumerate() {
+            total += 1;
+            let work = get_pos_hash(coinbase, *amount, &self.previous_hash);
+            if work < target {
+                let header = BlockHeader {
+                    version: 0, // always zero
+                    previous_hash: self.previous_hash,
+                    merkleroot: U256::zero(), // update after
+                    time: coinbase.time,
+                    bits: self.bits,
+                    nonce: 0, // always zero
+                };
+                return WorkerResult::PoS((work, coinbase.clone(), header, *amount));
+            }
+            // skip if over a second or canceled
+            if index % 10 == 0 && (1.0 <= now.elapsed().as_secs_f32() || cancel.load(Ordering::Relaxed)) {
+                break;
+            }
+        }
+
+        // update hashrate
+        self.generate_info.replace((total, now.elapsed().as_secs_f64()));
+
+        // not found work
+        WorkerResult::NotFoundWork
+    }
+
+    fn update_by_new_block(
+        &mut self,
+        chain: &mut Chain,
+        new_block: &Block,
+        new_bits: u32,
+        block_reward: u64,
+        _txs_reward: u64,
+        _txs: &UnconfirmedTxs,
+    ) {
+        // get list lock unspent for staking limited by some condition
+        let mut limit = 5000usize;
+        let mut unspent = Vec::with_capacity(limit);
+        for (input, output) in chain.get_account_lock_iter() {
+            // check conditions
+            if output.1 != 0 {
+                continue; // skip: coinId is 0
+            }
+            if output.2 < 1_0000_0000 {
+                continue; // skip: amount is more than 1.0
+            }
+
+            // check height
+            match chain.get_tx_height(&input.0) {
+                Ok(Some(height)) => {
+                    if new_block.height + 1 <= height + LOCK_MATURE_HEIGHT {
+                        continue; // skip: not enough mature lock
+                    }
+                },
+                _ => continue, // skip: unconfirmed or not found tx
+            }
+
+            // find good unspent
+            // note: relock to the same lock address with reward
+            unspent.push((
+                TxBody {
+                    version: 0,
+                    txtype: TxType::PoS,
+                    time: 0,
+                    deadline: 0,
+                    inputs: vec![input],
+                    outputs: vec![TxOutput(output.0, output.1, output.2 + block_reward)],
+                    gas_price: 0,
+                    gas_amount: 0,
+                    message: TxMessage::Nothing,
+                },
+                output.2,
+            ));
+
+            // loop limit
+            match limit.checked_sub(1) {
+                Some(new_limit) => limit = new_limit,
+                None => break,
+            }
+        }
+
+        // replace old coinbase & previous
+        unspent.shrink_to_fit();
+        self.coinbase = unspent;
+        self.previous_hash = new_block.header.hash();
+        self.bits = new_bits;
+    }
+
+    fn update_time_and_deadline(&mut self, time: u32, deadline: u32, txs: &UnconfirmedTxs) {
+        assert!(txs.time <= time);
+        assert!(deadline < txs.deadline);
+        for (tx, _) in self.coinbase.iter_mut() {
+            tx.time = time;
+            tx.deadline = deadline;
+        }
+    }
+
+    fn get_hashrate(&self) -> Option<usize> {
+        match self.generate_info {
+            Some((hashrate, _)) => Some(hashrate),
+            None => None,
+        }
+    }
+
+    fn get_info(&self) -> String {
+        match self.generate_info {
+            Some((hashrate, span)) => format!(
+                "<FlkPoS {}locked {}hash/s {:.2}%load>",
+                self.coinbase.len(),
+                hashrate,
+                (span / 1.0 * 100.0) as f32
+            ),
+            None => format!("<FlkPoS {}locked ...>", self.coinbase.len()),
+        }
+    }
+
+    fn get_flag(&self) -> &BlockFlag {
+        &self.flag
+    }
+}
+
 /// proof of capacity
 pub struct PocWorker {
     flag: BlockFlag,
@@ -440,7 +785,7 @@ impl PocWorker {
 }
 
 impl WorkerTrait for PocWorker {
-    fn generate(&mut self) -> WorkerResult {
+    fn generate(&mut self, cancel: &AtomicBool) -> WorkerResult {
         let now = Instant::now();
         let mut count = 0;
 
@@ -471,7 +816,7 @@ impl WorkerTrait for PocWorker {
                 },
                 Err(_err) => {
                     count += plot.end - plot.start;
-                    if 1.0 <= now.elapsed().as_secs_f64() {
+                    if 1.0 <= now.elapsed().as_secs_f64() || cancel.load(Ordering::Relaxed) {
                         break;
                     }
                 },
@@ -530,10 +875,20 @@ impl WorkerTrait for PocWorker {
 /// future object of generating threads
 pub struct GenerateFuture<R = (WorkerResult, WorkerType)> {
     threads: Option<Vec<thread::JoinHandle<R>>>,
+    /// number of threads finished work
+    finished: Arc<AtomicUsize>,
     result: Option<Vec<R>>,
 }
 
 impl<R> GenerateFuture<R> {
+    /// check threads work finished without blocking
+    pub fn is_finished(&self) -> bool {
+        match self.threads.as_ref() {
+            Some(threads) => threads.len() <= self.finished.load(Ordering::Acquire),
+            None => true,
+        }
+    }
+
     /// wait for threads work finish
     pub fn wait(&mut self) {
         if self.result.is_some() {
@@ -556,6 +911,140 @@ impl<R> GenerateFuture<R> {
     }
 }
 
+/// message to the pooled worker thread
+enum PoolMessage {
+    /// (epoch, new_block, new_bits, block_reward, unconfirmed)
+    NewBlock(usize, Block, u32, u64, UnconfirmedTxs),
+    /// (epoch, time, deadline, unconfirmed)
+    TimeAndDeadline(usize, u32, u32, UnconfirmedTxs),
+    /// exit generating loop and return the worker
+    Shutdown,
+}
+
+/// generated result of pooled worker (epoch, flag, result)
+type PoolResult = (usize, BlockFlag, WorkerResult);
+
+/// PoW template of pooled worker (epoch, header, coinbase)
+type PoolTemplate = Option<(usize, BlockHeader, TxBody)>;
+
+/// worker living on a persistent thread
+struct PoolMember {
+    flag: BlockFlag,
+    sender: Sender<PoolMessage>,
+    thread: thread::JoinHandle<WorkerType>,
+    /// newest worker info
+    info: Arc<Mutex<String>>,
+    /// newest PoW template
+    template: Arc<Mutex<PoolTemplate>>,
+}
+
+impl PoolMember {
+    /// spawn generating loop thread
+    ///
+    /// note: generating start after received new block and pause after found work until next update
+    fn spawn(
+        mut worker: WorkerType,
+        chain: Arc<Mutex<Chain>>,
+        cancel: Arc<AtomicBool>,
+        result_sender: Sender<PoolResult>,
+    ) -> Self {
+        let flag = worker.get_flag().clone();
+        let (sender, receiver) = channel();
+        let info = Arc::new(Mutex::new(worker.get_info()));
+        let thread_flag = flag.clone();
+        let thread_info = info.clone();
+        let template = Arc::new(Mutex::new(None));
+        let thread_template = template.clone();
+        let thread = thread::spawn(move || {
+            // epoch of the newest update, none means waiting new block
+            let mut epoch: Option<usize> = None;
+            // wait for next update after found work
+            let mut found = false;
+            loop {
+                // block the thread if nothing to do
+                let working = epoch.is_some() && !found && !cancel.load(Ordering::Acquire);
+                let msg = if working {
+                    match receiver.try_recv() {
+                        Ok(msg) => Some(msg),
+                        Err(TryRecvError::Empty) => None,
+                        Err(TryRecvError::Disconnected) => break,
+                    }
+                } else {
+                    match receiver.recv() {
+                        Ok(msg) => Some(msg),
+                        Err(_) => break,
+                    }
+                };
+
+                // update by message
+                match msg {
+                    Some(PoolMessage::NewBlock(new_epoch, block, bits, reward, unconfirmed)) => {
+                        let mut chain = chain.lock().unwrap();
+                        worker.update_by_new_block(
+                            &mut chain,
+                            &block,
+                            bits,
+                            reward,
+                            unconfirmed.reward,
+                            &unconfirmed,
+                        );
+                        epoch.replace(new_epoch);
+                        *thread_template.lock().unwrap() = worker
+                            .get_template()
+                            .map(|(header, coinbase)| (new_epoch, header, coinbase));
+                        found = false;
+                        continue;
+                    },
+                    Some(PoolMessage::TimeAndDeadline(new_epoch, time, deadline, unconfirmed)) => {
+                        worker.update_time_and_deadline(time, deadline, &unconfirmed);
+                        if epoch.is_some() {
+                            epoch.replace(new_epoch);
+                            *thread_template.lock().unwrap() = worker
+                                .get_template()
+                                .map(|(header, coinbase)| (new_epoch, header, coinbase));
+                        }
+                        found = false;
+                        continue;
+                    },
+                    Some(PoolMessage::Shutdown) => break,
+                    None => (),
+                }
+
+                // generate
+                match worker.generate(&cancel) {
+                    WorkerResult::NotFoundWork => (),
+                    result => {
+                        // note: wait for next update after found work
+                        if result_sender
+                            .send((epoch.unwrap(), thread_flag.clone(), result))
+                            .is_err()
+                        {
+                            break;
+                        }
+                        found = true;
+                    },
+                }
+                *thread_info.lock().unwrap() = worker.get_info();
+            }
+            worker
+        });
+        PoolMember {
+            flag,
+            sender,
+            thread,
+            info,
+            template,
+        }
+    }
+
+    /// stop the thread and get back the worker
+    fn shutdown(self) -> Option<WorkerType> {
+        // note: return None if the thread panicked
+        self.sender.send(PoolMessage::Shutdown).ok();
+        self.thread.join().ok()
+    }
+}
+
 /// new block generator
 pub struct GenerateBuilder {
     /// generating threads.
@@ -568,30 +1057,55 @@ pub struct GenerateBuilder {
     reward: u64,
     /// mining info to generate mined block
     new_block_info: HashMap<BlockFlag, (f32,)>,
+    /// PoW templates of threads kept while the workers are thrown
+    pow_templates: HashMap<BlockFlag, (BlockHeader, TxBody)>,
     /// unconfirmed transaction's hash list
     unconfirmed: UnconfirmedTxs,
+    /// persistent generating threads (pool mode)
+    pool: Vec<PoolMember>,
+    /// chain shared with pool threads
+    pool_chain: Option<Arc<Mutex<Chain>>>,
+    /// pause pool threads until next update
+    cancel: Arc<AtomicBool>,
+    /// increment by update to ignore old result
+    epoch: usize,
+    /// pool result channel
+    result_sender: Sender<PoolResult>,
+    result_receiver: Receiver<PoolResult>,
 }
 
 impl GenerateBuilder {
     pub fn new() -> Self {
+        let (result_sender, result_receiver) = channel();
         GenerateBuilder {
             threads: vec![],
             reserve: vec![],
             height: 0,
             reward: 0,
             new_block_info: HashMap::new(),
+            pow_templates: HashMap::new(),
             unconfirmed: UnconfirmedTxs {
                 txs: vec![],
                 time: u32::MAX,
                 deadline: 0,
                 reward: 0,
             },
+            pool: vec![],
+            pool_chain: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            epoch: 0,
+            result_sender,
+            result_receiver,
         }
     }
 
     /// get thread info
     pub fn get_worker_info(&self) -> Vec<String> {
-        self.threads.iter().map(|thread| thread.get_info()).collect()
+        self.threads
+            .iter()
+            .map(|thread| thread.get_info())
+            .chain(self.pool.iter().map(|member| member.info.lock().unwrap().clone()))
+            .collect()
     }
 
     /// add new worker.
@@ -603,7 +1117,24 @@ impl GenerateBuilder {
                 return Err(format!("already mining thread exist: {}", thread.get_info()));
             }
         }
-        self.threads.push(worker);
+        for member in self.pool.iter() {
+            if &member.flag == worker.get_flag() {
+                return Err(format!("already mining pool thread exist: {:?}", member.flag));
+            }
+        }
+        match self.pool_chain.as_ref() {
+            Some(chain) => {
+                // note: start generating after next new block
+                let member = PoolMember::spawn(
+                    worker,
+                    chain.clone(),
+                    self.cancel.clone(),
+                    self.result_sender.clone(),
+                );
+                self.pool.push(member);
+            },
+            None => self.threads.push(worker),
+        }
         Ok(())
     }
 
@@ -616,6 +1147,10 @@ impl GenerateBuilder {
         self.threads
             .drain_filter(|thread| thread.get_flag() == flag)
             .for_each(drop);
+        self.pool
+            .drain_filter(|member| &member.flag == flag)
+            .for_each(|member| drop(member.shutdown()));
+        self.pow_templates.remove(flag);
     }
 
     /// update mining block's unconfirmed list
@@ -637,6 +1172,22 @@ impl GenerateBuilder {
         // update by new block info
         // note: update unconfirmed list before
         let previous_hash = new_block.header.hash();
+        self.epoch += 1;
+        for member in self.pool.iter() {
+            let flag = member.flag.clone();
+            let new_bits = diff.calc_next_bits(&previous_hash, &flag, &chain.tables)?;
+            let new_bias = diff.calc_next_bias(&previous_hash, &flag, &chain.tables)?;
+            // note: the thread lock chain after the caller released
+            let msg = PoolMessage::NewBlock(
+                self.epoch,
+                new_block.clone(),
+                new_bits,
+                new_block_reward,
+                self.unconfirmed.clone(),
+            );
+            member.sender.send(msg).ok();
+            self.new_block_info.insert(flag, (new_bias,));
+        }
         for worker in self.threads.iter_mut() {
             let flag = worker.get_flag().clone();
             let new_bits = diff.calc_next_bits(&previous_hash, &flag, &chain.tables)?;
@@ -649,6 +1200,9 @@ impl GenerateBuilder {
                 self.unconfirmed.reward,
                 &self.unconfirmed,
             );
+            if let Some(template) = worker.get_template() {
+                self.pow_templates.insert(flag.clone(), template);
+            }
             self.new_block_info.insert(flag, (new_bias,));
         }
 
@@ -656,19 +1210,118 @@ impl GenerateBuilder {
         self.height = new_block.height + 1;
         self.reward = new_block_reward;
 
+        // restart paused pool
+        self.cancel.store(false, Ordering::Release);
+
         // success
         Ok(())
     }
 
+    /// update mining time & deadline of all workers
+    ///
+    /// # panic
+    /// ```
+    /// assert!(unconfirmed.time <= time);
+    /// assert!(deadline < unconfirmed.deadline);
+    /// ```
+    pub fn update_time_and_deadline(&mut self, time: u32, deadline: u32) {
+        assert!(self.unconfirmed.time <= time);
+        assert!(deadline < self.unconfirmed.deadline);
+        self.epoch += 1;
+        for member in self.pool.iter() {
+            let msg = PoolMessage::TimeAndDeadline(self.epoch, time, deadline, self.unconfirmed.clone());
+            member.sender.send(msg).ok();
+        }
+        for worker in self.threads.iter_mut() {
+            worker.update_time_and_deadline(time, deadline, &self.unconfirmed);
+            if let Some(template) = worker.get_template() {
+                self.pow_templates.insert(worker.get_flag().clone(), template);
+            }
+        }
+    }
+
+    /// move all workers to persistent threads and keep generating in background
+    ///
+    /// note: generating start after next `update_by_new_block`
+    pub fn start_pool(&mut self, chain: Arc<Mutex<Chain>>) -> Result<(), String> {
+        if self.pool_chain.is_some() {
+            return Err("pool is already started".to_owned());
+        }
+        self.cancel.store(false, Ordering::Release);
+        let workers: Vec<WorkerType> = self.reserve.drain(..).chain(self.threads.drain(..)).collect();
+        for worker in workers {
+            let member = PoolMember::spawn(
+                worker,
+                chain.clone(),
+                self.cancel.clone(),
+                self.result_sender.clone(),
+            );
+            self.pool.push(member);
+        }
+        self.pool_chain.replace(chain);
+        Ok(())
+    }
+
+    /// pause pool threads until next update
+    ///
+    /// note: a running generation round finish before pause
+    pub fn cancel_pool(&mut self) {
+        self.cancel.store(true, Ordering::Release);
+    }
+
+    /// shutdown pool threads and get back workers
+    ///
+    /// note: don't lock the chain while shutdown because the thread may wait for it
+    pub fn stop_pool(&mut self) {
+        self.cancel.store(true, Ordering::Release);
+        for member in self.pool.drain(..) {
+            if let Some(worker) = member.shutdown() {
+                self.threads.push(worker);
+            }
+        }
+        self.pool_chain = None;
+        // drop all results
+        while self.result_receiver.try_recv().is_ok() {}
+    }
+
+    /// pool threads are working
+    pub fn is_pool_started(&self) -> bool {
+        self.pool_chain.is_some()
+    }
+
+    /// get new block generated by pool if found
+    pub fn pool_result(&mut self, chain: &Chain) -> Option<(Block, Vec<TxVerifiable>)> {
+        while let Ok((epoch, flag, result)) = self.result_receiver.try_recv() {
+            // note: ignore result generated before the newest update
+            if epoch != self.epoch {
+                continue;
+            }
+            if let Some(mined) = self.result_to_block(chain, &flag, result) {
+                // note: pause others until next new block
+                self.cancel.store(true, Ordering::Release);
+                return Some(mined);
+            }
+        }
+        None
+    }
+
     /// throw worker to other threads and return future
     pub fn throw_task(&mut self) -> GenerateFuture {
         let mut threads = vec![];
+        let finished = Arc::new(AtomicUsize::new(0));
         while let Some(mut worker) = self.threads.pop() {
-            threads.push(thread::spawn(move || (worker.generate(), worker)))
+            let finished = finished.clone();
+            threads.push(thread::spawn(move || {
+                // note: thrown task is not canceled by the pool flag
+                let result = worker.generate(&AtomicBool::new(false));
+                finished.fetch_add(1, Ordering::Release);
+                (result, worker)
+            }))
         }
         // note: get worker info in advance because thread is empty
         GenerateFuture {
             threads: Some(threads),
+            finished,
             result: None,
         }
     }
@@ -685,112 +1338,7 @@ impl GenerateBuilder {
             // note: ignore if already mined block found
             if mined.is_none() {
                 let flag = worker.get_flag().clone();
-
-                match result {
-                    WorkerResult::PoW((work_hash, coinbase, bytes)) => {
-                        // generate block
-                        let (bias,) = self.new_block_info.get(&flag).cloned().unwrap();
-                        let header = BlockHeader::from_bytes(bytes.as_ref());
-                        let txs_len = self.unconfirmed.txs.len() + 1;
-                        let mut txs_hash = Vec::with_capacity(txs_len);
-                        let coinbase_hash = U256::from(coinbase.hash().as_slice());
-                        txs_hash.push(coinbase_hash.clone());
-                        txs_hash.extend_from_slice(&self.unconfirmed.txs);
-                        assert_eq!(header.merkleroot, calc_merkleroot_hash(txs_hash.clone()));
-                        let block = Block::new(work_hash, self.height, flag, bias, header, txs_hash);
-
-                        // don't need to generate signature
-                        // note: PoW coinbase don't have signature and input
-
-                        // generate txs
-                        let coinbase = TxVerifiable {
-                            hash: coinbase_hash,
-                            body: coinbase,
-                            signature: vec![],
-                            inputs_cache: vec![],
-                        };
-                        let txs = self.unconfirmed.get_mining_block_txs(coinbase, &chain.tables);
-
-                        // success
-                        mined = Some((block, txs));
-                    },
-                    WorkerResult::PoS((work_hash, coinbase, mut header, amount)) => {
-                        // generate block
-                        let (bias,) = self.new_block_info.get(&flag).cloned().unwrap();
-                        let txs_len = self.unconfirmed.txs.len() + 1;
-                        let mut txs_hash = Vec::with_capacity(txs_len);
-                        let coinbase_hash = U256::from(coinbase.hash().as_slice());
-                        txs_hash.push(coinbase_hash.clone());
-                        txs_hash.extend_from_slice(&self.unconfirmed.txs);
-                        header.merkleroot = calc_merkleroot_hash(txs_hash.clone());
-                        let block = Block::new(work_hash, self.height, flag, bias, header, txs_hash);
-
-                        // generate signature
-                        // note: staking signature is header's not tx's
-                        let mut output_of_input = coinbase.outputs[0].clone();
-                        output_of_input.2 = amount;
-                        let signature = chain
-                            .account
-                            .get_single_sign_by_addr(&output_of_input.0, block.header.to_bytes().as_ref())
-                            .expect("generate signature for PoS mining");
-
-                        // generate txs
-                        let coinbase = TxVerifiable {
-                            hash: coinbase_hash,
-                            body: coinbase,
-                            signature: vec![signature],
-                            inputs_cache: vec![output_of_input],
-                        };
-                        let txs = self.unconfirmed.get_mining_block_txs(coinbase, &chain.tables);
-
-                        // success
-                        mined = Some((block, txs));
-                    },
-                    WorkerResult::PoC((work_hash, mut header, plot)) => {
-                        // generate coinbase
-                        let coinbase = TxBody {
-                            version: 0,
-                            txtype: TxType::PoS,
-                            time: header.time,
-                            deadline: header.time + 10800,
-                            inputs: vec![],
-                            outputs: vec![TxOutput(plot.addr, 0, self.reward)],
-                            gas_price: 0,
-                            gas_amount: 0,
-                            message: TxMessage::Nothing,
-                        };
-
-                        // generate block
-                        let (bias,) = self.new_block_info.get(&flag).cloned().unwrap();
-                        let txs_len = self.unconfirmed.txs.len() + 1;
-                        let mut txs_hash = Vec::with_capacity(txs_len);
-                        let coinbase_hash = U256::from(coinbase.hash().as_slice());
-                        txs_hash.push(coinbase_hash.clone());
-                        txs_hash.extend_from_slice(&self.unconfirmed.txs);
-                        header.merkleroot = calc_merkleroot_hash(txs_hash.clone());
-                        let block = Block::new(work_hash, self.height, flag, bias, header, txs_hash);
-
-                        // generate signature
-                        // note: staking signature is header's not tx's
-                        let signature = chain
-                            .account
-                            .get_single_sign_by_addr(&plot.addr, block.header.to_bytes().as_ref())
-                            .expect("generate signature for PoC mining");
-
-                        // generate txs
-                        let coinbase = TxVerifiable {
-                            hash: coinbase_hash,
-                            body: coinbase,
-                            signature: vec![signature],
-                            inputs_cache: vec![],
-                        };
-                        let txs = self.unconfirmed.get_mining_block_txs(coinbase, &chain.tables);
-
-                        // success
-                        mined = Some((block, txs));
-                    },
-                    WorkerResult::NotFoundWork => (),
-                }
+                mined = self.result_to_block(chain, &flag, result);
             }
 
             // return worker
@@ -800,4 +1348,448 @@ impl GenerateBuilder {
         // success
         mined
     }
+
+    /// get PoW template (header, coinbase, unconfirmed txs) of the flag's worker
+    ///
+    /// note: pooled worker's template is None until the thread received the newest update
+    pub fn get_pow_template(&self, flag: &BlockFlag) -> Option<(BlockHeader, TxBody, Vec<U256>)> {
+        let (header, coinbase) = match self.pool.iter().find(|member| &member.flag == flag) {
+            Some(member) => match member.template.lock().unwrap().as_ref() {
+                Some((epoch, header, coinbase)) if *epoch == self.epoch => (header.clone(), coinbase.clone()),
+                _ => return None,
+            },
+            None => self.pow_templates.get(flag).cloned()?,
+        };
+        Some((header, coinbase, self.unconfirmed.txs.clone()))
+    }
+
+    /// generate mined block from external miner's result (ex. stratum)
+    ///
+    /// note: return None if the result is based on old unconfirmed txs
+    pub fn external_result(
+        &self,
+        chain: &Chain,
+        flag: &BlockFlag,
+        result: WorkerResult,
+    ) -> Option<(Block, Vec<TxVerifiable>)> {
+        if !self.new_block_info.contains_key(flag) {
+            return None;
+        }
+        if let WorkerResult::PoW((_work, coinbase, bytes)) = &result {
+            let header = BlockHeader::from_bytes(bytes.as_ref());
+            let mut txs_hash = Vec::with_capacity(self.unconfirmed.txs.len() + 1);
+            txs_hash.push(U256::from(coinbase.hash().as_slice()));
+            txs_hash.extend_from_slice(&self.unconfirmed.txs);
+            if header.merkleroot != calc_merkleroot_hash(txs_hash) {
+                return None;
+            }
+        }
+        self.result_to_block(chain, flag, result)
+    }
+
+    /// generate mined block from worker's result
+    fn result_to_block(
+        &self,
+        chain: &Chain,
+        flag: &BlockFlag,
+        result: WorkerResult,
+    ) -> Option<(Block, Vec<TxVerifiable>)> {
+        let flag = flag.clone();
+        match result {
+            WorkerResult::PoW((work_hash, coinbase, bytes)) => {
+                // generate block
+                let (bias,) = self.new_block_info.get(&flag).cloned().unwrap();
+                let header = BlockHeader::from_bytes(bytes.as_ref());
+                let txs_len = self.unconfirmed.txs.len() + 1;
+                let mut txs_hash = Vec::with_capacity(txs_len);
+                let coinbase_hash = U256::from(coinbase.hash().as_slice());
+                txs_hash.push(coinbase_hash.clone());
+                txs_hash.extend_from_slice(&self.unconfirmed.txs);
+                assert_eq!(header.merkleroot, calc_merkleroot_hash(txs_hash.clone()));
+                let block = Block::new(work_hash, self.height, flag, bias, header, txs_hash);
+
+                // don't need to generate signature
+                // note: PoW coinbase don't have signature and input
+
+                // generate txs
+                let coinbase = TxVerifiable {
+                    hash: coinbase_hash,
+                    body: coinbase,
+                    signature: vec![],
+                    inputs_cache: vec![],
+                };
+                let txs = self.unconfirmed.get_mining_block_txs(coinbase, &chain.tables);
+
+                // success
+                Some((block, txs))
+            },
+            WorkerResult::PoS((work_hash, coinbase, mut header, amount)) => {
+                // generate block
+                let (bias,) = self.new_block_info.get(&flag).cloned().unwrap();
+                let txs_len = self.unconfirmed.txs.len() + 1;
+                let mut txs_hash = Vec::with_capacity(txs_len);
+                let coinbase_hash = U256::from(coinbase.hash().as_slice());
+                txs_hash.push(coinbase_hash.clone());
+                txs_hash.extend_from_slice(&self.unconfirmed.txs);
+                header.merkleroot = calc_merkleroot_hash(txs_hash.clone());
+                let block = Block::new(work_hash, self.height, flag, bias, header, txs_hash);
+
+                // generate signature
+                // note: staking signature is header's not tx's
+                // note: FlkPos's input is a lock address signed by the owner
+                let mut output_of_input = coinbase.outputs[0].clone();
+                output_of_input.2 = amount;
+                let signature = chain
+                    .account
+                    .get_single_sign_by_addr(&output_of_input.0, block.header.to_bytes().as_ref())
+                    .expect("generate signature for PoS mining");
+
+                // generate txs
+                let coinbase = TxVerifiable {
+                    hash: coinbase_hash,
+                    body: coinbase,
+                    signature: vec![signature],
+                    inputs_cache: vec![output_of_input],
+                };
+                let txs = self.unconfirmed.get_mining_block_txs(coinbase, &chain.tables);
+
+                // success
+                Some((block, txs))
+            },
+            WorkerResult::PoC((work_hash, mut header, plot)) => {
+                // generate coinbase
+                let coinbase = TxBody {
+                    version: 0,
+                    txtype: TxType::PoS,
+                    time: header.time,
+                    deadline: header.time + 10800,
+                    inputs: vec![],
+                    outputs: vec![TxOutput(plot.addr, 0, self.reward)],
+                    gas_price: 0,
+                    gas_amount: 0,
+                    message: TxMessage::Nothing,
+                };
+
+                // generate block
+                let (bias,) = self.new_block_info.get(&flag).cloned().unwrap();
+                let txs_len = self.unconfirmed.txs.len() + 1;
+                let mut txs_hash = Vec::with_capacity(txs_len);
+                let coinbase_hash = U256::from(coinbase.hash().as_slice());
+                txs_hash.push(coinbase_hash.clone());
+                txs_hash.extend_from_slice(&self.unconfirmed.txs);
+                header.merkleroot = calc_merkleroot_hash(txs_hash.clone());
+                let block = Block::new(work_hash, self.height, flag, bias, header, txs_hash);
+
+                // generate signature
+                // note: staking signature is header's not tx's
+                let signature = chain
+                    .account
+                    .get_single_sign_by_addr(&plot.addr, block.header.to_bytes().as_ref())
+                    .expect("generate signature for PoC mining");
+
+                // generate txs
+                let coinbase = TxVerifiable {
+                    hash: coinbase_hash,
+                    body: coinbase,
+                    signature: vec![signature],
+                    inputs_cache: vec![],
+                };
+                let txs = self.unconfirmed.get_mining_block_txs(coinbase, &chain.tables);
+
+                // success
+                Some((block, txs))
+            },
+            WorkerResult::NotFoundWork => None,
+        }
+    }
+}
+
+impl Drop for GenerateBuilder {
+    fn drop(&mut self) {
+        // note: the owner must release chain lock before drop because the thread may wait for it
+        self.cancel.store(true, Ordering::Release);
+        for member in self.pool.iter() {
+            member.sender.send(PoolMessage::Shutdown).ok();
+        }
+        for member in self.pool.drain(..) {
+            member.thread.join().ok();
+        }
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// worker return a PoW result each time a token is given, without hashing
+    struct StubWorker {
+        flag: BlockFlag,
+        tokens: Receiver<()>,
+        /// count of `generate` calls
+        entered: Arc<AtomicUsize>,
+        previous_hash: U256,
+        bits: u32,
+        time: u32,
+        nonce: u32,
+    }
+
+    impl StubWorker {
+        fn with_tokens() -> (WorkerType, Sender<()>, Arc<AtomicUsize>) {
+            let (sender, tokens) = channel();
+            let entered = Arc::new(AtomicUsize::new(0));
+            let worker = Box::new(StubWorker {
+                flag: BlockFlag::X11Pow,
+                tokens,
+                entered: entered.clone(),
+                previous_hash: U256::zero(),
+                bits: 0,
+                time: 0,
+                nonce: 0,
+            });
+            (worker, sender, entered)
+        }
+    }
+
+    impl WorkerTrait for StubWorker {
+        fn generate(&mut self, cancel: &AtomicBool) -> WorkerResult {
+            self.entered.fetch_add(1, Ordering::AcqRel);
+            loop {
+                if cancel.load(Ordering::Acquire) {
+                    return WorkerResult::NotFoundWork;
+                }
+                match self.tokens.recv_timeout(Duration::from_millis(10)) {
+                    Ok(()) => break,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return WorkerResult::NotFoundWork,
+                }
+            }
+            self.nonce += 1;
+            let coinbase = TxBody::new(
+                0,
+                TxType::PoW,
+                self.time,
+                self.time + 10800,
+                0,
+                0,
+                TxMessage::Nothing,
+            );
+            let header = BlockHeader {
+                version: 0,
+                previous_hash: self.previous_hash,
+                merkleroot: calc_merkleroot_hash(vec![U256::from(coinbase.hash().as_slice())]),
+                time: self.time,
+                bits: self.bits,
+                nonce: self.nonce,
+            };
+            WorkerResult::PoW((U256::zero(), coinbase, header.to_bytes()))
+        }
+
+        fn update_by_new_block(
+            &mut self,
+            _chain: &mut Chain,
+            new_block: &Block,
+            new_bits: u32,
+            _block_reward: u64,
+            _txs_reward: u64,
+            _txs: &UnconfirmedTxs,
+        ) {
+            self.previous_hash = new_block.header.hash();
+            self.bits = new_bits;
+        }
+
+        fn update_time_and_deadline(&mut self, time: u32, _deadline: u32, _txs: &UnconfirmedTxs) {
+            self.time = time;
+        }
+
+        fn get_hashrate(&self) -> Option<usize> {
+            None
+        }
+
+        fn get_info(&self) -> String {
+            format!("stub nonce={}", self.nonce)
+        }
+
+        fn get_flag(&self) -> &BlockFlag {
+            &self.flag
+        }
+    }
+
+    fn chain_fixture(dir: &Path) -> Arc<Mutex<Chain>> {
+        let sk = Some(b"1qwq53lmi8rapvcciqmuiorxdie5irwmw1dccbegkze9vjdpy7mz6nsd6j991a6b".to_vec());
+        Arc::new(Mutex::new(Chain::new(dir, &sk, 100, false, false).unwrap()))
+    }
+
+    /// pool threads must be stopped before
+    fn destroy(chain: Arc<Mutex<Chain>>) {
+        let chain = Arc::try_unwrap(chain)
+            .ok()
+            .expect("pool threads still have chain");
+        chain.into_inner().unwrap().tables.close_and_destroy();
+    }
+
+    fn new_block(height: u32) -> Block {
+        let header = BlockHeader {
+            version: 0,
+            previous_hash: U256::from(height),
+            merkleroot: U256::zero(),
+            time: 100,
+            bits: 0,
+            nonce: 0,
+        };
+        Block::new(U256::zero(), height, BlockFlag::X11Pow, 1.0, header, vec![])
+    }
+
+    fn unconfirmed() -> UnconfirmedTxs {
+        UnconfirmedTxs {
+            txs: vec![],
+            time: 0,
+            deadline: u32::MAX,
+            reward: 0,
+        }
+    }
+
+    fn wait_until<F: Fn() -> bool>(f: F) {
+        let now = Instant::now();
+        while !f() {
+            assert!(now.elapsed() < Duration::from_secs(10), "timeout");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn wait_result(gene: &mut GenerateBuilder, chain: &Arc<Mutex<Chain>>) -> (Block, Vec<TxVerifiable>) {
+        let now = Instant::now();
+        loop {
+            if let Some(mined) = gene.pool_result(&chain.lock().unwrap()) {
+                return mined;
+            }
+            assert!(now.elapsed() < Duration::from_secs(10), "timeout");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn pool_epoch() {
+        let tmp = tempdir().unwrap();
+        let chain = chain_fixture(tmp.path().join("database").as_ref());
+        let mut diff = DifficultyBuilder::new(vec![(BlockFlag::X11Pow, 60, 30, 60)]);
+        let (worker, tokens, entered) = StubWorker::with_tokens();
+        let mut gene = GenerateBuilder::new();
+        gene.push_worker(worker).unwrap();
+        gene.start_pool(chain.clone()).unwrap();
+        assert!(gene.start_pool(chain.clone()).is_err());
+        gene.update_unconfirmed_list(unconfirmed());
+        let block = new_block(5);
+        gene.update_by_new_block(&mut chain.lock().unwrap(), &block, 100, &mut diff)
+            .unwrap();
+
+        // found before time update is old epoch's result
+        wait_until(|| entered.load(Ordering::Acquire) == 1);
+        gene.update_time_and_deadline(200, 300);
+        tokens.send(()).unwrap();
+        wait_until(|| entered.load(Ordering::Acquire) == 2);
+        assert!(gene.pool_result(&chain.lock().unwrap()).is_none());
+
+        // newest epoch's result
+        tokens.send(()).unwrap();
+        let (mined, txs) = wait_result(&mut gene, &chain);
+        assert_eq!(mined.header.previous_hash, block.header.hash());
+        assert_eq!(mined.header.nonce, 2);
+        assert_eq!(mined.header.time, 200);
+        assert_eq!(mined.height, 6);
+        assert_eq!(txs.len(), 1);
+
+        gene.stop_pool();
+        destroy(chain);
+    }
+
+    #[test]
+    fn pool_cancel_restart() {
+        let tmp = tempdir().unwrap();
+        let chain = chain_fixture(tmp.path().join("database").as_ref());
+        let mut diff = DifficultyBuilder::new(vec![(BlockFlag::X11Pow, 60, 30, 60)]);
+        let (worker, tokens, entered) = StubWorker::with_tokens();
+        let mut gene = GenerateBuilder::new();
+        gene.start_pool(chain.clone()).unwrap();
+        gene.push_worker(worker).unwrap();
+        gene.update_unconfirmed_list(unconfirmed());
+        gene.update_by_new_block(&mut chain.lock().unwrap(), &new_block(5), 100, &mut diff)
+            .unwrap();
+        wait_until(|| entered.load(Ordering::Acquire) == 1);
+
+        // paused worker don't generate until next update
+        gene.cancel_pool();
+        thread::sleep(Duration::from_millis(100));
+        tokens.send(()).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(entered.load(Ordering::Acquire), 1);
+        assert!(gene.pool_result(&chain.lock().unwrap()).is_none());
+
+        // restart by new block
+        let block = new_block(6);
+        gene.update_by_new_block(&mut chain.lock().unwrap(), &block, 100, &mut diff)
+            .unwrap();
+        let (mined, _txs) = wait_result(&mut gene, &chain);
+        assert_eq!(mined.header.previous_hash, block.header.hash());
+        assert_eq!(mined.height, 7);
+        assert_eq!(entered.load(Ordering::Acquire), 2);
+
+        // others are paused after found
+        assert!(gene.cancel.load(Ordering::Acquire));
+
+        gene.stop_pool();
+        destroy(chain);
+    }
+
+    #[test]
+    fn pool_shutdown() {
+        let tmp = tempdir().unwrap();
+        let chain = chain_fixture(tmp.path().join("database").as_ref());
+        let mut diff = DifficultyBuilder::new(vec![(BlockFlag::X11Pow, 60, 30, 60)]);
+        let (worker, tokens, _entered) = StubWorker::with_tokens();
+        let mut gene = GenerateBuilder::new();
+        gene.push_worker(worker).unwrap();
+        gene.start_pool(chain.clone()).unwrap();
+        gene.update_unconfirmed_list(unconfirmed());
+        gene.update_by_new_block(&mut chain.lock().unwrap(), &new_block(5), 100, &mut diff)
+            .unwrap();
+
+        // stop with a result not received
+        tokens.send(()).unwrap();
+        wait_until(|| gene.get_worker_info() == vec!["stub nonce=1".to_owned()]);
+        gene.stop_pool();
+        assert!(!gene.is_pool_started());
+        assert_eq!(gene.pool.len(), 0);
+        assert_eq!(gene.threads.len(), 1);
+        assert!(gene.pool_result(&chain.lock().unwrap()).is_none());
+
+        // the worker come back to the pool
+        gene.start_pool(chain.clone()).unwrap();
+        assert_eq!(gene.threads.len(), 0);
+        gene.update_by_new_block(&mut chain.lock().unwrap(), &new_block(6), 100, &mut diff)
+            .unwrap();
+        tokens.send(()).unwrap();
+        let (mined, _txs) = wait_result(&mut gene, &chain);
+        assert_eq!(mined.header.nonce, 2);
+
+        // drop also join the threads
+        drop(gene);
+        destroy(chain);
+    }
+
+    #[test]
+    fn nonce_ranges() {
+        for threads in [1usize, 3, 7, 256].iter() {
+            let mut next = 0;
+            for index in 0..*threads {
+                let (start, end) = nonce_range(index, *threads);
+                assert_eq!(start, next);
+                assert!(start < end);
+                next = end;
+            }
+            // note: cover all nonces without leftover
+            assert_eq!(next, u32::MAX as u64 + 1);
+        }
+    }
 }
@@ -2,10 +2,14 @@ mod block;
 mod difficulty;
 mod generate;
 mod reward;
+mod stratum;
+mod template;
 mod utils;
 
 pub use block::*;
 pub use difficulty::*;
 pub use generate::*;
 pub use reward::*;
+pub use stratum::*;
+pub use template::*;
 pub use utils::*;
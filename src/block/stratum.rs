@@ -0,0 +1,684 @@
+use crate::block::{bits_to_target, get_work_hash, BlockFlag, BlockHeader, WorkerResult};
+use crate::tx::{TxBody, TxMessage};
+use crate::utils::*;
+use bigint::U256;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// max jobs kept for a session, drop oldest over it
+const MAX_SESSION_JOBS: usize = 16;
+
+/// max jobs kept for all sessions, drop oldest over it
+const MAX_JOBS: usize = 1024;
+
+/// max winning shares not taken yet, drop oldest over it
+const MAX_FOUND: usize = 16;
+
+/// max lines waiting for a client, disconnect slow client over it
+const MAX_SEND_QUEUE: usize = 64;
+
+/// max connections, refuse new client over it
+const MAX_CLIENTS: usize = 128;
+
+/// max bytes of a request line, disconnect the client over it
+const MAX_LINE_LEN: u64 = 4096;
+
+/// give up writing to a stuck client
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// disconnect a client silent too long
+const READ_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// extra-nonce range size given to a job
+const EXTRA_NONCE_SIZE: u64 = 1 << 16;
+
+/// block template of PoW mining (flag, header, coinbase, unconfirmed txs)
+struct StratumTemplate {
+    flag: BlockFlag,
+    header: BlockHeader,
+    coinbase: TxBody,
+    /// merkle branch of coinbase
+    branch: Arc<Vec<U256>>,
+}
+
+/// mining job given to a client
+struct StratumJob {
+    session: u32,
+    flag: BlockFlag,
+    header: BlockHeader,
+    coinbase: TxBody,
+    branch: Arc<Vec<U256>>,
+    /// extra-nonce range [start, start + EXTRA_NONCE_SIZE)
+    extra_nonce: u64,
+    /// submitted (extra-nonce, nonce) to reject duplicate share
+    shares: HashSet<(u64, u32)>,
+}
+
+/// connected client
+struct StratumClient {
+    session: u32,
+    /// shutdown only, write through `sender`
+    stream: TcpStream,
+    subscribed: bool,
+    sender: SyncSender<String>,
+}
+
+struct StratumState {
+    template: Option<StratumTemplate>,
+    jobs: HashMap<u32, StratumJob>,
+    /// job ids from oldest
+    job_order: VecDeque<u32>,
+    next_job_id: u32,
+    /// start of coinbase's extra-nonce range, unique each job
+    next_extra_nonce: u64,
+    share_target: U256,
+    clients: Vec<StratumClient>,
+    /// client threads including not registered to `clients` yet
+    connections: usize,
+    next_session: u32,
+    /// winning shares not taken yet
+    found: VecDeque<(BlockFlag, WorkerResult)>,
+    /// accepted share count
+    accepted: usize,
+}
+
+impl StratumState {
+    /// generate new job with unique extra-nonce range
+    fn new_job(&mut self, session: u32) -> Result<Value, String> {
+        let template = self.template.as_ref().ok_or("no job template".to_owned())?;
+
+        // coinbase with extra-nonce
+        // note: 8 bytes message not to conflict with PowWorker's extra-nonce
+        let extra_nonce = self.next_extra_nonce;
+        self.next_extra_nonce = self.next_extra_nonce.wrapping_add(EXTRA_NONCE_SIZE);
+        let coinbase = with_extra_nonce(&template.coinbase, extra_nonce);
+
+        // header
+        let mut header = template.header.clone();
+        header.merkleroot = calc_merkleroot_by_branch(&coinbase, &template.branch);
+        header.nonce = 0;
+
+        // job
+        // note: miner can change extra-nonce (last 8 bytes of coinbase) in the range
+        let job_id = self.next_job_id;
+        self.next_job_id = self.next_job_id.wrapping_add(1);
+        let job = json!({
+            "job_id": job_id,
+            "flag": template.flag.to_int(),
+            "extra_nonce": extra_nonce,
+            "extra_nonce_size": EXTRA_NONCE_SIZE,
+            "coinbase": hex::encode(coinbase.to_bytes()),
+            "merkle_branch": template.branch.iter().map(u256_to_hex).collect::<Vec<_>>(),
+            "header": hex::encode(header.to_bytes().as_ref()),
+            "share_target": u256_to_hex(&self.share_target),
+        });
+        let job_data = StratumJob {
+            session,
+            flag: template.flag.clone(),
+            header,
+            coinbase,
+            branch: template.branch.clone(),
+            extra_nonce,
+            shares: HashSet::new(),
+        };
+        self.insert_job(job_id, job_data);
+        Ok(job)
+    }
+
+    /// insert job and drop oldest over the limits
+    fn insert_job(&mut self, job_id: u32, job: StratumJob) {
+        let session = job.session;
+        let session_jobs = self
+            .job_order
+            .iter()
+            .filter(|id| self.jobs.get(*id).map(|job| job.session) == Some(session))
+            .count();
+        if MAX_SESSION_JOBS <= session_jobs {
+            let position = self
+                .job_order
+                .iter()
+                .position(|id| self.jobs.get(id).map(|job| job.session) == Some(session))
+                .unwrap();
+            let oldest = self.job_order.remove(position).unwrap();
+            self.jobs.remove(&oldest);
+        }
+        while MAX_JOBS <= self.job_order.len() {
+            let oldest = self.job_order.pop_front().unwrap();
+            self.jobs.remove(&oldest);
+        }
+        self.job_order.push_back(job_id);
+        self.jobs.insert(job_id, job);
+    }
+
+    /// drop all jobs
+    fn clear_jobs(&mut self) {
+        self.jobs.clear();
+        self.job_order.clear();
+    }
+
+    /// check submitted share and return true if the share is a new block
+    fn submit(&mut self, job_id: u32, extra_nonce: Option<u64>, nonce: u32) -> Result<bool, String> {
+        let job = self
+            .jobs
+            .get_mut(&job_id)
+            .ok_or("unknown or stale job".to_owned())?;
+        let extra_nonce = extra_nonce.unwrap_or(job.extra_nonce);
+        if extra_nonce.wrapping_sub(job.extra_nonce) >= EXTRA_NONCE_SIZE {
+            return Err("extra-nonce is out of range".to_owned());
+        }
+        if !job.shares.insert((extra_nonce, nonce)) {
+            return Err("duplicate share".to_owned());
+        }
+
+        // check work
+        let coinbase = with_extra_nonce(&job.coinbase, extra_nonce);
+        let mut header = job.header.clone();
+        header.merkleroot = calc_merkleroot_by_branch(&coinbase, &job.branch);
+        header.nonce = nonce;
+        let work = get_work_hash(&job.flag, &header, None, None)?;
+        let target = bits_to_target(header.bits)?;
+        if self.share_target.max(target) <= work {
+            return Err("low difficulty share".to_owned());
+        }
+        self.accepted += 1;
+
+        // winning share
+        if work < target {
+            let result = WorkerResult::PoW((work, coinbase, header.to_bytes()));
+            if MAX_FOUND <= self.found.len() {
+                self.found.pop_front();
+            }
+            self.found.push_back((job.flag.clone(), result));
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// process a request and return result
+    fn process(&mut self, session: u32, method: &str, params: &Value) -> Result<Value, String> {
+        match method {
+            "mining.subscribe" => {
+                for client in self.clients.iter_mut() {
+                    if client.session == session {
+                        client.subscribed = true;
+                    }
+                }
+                Ok(json!({ "session": session }))
+            },
+            "mining.get_job" => self.new_job(session),
+            "mining.submit" => {
+                // params: [job_id, nonce] or [job_id, extra_nonce, nonce]
+                let job_id = params.get(0).and_then(Value::as_u64);
+                let (extra_nonce, nonce) = match params.as_array().map(Vec::len) {
+                    Some(3) => (params.get(1).and_then(Value::as_u64), params.get(2)),
+                    _ => (None, params.get(1)),
+                };
+                let nonce = nonce.and_then(Value::as_u64);
+                match (job_id, nonce) {
+                    (Some(job_id), Some(nonce)) if job_id <= 0xffffffff && nonce <= 0xffffffff => {
+                        let block = self.submit(job_id as u32, extra_nonce, nonce as u32)?;
+                        Ok(json!({ "accepted": true, "block": block }))
+                    },
+                    _ => Err("params is [job_id u32, (extra_nonce u64,) nonce u32]".to_owned()),
+                }
+            },
+            method => Err(format!("unknown method {}", method)),
+        }
+    }
+
+    /// queue a line to the client, disconnect if the queue is full
+    fn send_line(&mut self, session: u32, line: String) -> bool {
+        let client = match self.clients.iter().position(|client| client.session == session) {
+            Some(index) => &self.clients[index],
+            None => return false,
+        };
+        match client.sender.try_send(line) {
+            Ok(()) => true,
+            Err(_) => {
+                client.stream.shutdown(Shutdown::Both).ok();
+                self.clients.retain(|client| client.session != session);
+                false
+            },
+        }
+    }
+
+    /// notify new job to all subscribed clients
+    ///
+    /// note: don't block because only queue lines
+    fn notify_all(&mut self) {
+        let sessions = self
+            .clients
+            .iter()
+            .filter(|client| client.subscribed)
+            .map(|client| client.session)
+            .collect::<Vec<_>>();
+        for session in sessions {
+            let line = match self.new_job(session) {
+                Ok(job) => json!({"id": Value::Null, "method": "mining.notify", "params": job}),
+                Err(_) => break,
+            };
+            self.send_line(session, line.to_string());
+        }
+    }
+}
+
+/// coinbase with 8 bytes extra-nonce message
+fn with_extra_nonce(coinbase: &TxBody, extra_nonce: u64) -> TxBody {
+    let mut coinbase = coinbase.clone();
+    coinbase.message = TxMessage::Byte(extra_nonce.to_le_bytes().to_vec());
+    coinbase
+}
+
+/// calc merkleroot from coinbase and its merkle branch
+fn calc_merkleroot_by_branch(coinbase: &TxBody, branch: &[U256]) -> U256 {
+    // note: coinbase is index 0, always left child and never rejected
+    fold_merkle_branch(&U256::from(coinbase.hash().as_slice()), branch, 0).unwrap()
+}
+
+/// JSON-over-TCP job server for external PoW miners
+///
+/// note: one request or notification per line
+pub struct StratumServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<StratumState>>,
+    closed: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl StratumServer {
+    /// bind and start accepting clients
+    pub fn start(addr: &str, share_bits: u32) -> Result<Self, String> {
+        let share_target = bits_to_target(share_bits)?;
+        let listener = TcpListener::bind(addr).map_err(|err| err.to_string())?;
+        listener.set_nonblocking(true).map_err(|err| err.to_string())?;
+        let addr = listener.local_addr().map_err(|err| err.to_string())?;
+        let state = Arc::new(Mutex::new(StratumState {
+            template: None,
+            jobs: HashMap::new(),
+            job_order: VecDeque::new(),
+            next_job_id: 0,
+            next_extra_nonce: 0,
+            share_target,
+            clients: vec![],
+            connections: 0,
+            next_session: 0,
+            found: VecDeque::new(),
+            accepted: 0,
+        }));
+        let closed = Arc::new(AtomicBool::new(false));
+
+        // accept loop
+        let thread_state = state.clone();
+        let thread_closed = closed.clone();
+        let thread = thread::spawn(move || {
+            while !thread_closed.load(Ordering::Acquire) {
+                match listener.accept() {
+                    Ok((stream, _addr)) => {
+                        // note: refuse over the limit because a client use reader and writer threads
+                        {
+                            let mut state = thread_state.lock().unwrap();
+                            if MAX_CLIENTS <= state.connections {
+                                stream.shutdown(Shutdown::Both).ok();
+                                continue;
+                            }
+                            state.connections += 1;
+                        }
+                        let state = thread_state.clone();
+                        thread::spawn(move || {
+                            handle_client(stream, state.clone());
+                            state.lock().unwrap().connections -= 1;
+                        });
+                    },
+                    Err(ref err) if err.kind() == ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(100));
+                    },
+                    Err(_) => break,
+                }
+            }
+        });
+
+        Ok(StratumServer {
+            addr,
+            state,
+            closed,
+            thread: Some(thread),
+        })
+    }
+
+    /// bound address (useful when port is 0)
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// replace job template and notify new jobs to clients
+    ///
+    /// note: jobs of old template become stale
+    pub fn update_template(&self, flag: &BlockFlag, header: BlockHeader, coinbase: TxBody, txs: Vec<U256>) {
+        let mut state = self.state.lock().unwrap();
+        // note: first hash is dummy, replaced by coinbase
+        let mut hashs = Vec::with_capacity(1 + txs.len());
+        hashs.push(U256::zero());
+        hashs.extend_from_slice(&txs);
+        state.template.replace(StratumTemplate {
+            flag: flag.clone(),
+            header,
+            coinbase,
+            branch: Arc::new(merkle_branch(hashs, 0)),
+        });
+        state.clear_jobs();
+        state.notify_all();
+    }
+
+    /// take winning shares found by clients
+    ///
+    /// note: only newest shares are kept, the caller should drain periodically
+    pub fn pop_found(&self) -> Vec<(BlockFlag, WorkerResult)> {
+        self.state.lock().unwrap().found.drain(..).collect()
+    }
+
+    pub fn get_info(&self) -> String {
+        let state = self.state.lock().unwrap();
+        format!(
+            "<Stratum {} {}clients {}jobs {}shares>",
+            self.addr,
+            state.clients.len(),
+            state.jobs.len(),
+            state.accepted
+        )
+    }
+
+    /// stop accepting and disconnect all clients
+    pub fn stop(&mut self) {
+        self.closed.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+        let mut state = self.state.lock().unwrap();
+        for client in state.clients.drain(..) {
+            client.stream.shutdown(Shutdown::Both).ok();
+        }
+    }
+}
+
+impl Drop for StratumServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// read requests of a client until disconnected
+fn handle_client(stream: TcpStream, state: Arc<Mutex<StratumState>>) {
+    // note: a slow client cannot block others because lines are written by its own thread
+    let (sender, receiver) = sync_channel::<String>(MAX_SEND_QUEUE);
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    writer.set_write_timeout(Some(WRITE_TIMEOUT)).ok();
+    thread::spawn(move || {
+        for line in receiver.iter() {
+            if writer.write_all(format!("{}\n", line).as_bytes()).is_err() {
+                writer.shutdown(Shutdown::Both).ok();
+                break;
+            }
+        }
+    });
+
+    let session = {
+        let mut state = state.lock().unwrap();
+        let session = state.next_session;
+        state.next_session += 1;
+        match stream.try_clone() {
+            Ok(stream) => state.clients.push(StratumClient {
+                session,
+                stream,
+                subscribed: false,
+                sender,
+            }),
+            Err(_) => return,
+        }
+        session
+    };
+
+    stream.set_read_timeout(Some(READ_TIMEOUT)).ok();
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        // note: a line is bounded not to buffer endless bytes without newline
+        line.clear();
+        match (&mut reader).take(MAX_LINE_LEN).read_line(&mut line) {
+            Ok(0) => break,
+            Ok(size) if size as u64 == MAX_LINE_LEN && !line.ends_with('\n') => break,
+            Ok(_) => (),
+            Err(_) => break, // timeout or not utf8
+        }
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(_) => break, // close broken client
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        // note: queue under lock not to mix with notification
+        let mut state = state.lock().unwrap();
+        let response = match state.process(session, method, &params) {
+            Ok(result) => json!({"id": id, "result": result, "error": Value::Null}),
+            Err(err) => json!({"id": id, "result": Value::Null, "error": err}),
+        };
+        if !state.send_line(session, response.to_string()) {
+            break;
+        }
+
+        // send first job after subscribe
+        if method == "mining.subscribe" {
+            if let Ok(job) = state.new_job(session) {
+                let notify = json!({"id": Value::Null, "method": "mining.notify", "params": job});
+                if !state.send_line(session, notify.to_string()) {
+                    break;
+                }
+            }
+        }
+    }
+
+    // remove disconnected client
+    // note: the writer thread exit after the sender dropped
+    let mut state = state.lock().unwrap();
+    state.clients.retain(|client| client.session != session);
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod stratum {
+    use super::*;
+    use crate::block::*;
+    use crate::tx::*;
+    use bigint::U256;
+    use serde_json::{json, Value};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpStream;
+
+    #[test]
+    fn local_client() {
+        // note: maximum target, all share is a winning share
+        let mut server = StratumServer::start("127.0.0.1:0", 0x2100ffff).unwrap();
+        let header = BlockHeader {
+            version: 0,
+            previous_hash: U256::from(1u32),
+            merkleroot: U256::zero(),
+            time: 1000,
+            bits: 0x2100ffff,
+            nonce: 0,
+        };
+        let coinbase = TxBody::new(0, TxType::PoW, 1000, 11800, 0, 0, TxMessage::Nothing);
+        server.update_template(&BlockFlag::X11Pow, header, coinbase, vec![]);
+
+        // subscribe & receive first job
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        let mut read_line = || -> Value {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            serde_json::from_str(&line).unwrap()
+        };
+        client
+            .write_all(b"{\"id\": 1, \"method\": \"mining.subscribe\", \"params\": []}\n")
+            .unwrap();
+        let response = read_line();
+        assert_eq!(response["id"], json!(1));
+        let notify = read_line();
+        assert_eq!(notify["method"], json!("mining.notify"));
+        let job_id = notify["params"]["job_id"].as_u64().unwrap();
+
+        // submit share
+        let submit = json!({"id": 2, "method": "mining.submit", "params": [job_id, 7]});
+        client.write_all(format!("{}\n", submit).as_bytes()).unwrap();
+        let response = read_line();
+        assert_eq!(response["result"]["block"], json!(true));
+
+        // duplicate share
+        client.write_all(format!("{}\n", submit).as_bytes()).unwrap();
+        let response = read_line();
+        assert_eq!(response["error"], json!("duplicate share"));
+
+        // winning share
+        let found = server.pop_found();
+        assert_eq!(found.len(), 1);
+        match &found[0] {
+            (BlockFlag::X11Pow, WorkerResult::PoW((_work, coinbase, bytes))) => {
+                let header = BlockHeader::from_bytes(bytes.as_ref());
+                assert_eq!(header.nonce, 7);
+                let hash = U256::from(coinbase.hash().as_slice());
+                assert_eq!(header.merkleroot, crate::utils::calc_merkleroot_hash(vec![hash]));
+            },
+            _ => panic!("not PoW result"),
+        }
+        server.stop();
+    }
+
+    #[test]
+    fn limit_clients() {
+        let mut server = StratumServer::start("127.0.0.1:0", 0x2100ffff).unwrap();
+        let subscribe = b"{\"id\": 1, \"method\": \"mining.subscribe\", \"params\": []}\n";
+        let is_alive = |client: &mut TcpStream| -> bool {
+            let mut line = String::new();
+            client.write_all(subscribe).is_ok()
+                && BufReader::new(client)
+                    .read_line(&mut line)
+                    .map(|size| 0 < size)
+                    .unwrap_or(false)
+        };
+
+        // refuse over MAX_CLIENTS
+        let mut clients = (0..MAX_CLIENTS)
+            .map(|_| TcpStream::connect(server.local_addr()).unwrap())
+            .collect::<Vec<_>>();
+        assert!(is_alive(&mut clients[0]));
+        let mut refused = TcpStream::connect(server.local_addr()).unwrap();
+        assert!(!is_alive(&mut refused));
+
+        // accept again after disconnected
+        clients.pop().unwrap().shutdown(Shutdown::Both).unwrap();
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        let now = std::time::Instant::now();
+        while !is_alive(&mut client) {
+            assert!(now.elapsed() < Duration::from_secs(10));
+            client = TcpStream::connect(server.local_addr()).unwrap();
+        }
+        server.stop();
+    }
+
+    #[test]
+    fn limit_line_length() {
+        let mut server = StratumServer::start("127.0.0.1:0", 0x2100ffff).unwrap();
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+
+        // disconnect a client sending no newline
+        let spaces = vec![b' '; MAX_LINE_LEN as usize];
+        client.write_all(&spaces).unwrap();
+        let mut line = String::new();
+        assert_eq!(BufReader::new(&client).read_line(&mut line).unwrap(), 0);
+        server.stop();
+    }
+
+    #[test]
+    fn merkle_branch_of_coinbase() {
+        let coinbase = TxBody::new(0, TxType::PoW, 1000, 11800, 0, 0, TxMessage::Nothing);
+        for size in 0..9u32 {
+            let txs = (1..=size).map(U256::from).collect::<Vec<_>>();
+            let mut hashs = vec![U256::zero()];
+            hashs.extend_from_slice(&txs);
+            let branch = merkle_branch(hashs, 0);
+            let mut hashs = vec![U256::from(coinbase.hash().as_slice())];
+            hashs.extend_from_slice(&txs);
+            let root = calc_merkleroot_hash(hashs);
+            assert_eq!(calc_merkleroot_by_branch(&coinbase, &branch), root);
+        }
+    }
+
+    #[test]
+    fn limit_jobs() {
+        let mut state = StratumState {
+            template: Some(StratumTemplate {
+                flag: BlockFlag::X11Pow,
+                header: BlockHeader {
+                    version: 0,
+                    previous_hash: U256::from(1u32),
+                    merkleroot: U256::zero(),
+                    time: 1000,
+                    bits: 0x2100ffff,
+                    nonce: 0,
+                },
+                coinbase: TxBody::new(0, TxType::PoW, 1000, 11800, 0, 0, TxMessage::Nothing),
+                branch: Arc::new(vec![]),
+            }),
+            jobs: HashMap::new(),
+            job_order: VecDeque::new(),
+            next_job_id: 0,
+            next_extra_nonce: 0,
+            share_target: bits_to_target(0x2100ffff).unwrap(),
+            clients: vec![],
+            connections: 0,
+            next_session: 0,
+            found: VecDeque::new(),
+            accepted: 0,
+        };
+
+        // drop oldest job of the session
+        for _ in 0..MAX_SESSION_JOBS + 2 {
+            state.new_job(0).unwrap();
+        }
+        assert_eq!(state.jobs.len(), MAX_SESSION_JOBS);
+        assert!(state.submit(0, None, 0).is_err());
+
+        // drop oldest job of all
+        for session in 1..(MAX_JOBS / MAX_SESSION_JOBS + 2) as u32 {
+            for _ in 0..MAX_SESSION_JOBS {
+                state.new_job(session).unwrap();
+            }
+        }
+        assert_eq!(state.jobs.len(), MAX_JOBS);
+        assert_eq!(state.job_order.len(), MAX_JOBS);
+
+        // extra-nonce range of a job
+        let job_id = *state.job_order.back().unwrap();
+        let last = state.jobs[&job_id].extra_nonce + EXTRA_NONCE_SIZE - 1;
+        assert!(state.submit(job_id, Some(last), 0).is_ok());
+        assert!(state.submit(job_id, Some(last + 1), 0).is_err());
+        assert!(state.submit(job_id, Some(last), 0).is_err());
+
+        // winning shares are limited
+        for nonce in 1..(MAX_FOUND as u32 + 4) {
+            state.submit(job_id, None, nonce).unwrap();
+        }
+        assert_eq!(state.found.len(), MAX_FOUND);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::block::{
+    bits_to_target,
+    get_work_hash,
+    Block,
+    BlockFlag,
+    BlockHeader,
+    DifficultyBuilder,
+    RewardBuilder,
+};
+use crate::chain::unconfirmed::UnconfirmedTxs;
+use crate::chain::Chain;
+use crate::tx::{TxBody, TxMessage, TxOutput, TxType, TxVerifiable};
+use crate::utils::*;
+use bigint::U256;
+
+/// block template for external PoW miners (getblocktemplate)
+#[derive(Clone)]
+pub struct BlockTemplate {
+    pub flag: BlockFlag,
+    pub height: u32,
+    /// header with coinbase's merkleroot and nonce 0
+    pub header: BlockHeader,
+    pub coinbase: TxBody,
+    /// unconfirmed txs included after coinbase
+    pub unconfirmed: UnconfirmedTxs,
+    pub bias: f32,
+    /// block reward + txs reward
+    pub reward: u64,
+    /// coinbase's merkle branch
+    pub branch: Vec<U256>,
+}
+
+impl BlockTemplate {
+    /// new template on the best block
+    ///
+    /// note: time and deadline must cover unconfirmed txs
+    pub fn new(
+        chain: &mut Chain,
+        diff: &mut DifficultyBuilder,
+        reward: &RewardBuilder,
+        flag: &BlockFlag,
+        unconfirmed: UnconfirmedTxs,
+        time: u32,
+        deadline: u32,
+        block_ver: u32,
+        tx_ver: u32,
+    ) -> Result<Self, String> {
+        match flag {
+            BlockFlag::YesPow | BlockFlag::X11Pow | BlockFlag::X16sPow => (),
+            flag => return Err(format!("template is only for PoW but {:?}", flag)),
+        }
+        if time < unconfirmed.time || unconfirmed.deadline <= deadline {
+            return Err(format!(
+                "time and deadline don't cover unconfirmed txs time={} deadline={}",
+                unconfirmed.time, unconfirmed.deadline
+            ));
+        }
+        if chain.best_chain.is_empty() {
+            return Err("best block is not found".to_owned());
+        }
+
+        // mining info
+        let best_block = chain.get_best_block_ref().clone();
+        let previous_hash = best_block.header.hash();
+        let height = best_block.height + 1;
+        let bits = diff.calc_next_bits(&previous_hash, flag, &chain.tables)?;
+        let bias = diff.calc_next_bias(&previous_hash, flag, &chain.tables)?;
+        let reward = reward.calc_block_reward(height) + unconfirmed.reward;
+
+        // coinbase
+        // note: PoW coinbase don't have signature and input
+        let addr = chain.get_account_address(0, false)?;
+        let mut coinbase = TxBody::new(tx_ver, TxType::PoW, time, deadline, 0, 0, TxMessage::Nothing);
+        coinbase.outputs.push(TxOutput(addr, 0, reward));
+
+        // header
+        let mut hashs = Vec::with_capacity(1 + unconfirmed.txs.len());
+        hashs.push(U256::from(coinbase.hash().as_slice()));
+        hashs.extend_from_slice(&unconfirmed.txs);
+        let branch = merkle_branch(hashs.clone(), 0);
+        let header = BlockHeader {
+            version: block_ver,
+            previous_hash,
+            merkleroot: calc_merkleroot_hash(hashs),
+            time,
+            bits,
+            nonce: 0,
+        };
+
+        Ok(BlockTemplate {
+            flag: flag.clone(),
+            height,
+            header,
+            coinbase,
+            unconfirmed,
+            bias,
+            reward,
+            branch,
+        })
+    }
+
+    /// rebuild block from submitted header and coinbase
+    ///
+    /// note: coinbase's message may be changed by miner (extra-nonce)
+    pub fn to_block(
+        &self,
+        chain: &Chain,
+        header: &[u8],
+        coinbase: TxBody,
+    ) -> Result<(Block, Vec<TxVerifiable>), String> {
+        if header.len() != 80 {
+            return Err(format!("header is 80 bytes but {}", header.len()));
+        }
+        let header = BlockHeader::from_bytes(header);
+        if header.previous_hash != self.header.previous_hash || header.bits != self.header.bits {
+            return Err("header is not based on the template".to_owned());
+        }
+        if coinbase.txtype != TxType::PoW {
+            return Err(format!("coinbase is PoW type but {:?}", coinbase.txtype));
+        }
+
+        // check merkleroot
+        let coinbase_hash = U256::from(coinbase.hash().as_slice());
+        let mut txs_hash = Vec::with_capacity(1 + self.unconfirmed.txs.len());
+        txs_hash.push(coinbase_hash.clone());
+        txs_hash.extend_from_slice(&self.unconfirmed.txs);
+        if header.merkleroot != calc_merkleroot_hash(txs_hash.clone()) {
+            return Err("merkleroot is not calculated from coinbase and txs".to_owned());
+        }
+
+        // check work
+        let work_hash = get_work_hash(&self.flag, &header, None, None)?;
+        if bits_to_target(header.bits)? <= work_hash {
+            return Err("not enough work".to_owned());
+        }
+
+        // generate block & txs
+        let block = Block::new(
+            work_hash,
+            self.height,
+            self.flag.clone(),
+            self.bias,
+            header,
+            txs_hash,
+        );
+        let coinbase = TxVerifiable {
+            hash: coinbase_hash,
+            body: coinbase,
+            signature: vec![],
+            inputs_cache: vec![],
+        };
+        let txs = self.unconfirmed.get_mining_block_txs(coinbase, &chain.tables);
+        Ok((block, txs))
+    }
+}
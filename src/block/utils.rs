@@ -1,5 +1,5 @@
 use crate::block::{BlockFlag, BlockHeader};
-use crate::tx::{TxBody, TxOutput};
+use crate::tx::{is_lock_output, TxBody, TxOutput};
 use crate::utils::u256_to_bytes;
 use bc4py_hash::{get_poc_hash, get_x11_hash, get_x16s_hash, get_yespower_hash};
 use bigint::U256;
@@ -72,7 +72,7 @@ pub fn get_work_hash(
     // note: PoS type require coinbase & input_cache
     match flag {
         BlockFlag::Genesis => Ok(U256::zero()),
-        BlockFlag::CoinPos => {
+        BlockFlag::CoinPos | BlockFlag::FlkPos => {
             if input_cache.is_none() {
                 return Err("input_cache is none but required for params".to_owned());
             }
@@ -80,6 +80,12 @@ pub fn get_work_hash(
             if input.1 != 0 {
                 return Err("try to get coinbase but coinId isn't 0".to_owned());
             }
+            // note: same work but FlkPos stake only a lock output
+            match (flag, is_lock_output(input)) {
+                (BlockFlag::CoinPos, true) => return Err("lock output is staked only by FlkPos".to_owned()),
+                (BlockFlag::FlkPos, false) => return Err("FlkPos input isn't a lock output".to_owned()),
+                _ => (),
+            }
             if coinbase.is_none() {
                 return Err("coinbase is none but required".to_owned());
             }
@@ -96,7 +102,6 @@ pub fn get_work_hash(
             let work = U256::from(output.as_slice());
             Ok(work)
         },
-        BlockFlag::FlkPos => Err("not implemented yet".to_owned()),
         BlockFlag::YesPow => {
             let input = header.to_bytes().to_vec();
             let work = U256::from(get_yespower_hash(input).as_slice());
@@ -119,7 +124,7 @@ pub fn get_work_hash(
 #[cfg(test)]
 mod test {
     use crate::block::*;
-    use crate::tx::TxBody;
+    use crate::tx::{to_lock_addr, TxBody, TxOutput};
     use crate::utils::*;
     use bigint::U256;
 
@@ -165,4 +170,27 @@ mod test {
         let calc = get_pos_hash(&coinbase, amount, &previous_hash);
         assert_eq!(u256_to_hex(&calc), work);
     }
+
+    #[test]
+    fn flk_hash() {
+        let bytes = hex::decode("00000000020000003d3dfa016d67fa010000000000000000000000000000000000010100000000527c5bce9af709995d8b36ca18c517b33623d740d1b8374418176f045c115b6a0000de6e40c12db0920348ed0ebb136e3a926bad4a3a000000008a80355500000000").unwrap();
+        let coinbase = TxBody::from_bytes(bytes.as_slice()).unwrap();
+        let header = BlockHeader {
+            version: 0,
+            previous_hash: U256::from(1u32),
+            merkleroot: U256::zero(),
+            time: 0,
+            bits: 0,
+            nonce: 0,
+        };
+        let normal = TxOutput([0u8; 21], 0, 7_1234_1764);
+        let lock = TxOutput(to_lock_addr(&normal.0), 0, 7_1234_1764);
+
+        // lock output is staked only by FlkPos
+        let flk = get_work_hash(&BlockFlag::FlkPos, &header, Some(&coinbase), Some(&lock)).unwrap();
+        let pos = get_work_hash(&BlockFlag::CoinPos, &header, Some(&coinbase), Some(&normal)).unwrap();
+        assert_eq!(flk, pos);
+        assert!(get_work_hash(&BlockFlag::FlkPos, &header, Some(&coinbase), Some(&normal)).is_err());
+        assert!(get_work_hash(&BlockFlag::CoinPos, &header, Some(&coinbase), Some(&lock)).is_err());
+    }
 }
@@ -1,18 +1,22 @@
 use crate::balance::*;
 use crate::block::Block;
+use crate::chain::filter::BlockFilter;
+use crate::chain::secret::{zeroize_priv_key, EncryptedSecret, SCRYPT_LOG_N};
 use crate::chain::tables::{TableCursor, Tables};
 use crate::signature::Signature;
-use crate::tx::TxVerifiable;
+use crate::tx::{get_owner_addr, to_lock_addr, TxBody, TxOutput, TxVerifiable};
 use crate::utils::*;
+use bigint::U256;
 use hdwallet::traits::{Deserialize, Serialize};
 use hdwallet::{error::Error, ExtendedPrivKey, ExtendedPubKey, KeyIndex};
 use std::fmt;
 use std::slice::Iter;
+use zeroize::Zeroizing;
 
 type Address = [u8; 21];
 const PRE_FETCH_ADDR_LEN: usize = 25;
 const PRE_FETCH_ACCOUNT_LEN: usize = 20;
-const BIP32_HARDEN: u32 = 0x80000000;
+pub const BIP32_HARDEN: u32 = 0x80000000;
 
 /// listen account related all address, don't require secret key.
 /// derive `m/44'/CoinType'/account'/is_inner/index` from root_key.
@@ -25,6 +29,7 @@ pub struct Account {
     listen_outer: Vec<Address>,
     pub balance: Balances,
     visible: bool,
+    watch_only: bool, // created from xpub, cannot sign
 
     // for detect account status change
     changed: bool,
@@ -40,6 +45,7 @@ impl fmt::Debug for Account {
             .entry(&"outer", &self.listen_outer.len())
             .entry(&"balance", &self.balance)
             .entry(&"visible", &self.visible)
+            .entry(&"watch_only", &self.watch_only)
             .entry(&"changed", &self.changed)
             .finish()
     }
@@ -60,6 +66,7 @@ impl Account {
                 amount: 0,
             }]),
             visible,
+            watch_only: false,
             changed: true,
         };
 
@@ -75,7 +82,7 @@ impl Account {
     }
 
     fn from_bytes(account_id: u32, bytes: &[u8]) -> Result<Self, Error> {
-        // [root_key 33+32b][unused_index u32][inner_len u32][outer_len u32][visible u8]
+        // [root_key 33+32b][unused_index u32][inner_len u32][outer_len u32][flags u8]
         // [balance_len u32][coinId u32, amount i64]..
 
         // static
@@ -83,7 +90,9 @@ impl Account {
         let unused_index = bytes_to_u32(&bytes[65..65 + 4]) as usize;
         let inner_len = bytes_to_u32(&bytes[69..69 + 4]) as usize;
         let outer_len = bytes_to_u32(&bytes[73..73 + 4]) as usize;
-        let visible = 0 < bytes[77];
+        // note: flags is visible(0b01) and watch_only(0b10)
+        let visible = bytes[77] & 0b01 != 0;
+        let watch_only = bytes[77] & 0b10 != 0;
         let balance_len = bytes_to_u32(&bytes[78..78 + 4]) as usize;
 
         // listen inner
@@ -124,12 +133,13 @@ impl Account {
             listen_outer,
             balance,
             visible,
+            watch_only,
             changed: false,
         })
     }
 
     fn to_bytes(&self) -> Vec<u8> {
-        // [root_key 33+32b][unused_index u32][inner_len u32][outer_len u32][visible u8]
+        // [root_key 33+32b][unused_index u32][inner_len u32][outer_len u32][flags u8]
         // [balance_len u32][coinId u32, amount i64]..
         let mut vec = Vec::with_capacity(33 + 32 + 4 * 3 + 1 + 4);
 
@@ -139,7 +149,9 @@ impl Account {
         vec.extend_from_slice(&u32_to_bytes(self.unused_index as u32));
         vec.extend_from_slice(&u32_to_bytes(self.listen_inner.len() as u32));
         vec.extend_from_slice(&u32_to_bytes(self.listen_outer.len() as u32));
-        vec.push(if self.visible { 1 } else { 0 });
+        let visible = if self.visible { 0b01 } else { 0 };
+        let watch_only = if self.watch_only { 0b10 } else { 0 };
+        vec.push(visible | watch_only);
         vec.extend_from_slice(&u32_to_bytes(self.balance.0.len() as u32));
 
         // balance
@@ -191,6 +203,10 @@ impl Account {
         Ok(None)
     }
 
+    pub fn is_watch_only(&self) -> bool {
+        self.watch_only
+    }
+
     fn get_address_path(&self, addr: &Address) -> Option<(bool, u32)> {
         // return (is_inner, index)
         let index = self.listen_inner.iter().position(|_addr| _addr == addr);
@@ -294,7 +310,9 @@ impl Account {
 }
 
 pub struct AccountBuilder {
-    root_key: Option<ExtendedPrivKey>, // m/44'/CoinType'
+    root_key: Option<ExtendedPrivKey>,  // m/44'/CoinType', None if locked
+    encrypted: Option<EncryptedSecret>, // root_key encrypted by passphrase
+    unlock_until: Option<f64>,          // lock after the time, None is no timeout
     accounts: Vec<Account>,
 }
 
@@ -317,6 +335,8 @@ impl AccountBuilder {
         }
         let mut accounts = AccountBuilder {
             root_key: Some(sk),
+            encrypted: None,
+            unlock_until: None,
             accounts,
         };
         // update accounts
@@ -324,20 +344,136 @@ impl AccountBuilder {
         Ok(accounts)
     }
 
-    pub fn restore_from_tables(tables: &Tables, sk: &Option<Vec<u8>>) -> Result<Self, Error> {
+    /// no secret key, accounts are added by `add_watch_only_account`
+    pub fn new_watch_only() -> Self {
+        AccountBuilder {
+            root_key: None,
+            encrypted: None,
+            unlock_until: None,
+            accounts: vec![],
+        }
+    }
+
+    /// add a account listened by the xpub `m/44'/CoinType'/account_id'`
+    pub fn add_watch_only_account(
+        &mut self,
+        account_id: u32,
+        xpub: &[u8],
+        cur: &mut TableCursor,
+    ) -> Result<(), String> {
+        if self.get_account_ref(account_id).is_ok() {
+            return Err(format!("account_id {} is already used", account_id));
+        }
+        let root_key = ExtendedPubKey::deserialize(xpub).map_err(|err| format!("{:?}", err))?;
+        let mut account = Account::new(account_id, true, root_key).map_err(|err| format!("{:?}", err))?;
+        account.watch_only = true;
+        cur.write_account_state(account.account_id, &account.to_bytes())
+            .unwrap();
+        account.changed = false;
+        self.accounts.push(account);
+        Ok(())
+    }
+
+    pub fn restore_from_tables(tables: &Tables, sk: &Option<Vec<u8>>) -> Result<Self, String> {
         // note: check root_key is same with table's key
         let root_key = match sk {
-            Some(sk) => Some(ExtendedPrivKey::deserialize(sk)?),
+            Some(sk) => Some(ExtendedPrivKey::deserialize(sk).map_err(|err| format!("{:?}", err))?),
+            None => None,
+        };
+
+        // note: locked until `unlock` if sk isn't given
+        let encrypted = match tables.read_account_secret()? {
+            Some(bytes) => Some(EncryptedSecret::from_bytes(&bytes)?),
             None => None,
         };
 
         let mut accounts = vec![];
         for (key, value) in tables.read_account_iter() {
+            // note: skip encrypted secret
+            if key.len() != 4 {
+                continue;
+            }
             let account_id = bytes_to_u32(&key);
-            accounts.push(Account::from_bytes(account_id, &value)?);
+            let account = Account::from_bytes(account_id, &value).map_err(|err| format!("{:?}", err))?;
+            accounts.push(account);
         }
 
-        Ok(AccountBuilder { root_key, accounts })
+        Ok(AccountBuilder {
+            root_key,
+            encrypted,
+            unlock_until: None,
+            accounts,
+        })
+    }
+
+    /// encrypt rootKey by passphrase and lock, also use to change passphrase
+    pub fn encrypt_secret(&mut self, passphrase: &[u8], cur: &mut TableCursor) -> Result<(), String> {
+        self.lock_if_expired();
+        let root_key = self
+            .root_key
+            .as_ref()
+            .ok_or("cannot encrypt because rootKey is none or locked".to_owned())?;
+        let secret = Zeroizing::new(root_key.serialize());
+        let encrypted = EncryptedSecret::encrypt(&secret, passphrase, SCRYPT_LOG_N)?;
+        cur.write_account_secret(&encrypted.to_bytes())?;
+        self.encrypted = Some(encrypted);
+        self.lock()
+    }
+
+    /// decrypt rootKey by passphrase, lock again after timeout seconds (0 is no timeout)
+    pub fn unlock(&mut self, passphrase: &[u8], timeout: u32) -> Result<(), String> {
+        let encrypted = self
+            .encrypted
+            .as_ref()
+            .ok_or("wallet isn't encrypted".to_owned())?;
+        let secret = encrypted.decrypt(passphrase)?;
+        let root_key =
+            ExtendedPrivKey::deserialize(&secret).map_err(|err| format!("broken secret: {:?}", err))?;
+        if let Some(old) = self.root_key.as_mut() {
+            zeroize_priv_key(old);
+        }
+        self.root_key = Some(root_key);
+        self.unlock_until = if timeout == 0 {
+            None
+        } else {
+            Some(get_current_time() + timeout as f64)
+        };
+        Ok(())
+    }
+
+    /// remove rootKey from memory, only encrypted wallet
+    pub fn lock(&mut self) -> Result<(), String> {
+        if self.encrypted.is_none() {
+            return Err("cannot lock because wallet isn't encrypted".to_owned());
+        }
+        if let Some(root_key) = self.root_key.as_mut() {
+            zeroize_priv_key(root_key);
+        }
+        self.root_key = None;
+        self.unlock_until = None;
+        Ok(())
+    }
+
+    /// lock if unlock timeout is passed
+    pub fn lock_if_expired(&mut self) {
+        if self.is_expired() {
+            self.lock().unwrap();
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        match self.unlock_until {
+            Some(until) => until < get_current_time(),
+            None => false,
+        }
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted.is_some()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.root_key.is_none() || self.is_expired()
     }
 
     pub fn get_new_account<'a>(&'a mut self, cur: &mut TableCursor) -> Result<&'a mut Account, String> {
@@ -403,6 +539,8 @@ impl AccountBuilder {
 
     pub fn get_path_from_addr(&self, addr: &Address) -> Option<(u32, u32, u32)> {
         // return (account_id, is_inner, index)
+        // note: lock address is owner's path
+        let addr = &get_owner_addr(addr);
         for account in self.accounts.iter() {
             match account.get_address_path(addr) {
                 Some((is_inner, index)) => {
@@ -439,11 +577,36 @@ impl AccountBuilder {
             .for_each(|balance| fee.sub_balance(&balance));
         fee.compaction();
         assert_eq!(fee.sum(), body.gas_amount * body.gas_price as i64);
-        let mut movement = BalanceMovement::new(tx.hash, fee);
+        let movement = self.calc_movement(tx.hash, body, inputs_cache, fee)?;
+
+        // write movement as cache if need
+        // ignore if no move in the movement
+        if movement.get_movement_type() != MovementType::Nothing {
+            cur.write_temporary_movement(&movement).unwrap();
+        }
+
+        // update accounts
+        self.update_all_account_status(cur);
+
+        // success
+        Ok(())
+    }
+
+    /// find account related inputs & outputs
+    /// note: update `unused_index` and `listen_*`
+    fn calc_movement(
+        &mut self,
+        hash: U256,
+        body: &TxBody,
+        inputs_cache: &[TxOutput],
+        fee: Balances,
+    ) -> Result<BalanceMovement, Error> {
+        let mut movement = BalanceMovement::new(hash, fee);
 
         // inputs
+        // note: lock address is owned by the account address
         for inputs_cache in inputs_cache.iter() {
-            let addr = &inputs_cache.0;
+            let addr = &get_owner_addr(&inputs_cache.0);
             for account in self.accounts.iter_mut() {
                 if account.check_and_expand_listen(addr)?.is_some() {
                     account.update_unused_index(addr);
@@ -455,7 +618,7 @@ impl AccountBuilder {
 
         // outputs
         for output in body.outputs.iter() {
-            let addr = &output.0;
+            let addr = &get_owner_addr(&output.0);
             for account in self.accounts.iter_mut() {
                 match account.check_and_expand_listen(addr)? {
                     Some(is_inner) => {
@@ -467,18 +630,47 @@ impl AccountBuilder {
                 }
             }
         }
+        Ok(movement)
+    }
 
-        // write movement as cache if need
-        // ignore if no move in the movement
-        if movement.get_movement_type() != MovementType::Nothing {
-            cur.write_temporary_movement(&movement).unwrap();
+    /// rescan a finalized tx and return true if account tx
+    ///
+    /// note: `inputs_cache` is resolved outputs only because unknown output isn't account's
+    /// note: skip already recoded movement to rescan many times
+    /// note: execute `update_all_account_status` after rescan txs
+    pub fn rescan_tx(
+        &mut self,
+        hash: U256,
+        body: &TxBody,
+        inputs_cache: &[TxOutput],
+        height: u32,
+        position: u32,
+        cur: &mut TableCursor,
+    ) -> Result<bool, String> {
+        // note: cannot calc fee by `incoming - outgoing` because inputs_cache is partial
+        let mut fee = Balances(Vec::with_capacity(1));
+        fee.add_balance(&Balance {
+            coin_id: 0,
+            amount: body.gas_amount * body.gas_price as i64,
+        });
+        fee.compaction();
+        let movement = self
+            .calc_movement(hash, body, inputs_cache, fee)
+            .map_err(|err| format!("account rescan failed: {:?}", err))?;
+        if movement.get_movement_type() == MovementType::Nothing {
+            return Ok(false);
         }
 
-        // update accounts
-        self.update_all_account_status(cur);
-
-        // success
-        Ok(())
+        // same as `finalize_block` but movement isn't temporary
+        if !cur.tables.have_the_movement(height, position)? {
+            for (account_id, balances) in movement.get_account_movement() {
+                self.get_account_mut(account_id)
+                    .expect("already known account but?")
+                    .add_balances_and_update(&balances);
+            }
+            cur.write_movement(height, position, &movement)?;
+        }
+        Ok(true)
     }
 
     pub fn finalize_block(&mut self, block: &Block, cur: &mut TableCursor) -> Result<(), String> {
@@ -489,8 +681,7 @@ impl AccountBuilder {
             match cur.tables.read_temporary_movement(hash)? {
                 Some(movement) => {
                     for (account_id, balances) in movement.get_account_movement() {
-                        self.accounts
-                            .get_mut(account_id as usize)
+                        self.get_account_mut(account_id)
                             .expect("already known account but?")
                             .add_balances_and_update(&balances);
                     }
@@ -510,6 +701,7 @@ impl AccountBuilder {
     }
 
     pub fn is_account_address(&self, addr: &Address) -> bool {
+        let addr = &get_owner_addr(addr);
         for account in self.accounts.iter() {
             if account.listen_inner.contains(addr) || account.listen_outer.contains(addr) {
                 return true;
@@ -518,6 +710,17 @@ impl AccountBuilder {
         false
     }
 
+    /// check the block may include account tx by compact block filter
+    pub fn match_block_filter(&self, blockhash: &U256, filter: &BlockFilter) -> Result<bool, String> {
+        // note: lock address is owned by the account address
+        let mut addrs = Vec::new();
+        for addr in self.get_account_addr_iter() {
+            addrs.push(addr);
+            addrs.push(to_lock_addr(&addr));
+        }
+        filter.match_addrs(blockhash, &addrs)
+    }
+
     pub fn get_account_addr_iter(&self) -> AccountAddrIter {
         AccountAddrIter {
             account_iter: self.accounts.iter(),
@@ -527,10 +730,23 @@ impl AccountBuilder {
     }
 
     pub fn get_single_sign_by_addr(&self, addr: &Address, msg: &[u8]) -> Result<Signature, String> {
+        if self.encrypted.is_some() && self.is_locked() {
+            return Err("You can't sign because wallet is locked".to_owned());
+        }
         if self.root_key.is_none() {
             return Err("You can't sign because rootKey is none".to_owned());
         }
 
+        // watch-only account's key isn't derived from rootKey
+        let owner = get_owner_addr(addr);
+        if self
+            .accounts
+            .iter()
+            .any(|account| account.watch_only && account.get_address_path(&owner).is_some())
+        {
+            return Err(format!("{} is watch-only account address", hex::encode(addr)));
+        }
+
         // derive from rootKey
         let key = match self.get_path_from_addr(addr) {
             Some((account_id, is_inner, index)) => self
@@ -554,11 +770,28 @@ impl AccountBuilder {
         Signature::get_single_sign(sk, &pk, msg)
     }
 
+    /// check the address is signable by `get_single_sign_by_addr` now
+    pub fn is_signable_addr(&self, addr: &Address) -> bool {
+        if self.is_locked() {
+            return false;
+        }
+        let owner = get_owner_addr(addr);
+        self.accounts
+            .iter()
+            .any(|account| !account.watch_only && account.get_address_path(&owner).is_some())
+    }
+
     fn expand_account_capacity(&mut self) -> Result<(), Error> {
         // add a invisible account for listen
         assert!(self.root_key.is_some());
-        let last = self.accounts.last().unwrap();
-        let account_id = last.account_id + 1;
+        // note: watch-only account may have any id
+        let account_id = self
+            .accounts
+            .iter()
+            .map(|account| account.account_id)
+            .max()
+            .map(|account_id| account_id + 1)
+            .unwrap_or(0);
         let key = self
             .root_key
             .as_ref()
@@ -571,7 +804,7 @@ impl AccountBuilder {
         Ok(())
     }
 
-    fn update_all_account_status(&mut self, cur: &mut TableCursor) {
+    pub fn update_all_account_status(&mut self, cur: &mut TableCursor) {
         // refract status to tables at once
         // execute after account edit
         for account in self.accounts.iter_mut() {
@@ -622,7 +855,7 @@ impl Iterator for AccountAddrIter<'_> {
 #[allow(unused_imports)]
 #[cfg(test)]
 mod account {
-    use crate::chain::account::Account;
+    use crate::chain::account::{Account, AccountBuilder};
     use hdwallet::traits::Deserialize;
     use hdwallet::{ExtendedPrivKey, ExtendedPubKey};
 
@@ -645,4 +878,29 @@ mod account {
         let new_account = Account::from_bytes(account_id, &bytes).unwrap();
         assert_eq!(new_account, account);
     }
+
+    #[test]
+    fn watch_only() {
+        let prv = ExtendedPrivKey::random().unwrap();
+        let root_key = ExtendedPubKey::from_private_key(&prv);
+        let mut account = Account::new(3, true, root_key).unwrap();
+        account.watch_only = true;
+        account.changed = false;
+
+        let bytes = account.to_bytes();
+        let new_account = Account::from_bytes(3, &bytes).unwrap();
+        assert_eq!(new_account, account);
+        assert!(new_account.is_watch_only());
+
+        // cannot sign even if rootKey exists
+        let addr = account.listen_outer[0];
+        let builder = AccountBuilder {
+            root_key: Some(ExtendedPrivKey::random().unwrap()),
+            encrypted: None,
+            unlock_until: None,
+            accounts: vec![account],
+        };
+        assert!(builder.is_account_address(&addr));
+        assert!(builder.get_single_sign_by_addr(&addr, b"hello").is_err());
+    }
 }
@@ -0,0 +1,981 @@
+use crate::balance::Balances;
+use crate::chain::Chain;
+use crate::tx::*;
+use bigint::U256;
+
+type Address = [u8; 21];
+
+/// body size of an input or an output
+const IO_SIZE: u64 = 33;
+
+/// body size of a signature
+const SIGN_SIZE: u64 = 33 + 32 + 32;
+
+/// body size except inputs, outputs and message
+const BASE_SIZE: u64 = 39;
+
+/// hard limit of inputs length (txindex is u8)
+pub const MAX_INPUTS: usize = 255;
+
+/// give up branch-and-bound after the tries
+const BNB_MAX_TRIES: usize = 100000;
+
+/// search inputs which sum is in `[target, target + window]` and `accept` (no change output)
+///
+/// `values` are sorted from large to small, return the least waste selection
+pub fn branch_and_bound(
+    values: &[u64],
+    target: u64,
+    window: u64,
+    max_inputs: usize,
+    accept: &dyn Fn(&[usize]) -> bool,
+) -> Option<Vec<usize>> {
+    // remain[i] is sum of values[i..]
+    // note: sums are saturated, a value over u64 is never in the window
+    let mut remain = vec![0u64; values.len() + 1];
+    for i in (0..values.len()).rev() {
+        remain[i] = remain[i + 1].saturating_add(values[i]);
+    }
+    if remain[0] < target {
+        return None;
+    }
+
+    // depth first search, include first then exclude
+    let mut best: Option<(u64, Vec<usize>)> = None;
+    let mut selected: Vec<usize> = Vec::new();
+    let mut index = 0;
+    let mut sum = 0u64;
+    for _ in 0..BNB_MAX_TRIES {
+        let backtrack = if sum.saturating_add(remain[index]) < target || target.saturating_add(window) < sum {
+            true
+        } else if target <= sum {
+            let waste = sum - target;
+            if best.as_ref().map(|(best, _)| waste < *best).unwrap_or(true) && accept(&selected) {
+                best = Some((waste, selected.clone()));
+            }
+            true
+        } else {
+            max_inputs <= selected.len()
+        };
+        if backtrack {
+            // exclude the last included value
+            match selected.pop() {
+                Some(last) => {
+                    sum -= values[last];
+                    index = last + 1;
+                },
+                None => break,
+            }
+            if best.as_ref().map(|(waste, _)| *waste == 0).unwrap_or(false) {
+                break;
+            }
+        } else {
+            match sum.checked_add(values[index]) {
+                Some(new_sum) => {
+                    selected.push(index);
+                    sum = new_sum;
+                },
+                None => (), // exclude the overflowed value
+            }
+            index += 1;
+        }
+    }
+    best.map(|(_, selected)| selected)
+}
+
+/// select from the largest value until the sum reach `target`
+///
+/// `values` are sorted from large to small
+pub fn largest_first(values: &[u64], target: u64, max_inputs: usize) -> Option<Vec<usize>> {
+    let mut sum = 0u64;
+    let mut selected = Vec::new();
+    for (index, value) in values.iter().enumerate() {
+        if target <= sum {
+            break;
+        }
+        if max_inputs <= selected.len() {
+            return None;
+        }
+        // note: the sum over u64 cannot be a change output
+        sum = sum.checked_add(*value)?;
+        selected.push(index);
+    }
+    if target <= sum {
+        Some(selected)
+    } else {
+        None
+    }
+}
+
+/// build a signed transfer tx from account's unspent
+///
+/// fee is paid by coinId 0, `gas_amount` is tx size include signatures
+pub struct TxBuilder {
+    pub version: u32,
+    pub time: u32,
+    pub deadline: u32,
+    /// (address, coin_id, amount)
+    pub destinations: Vec<TxOutput>,
+    /// fee rate per byte
+    pub gas_price: u64,
+    pub message: TxMessage,
+    /// change outputs go to a new address of the account
+    pub change_account: u32,
+    /// also spend unspent of the used coins less than or equal to this, 0 is disabled
+    pub dust_limit: u64,
+}
+
+impl TxBuilder {
+    /// build the payment tx, error if it requires more than `MAX_INPUTS` inputs
+    pub fn build(&self, chain: &mut Chain) -> Result<TxVerifiable, String> {
+        let unspents = get_signable_unspents(chain);
+        let inputs = self.select_inputs(&unspents)?;
+        self.finalize(chain, inputs, self.destinations.clone())
+    }
+
+    /// propose merge txs to be pushed before the payment which require too many inputs
+    ///
+    /// later tx may spend the former's output, empty if the payment is built without merge
+    pub fn propose_consolidation(&self, chain: &mut Chain) -> Result<Vec<TxVerifiable>, String> {
+        let mut unspents = get_signable_unspents(chain);
+        let require = self.get_require()?;
+        let mut txs: Vec<TxVerifiable> = Vec::new();
+        loop {
+            let needs = self.count_needs(&unspents, &require)?;
+            if needs.iter().map(|(_, need)| need).sum::<usize>() <= MAX_INPUTS {
+                break;
+            }
+
+            // merge the smallest inputs of the coin which need most inputs
+            let (coin_id, need) = needs.into_iter().max_by_key(|(_, need)| *need).unwrap();
+            let candidates = sorted_candidates(&unspents, coin_id);
+            // note: keep a slot for fee input
+            let slots = if coin_id == 0 { MAX_INPUTS } else { MAX_INPUTS - 1 };
+            let merge_len = need.min(slots);
+            if merge_len < 2 {
+                return Err("cannot reduce inputs by merge".to_owned());
+            }
+            let mut inputs = candidates[need - merge_len..need].to_vec();
+            if coin_id != 0 && 0 < self.gas_price {
+                let fee_input = sorted_candidates(&unspents, 0)
+                    .into_iter()
+                    .next()
+                    .ok_or("no coinId 0 unspent to pay merge fee".to_owned())?;
+                inputs.push(fee_input);
+            }
+            let tx = self.finalize(chain, inputs, vec![])?;
+
+            // replace spent unspent by the merged output
+            unspents.retain(|(input, _)| !tx.body.inputs.contains(input));
+            for (index, output) in tx.body.outputs.iter().enumerate() {
+                unspents.push((TxInput(tx.hash, index as u8), output.clone()));
+            }
+            txs.push(tx);
+        }
+        Ok(txs)
+    }
+
+    /// destinations' total amount of each coin sorted by coinId
+    fn get_require(&self) -> Result<Balances, String> {
+        if self.destinations.is_empty() {
+            return Err("no destination".to_owned());
+        }
+        let mut require = Balances(Vec::with_capacity(1));
+        for output in self.destinations.iter() {
+            if output.2 == 0 {
+                return Err(format!("zero amount output to {}", hex::encode(&output.0[..])));
+            }
+            require.add(output.1, output.2);
+        }
+        require.0.sort_by_key(|balance| balance.coin_id);
+        Ok(require)
+    }
+
+    /// minimum inputs length of each coin by largest-first, coinId 0 is the last
+    fn count_needs(
+        &self,
+        unspents: &[(TxInput, TxOutput)],
+        require: &Balances,
+    ) -> Result<Vec<(u32, usize)>, String> {
+        let mut needs = Vec::with_capacity(require.0.len() + 1);
+        for balance in require.0.iter().filter(|balance| balance.coin_id != 0) {
+            let values = get_values(&sorted_candidates(unspents, balance.coin_id), 0);
+            let need = largest_first(&values, balance.amount as u64, usize::MAX)
+                .ok_or(format!("not enough balance of coinId {}", balance.coin_id))?
+                .len();
+            needs.push((balance.coin_id, need));
+        }
+        let others = needs.iter().map(|(_, need)| *need).sum::<usize>();
+        // note: pessimistically other coins' inputs are signed by each address
+        let target = self
+            .get_target0(require, others, needs.len(), others)?
+            .saturating_add(self.gas_fee(IO_SIZE)?);
+        let values = get_values(&sorted_candidates(unspents, 0), self.get_input_cost()?);
+        let need = if 0 < target {
+            largest_first(&values, target, usize::MAX)
+                .ok_or("not enough balance of coinId 0 include fee".to_owned())?
+                .len()
+        } else {
+            0
+        };
+        needs.push((0, need));
+        Ok(needs)
+    }
+
+    /// select inputs of all coins within `MAX_INPUTS`
+    fn select_inputs(&self, unspents: &[(TxInput, TxOutput)]) -> Result<Vec<(TxInput, TxOutput)>, String> {
+        let require = self.get_require()?;
+        let needs = self.count_needs(unspents, &require)?;
+        let total = needs.iter().map(|(_, need)| need).sum::<usize>();
+        if MAX_INPUTS < total {
+            return Err(format!(
+                "require {} inputs over {}, consolidate unspent first",
+                total, MAX_INPUTS
+            ));
+        }
+
+        // other coins: fee-free selection, reserve minimum inputs for the rest
+        let mut inputs: Vec<(TxInput, TxOutput)> = Vec::new();
+        let mut reserved = total;
+        let mut changes = 0;
+        for (coin_id, need) in needs.iter().filter(|(coin_id, _)| *coin_id != 0) {
+            let target = require.get_amount_by(*coin_id).unwrap() as u64;
+            let candidates = sorted_candidates(unspents, *coin_id);
+            let values = get_values(&candidates, 0);
+            reserved -= need;
+            let max_inputs = MAX_INPUTS - inputs.len() - reserved;
+            let selected = branch_and_bound(&values, target, 0, max_inputs, &|_| true)
+                .or_else(|| largest_first(&values, target, max_inputs))
+                .unwrap();
+            if sum_amount(&candidates, &selected)? != target {
+                changes += 1;
+            }
+            inputs.extend(selected.into_iter().map(|index| candidates[index].clone()));
+        }
+
+        // coinId 0: include fee, each input is pessimistically signed by a new address
+        let require0 = require.get_amount_by(0).unwrap_or(0) as u64;
+        let target = self.get_target0(&require, inputs.len(), changes, get_signers(&inputs).len())?;
+        if 0 < target {
+            let candidates = sorted_candidates(unspents, 0);
+            let input_cost = self.get_input_cost()?;
+            let values = get_values(&candidates, input_cost);
+            let max_inputs = MAX_INPUTS - inputs.len();
+
+            // no change output if leftover is exactly the fee
+            let gas_price = self.gas_price;
+            let accept = |selected: &[usize]| match sum_amount(&candidates, selected) {
+                Ok(sum) if gas_price == 0 => sum == require0,
+                Ok(sum) => (sum - require0) % gas_price == 0,
+                Err(_) => false,
+            };
+            let window = self.gas_fee(IO_SIZE * 2 + SIGN_SIZE)?;
+            let change_cost = self.gas_fee(IO_SIZE)?;
+            let selected = branch_and_bound(&values, target, window, max_inputs, &accept)
+                .or_else(|| largest_first(&values, target.saturating_add(change_cost), max_inputs))
+                .ok_or(format!(
+                    "not enough unspent of coinId 0 within {} inputs",
+                    max_inputs
+                ))?;
+            inputs.extend(selected.into_iter().map(|index| candidates[index].clone()));
+        }
+
+        // dust: consolidate small unspent of the used coins in free slots
+        if 0 < self.dust_limit {
+            let input_cost = self.get_input_cost()?;
+            let mut dusts = unspents
+                .iter()
+                .filter(|(input, output)| {
+                    output.2 <= self.dust_limit
+                        && (output.1 != 0 || input_cost < output.2)
+                        && inputs.iter().any(|(_, used)| used.1 == output.1)
+                        && !inputs.iter().any(|(used, _)| used == input)
+                })
+                .cloned()
+                .collect::<Vec<(TxInput, TxOutput)>>();
+            dusts.sort_by_key(|(_, output)| output.2);
+            dusts.truncate(MAX_INPUTS - inputs.len());
+            // note: inputs were selected without dust, spend no dust if it can't pay the fee
+            let mut with_dust = inputs.clone();
+            with_dust.extend(dusts);
+            if self.get_changes(&with_dust, &self.destinations).is_ok() {
+                inputs = with_dust;
+            }
+        }
+        Ok(inputs)
+    }
+
+    /// coinId 0 effective target except inputs of coinId 0
+    fn get_target0(
+        &self,
+        require: &Balances,
+        inputs: usize,
+        changes: usize,
+        signers: usize,
+    ) -> Result<u64, String> {
+        let require0 = require.get_amount_by(0).unwrap_or(0) as u64;
+        let size = BASE_SIZE
+            + self.message.length() as u64
+            + IO_SIZE * (inputs + changes + self.destinations.len()) as u64
+            + SIGN_SIZE * signers as u64;
+        require0
+            .checked_add(self.gas_fee(size)?)
+            .ok_or("amount overflow".to_owned())
+    }
+
+    /// fee of a coinId 0 input signed by a new address
+    fn get_input_cost(&self) -> Result<u64, String> {
+        self.gas_fee(IO_SIZE + SIGN_SIZE)
+    }
+
+    /// `gas_price * size` without overflow
+    fn gas_fee(&self, size: u64) -> Result<u64, String> {
+        self.gas_price
+            .checked_mul(size)
+            .ok_or("gas_price is too large".to_owned())
+    }
+
+    /// changes of `inputs` left after `outputs` and the fee, with gas_amount
+    ///
+    /// fee is subtracted from coinId 0 change, no change if it's exactly the fee
+    fn get_changes(
+        &self,
+        inputs: &[(TxInput, TxOutput)],
+        outputs: &[TxOutput],
+    ) -> Result<(Vec<(u32, u64)>, u64), String> {
+        if inputs.is_empty() {
+            return Err("no inputs".to_owned());
+        }
+        if MAX_INPUTS < inputs.len() {
+            return Err(format!("inputs is over {}", MAX_INPUTS));
+        }
+
+        // leftover of each coin
+        let mut leftover = Balances(Vec::with_capacity(1));
+        for (_, output) in inputs.iter() {
+            leftover.add(output.1, output.2);
+        }
+        for output in outputs.iter() {
+            leftover.sub(output.1, output.2);
+        }
+        leftover.0.sort_by_key(|balance| balance.coin_id);
+        if let Some(balance) = leftover.0.iter().find(|balance| balance.amount < 0) {
+            return Err(format!("not enough inputs of coinId {}", balance.coin_id));
+        }
+        let mut changes = leftover
+            .0
+            .iter()
+            .filter(|balance| balance.coin_id != 0 && 0 < balance.amount)
+            .map(|balance| (balance.coin_id, balance.amount as u64))
+            .collect::<Vec<(u32, u64)>>();
+
+        // fee
+        let leftover0 = leftover.get_amount_by(0).unwrap_or(0) as u64;
+        let size = BASE_SIZE
+            + self.message.length() as u64
+            + IO_SIZE * (inputs.len() + outputs.len() + changes.len()) as u64
+            + SIGN_SIZE * get_signers(inputs).len() as u64;
+        let fee = self.gas_fee(size)?;
+        let fee_with_change = self.gas_fee(size + IO_SIZE)?;
+        let gas_amount = if fee_with_change < leftover0 {
+            changes.push((0, leftover0 - fee_with_change));
+            size + IO_SIZE
+        } else if leftover0 == 0 && self.gas_price == 0 {
+            size
+        } else if fee <= leftover0 && leftover0 % self.gas_price == 0 {
+            leftover0 / self.gas_price
+        } else {
+            return Err(format!(
+                "cannot pay fee {} by coinId 0 leftover {}",
+                fee, leftover0
+            ));
+        };
+        let length = outputs.len() + changes.len();
+        if length == 0 || 255 < length {
+            return Err(format!("outputs is 1 to 255 but {}", length));
+        }
+        Ok((changes, gas_amount))
+    }
+
+    /// sign a tx spending `inputs` to `outputs`, leftover goes to change outputs
+    ///
+    /// changes go to a new address of `change_account`
+    pub fn finalize(
+        &self,
+        chain: &mut Chain,
+        inputs: Vec<(TxInput, TxOutput)>,
+        mut outputs: Vec<TxOutput>,
+    ) -> Result<TxVerifiable, String> {
+        let (changes, gas_amount) = self.get_changes(&inputs, &outputs)?;
+        if !changes.is_empty() {
+            let addr = chain.get_account_address(self.change_account, true)?;
+            outputs.extend(
+                changes
+                    .into_iter()
+                    .map(|(coin_id, amount)| TxOutput(addr, coin_id, amount)),
+            );
+        }
+        let signers = get_signers(&inputs);
+
+        // body
+        let mut body = TxBody::new(
+            self.version,
+            TxType::Transfer,
+            self.time,
+            self.deadline,
+            self.gas_price,
+            gas_amount as i64,
+            self.message.clone(),
+        );
+        body.inputs = inputs.iter().map(|(input, _)| input.clone()).collect();
+        body.outputs = outputs;
+
+        // sign by each owner
+        let msg = body.to_bytes();
+        let mut signature = Vec::with_capacity(signers.len());
+        for addr in signers.iter() {
+            signature.push(chain.account.get_single_sign_by_addr(addr, &msg)?);
+        }
+
+        Ok(TxVerifiable {
+            hash: U256::from(body.hash().as_slice()),
+            body,
+            signature,
+            inputs_cache: inputs.into_iter().map(|(_, output)| output).collect(),
+        })
+    }
+}
+
+/// unspent source of sweep
+pub enum SweepSource {
+    Account(u32),
+    Addresses(Vec<Address>),
+}
+
+/// merge small unspent of the account into outputs of `min_amount` or more (sized for staking)
+///
+/// `template` gives version, time, deadline, gas_price, message and change_account
+pub fn build_consolidation(
+    chain: &mut Chain,
+    template: &TxBuilder,
+    account_id: u32,
+    coin_id: u32,
+    max_inputs: usize,
+    min_amount: u64,
+) -> Result<Vec<TxVerifiable>, String> {
+    let mut unspents = get_signable_unspents(chain)
+        .into_iter()
+        .filter(|(_, output)| is_account_addr(chain, account_id, &output.0))
+        .collect::<Vec<(TxInput, TxOutput)>>();
+    let mut smalls = unspents
+        .iter()
+        .filter(|(_, output)| output.1 == coin_id && output.2 < min_amount)
+        .cloned()
+        .collect::<Vec<(TxInput, TxOutput)>>();
+    smalls.sort_by_key(|(_, output)| output.2);
+
+    // note: keep a slot for fee input
+    let max_inputs = if coin_id == 0 {
+        max_inputs.min(MAX_INPUTS)
+    } else {
+        max_inputs.min(MAX_INPUTS - 1)
+    };
+    if max_inputs < 2 {
+        return Err("max_inputs is 2 or more".to_owned());
+    }
+    let (input_cost, fee_base) = if coin_id == 0 {
+        let size = BASE_SIZE + template.message.length() as u64 + IO_SIZE;
+        (template.get_input_cost()?, template.gas_fee(size)?)
+    } else {
+        (0, 0)
+    };
+
+    let mut smalls = smalls.into_iter();
+    let mut txs: Vec<TxVerifiable> = Vec::new();
+    loop {
+        // merge from the smallest until the output reach `min_amount`
+        let mut inputs = Vec::with_capacity(max_inputs + 1);
+        let mut sum = 0u64;
+        while inputs.len() < max_inputs && sum < min_amount.saturating_add(fee_base) {
+            match smalls.next() {
+                Some(unspent) => {
+                    sum = sum.saturating_add(unspent.1 .2.saturating_sub(input_cost));
+                    inputs.push(unspent);
+                },
+                None => break,
+            }
+        }
+        if inputs.len() < 2 {
+            break;
+        }
+        if coin_id != 0 && 0 < template.gas_price {
+            let fee_input = sorted_candidates(&unspents, 0)
+                .into_iter()
+                .next()
+                .ok_or("no coinId 0 unspent to pay merge fee".to_owned())?;
+            inputs.push(fee_input);
+        }
+        // note: changes without outputs fail only by fee, the rest is too small to pay it
+        match template.get_changes(&inputs, &[]) {
+            Err(_) if !txs.is_empty() => break,
+            Err(err) => return Err(err),
+            Ok(_) => (),
+        }
+        let tx = template.finalize(chain, inputs, vec![])?;
+
+        // fee input's change is used by the next tx
+        unspents.retain(|(input, _)| !tx.body.inputs.contains(input));
+        for (index, output) in tx.body.outputs.iter().enumerate() {
+            unspents.push((TxInput(tx.hash, index as u8), output.clone()));
+        }
+        txs.push(tx);
+    }
+    Ok(txs)
+}
+
+/// move all funds of the source to the target address, fee is subtracted from coinId 0
+pub fn build_sweep(
+    chain: &mut Chain,
+    template: &TxBuilder,
+    source: &SweepSource,
+    target: &Address,
+) -> Result<Vec<TxVerifiable>, String> {
+    let unspents = get_signable_unspents(chain)
+        .into_iter()
+        .filter(|(_, output)| match source {
+            SweepSource::Account(account_id) => is_account_addr(chain, *account_id, &output.0),
+            SweepSource::Addresses(addrs) => addrs.contains(&output.0),
+        })
+        .collect::<Vec<(TxInput, TxOutput)>>();
+    if unspents.is_empty() {
+        return Err("nothing to sweep".to_owned());
+    }
+    let (coin0, others): (Vec<(TxInput, TxOutput)>, Vec<(TxInput, TxOutput)>) =
+        unspents.into_iter().partition(|(_, output)| output.1 == 0);
+    let mut coin0 = sorted_candidates(&coin0, 0).into_iter();
+    let mut others = others.into_iter();
+
+    // note: check all txs can pay fee before signing any
+    let mut plans: Vec<(Vec<(TxInput, TxOutput)>, Vec<TxOutput>)> = Vec::new();
+    loop {
+        // note: each tx require a coinId 0 input to pay fee
+        let mut inputs = Vec::with_capacity(MAX_INPUTS);
+        inputs.extend(coin0.next());
+        inputs.extend(others.by_ref().take(MAX_INPUTS - inputs.len()));
+        inputs.extend(coin0.by_ref().take(MAX_INPUTS - inputs.len()));
+        if inputs.is_empty() {
+            break;
+        }
+
+        // all to the target
+        let mut total = Balances(Vec::with_capacity(1));
+        for (_, output) in inputs.iter() {
+            total.add(output.1, output.2);
+        }
+        total.0.sort_by_key(|balance| balance.coin_id);
+        let mut outputs = total
+            .0
+            .iter()
+            .map(|balance| TxOutput(*target, balance.coin_id, balance.amount as u64))
+            .collect::<Vec<TxOutput>>();
+        let size = BASE_SIZE
+            + template.message.length() as u64
+            + IO_SIZE * (inputs.len() + outputs.len()) as u64
+            + SIGN_SIZE * get_signers(&inputs).len() as u64;
+        let fee = template.gas_fee(size)?;
+        match outputs.iter().position(|output| output.1 == 0) {
+            Some(index) if fee < outputs[index].2 => outputs[index].2 -= fee,
+            Some(index) if fee == outputs[index].2 => {
+                outputs.remove(index);
+            },
+            _ if fee == 0 => (),
+            _ => {
+                return Err(format!(
+                    "not enough coinId 0 to pay sweep fee {} of tx {}",
+                    fee,
+                    plans.len()
+                ))
+            },
+        }
+        plans.push((inputs, outputs));
+    }
+    let mut txs = Vec::with_capacity(plans.len());
+    for (inputs, outputs) in plans {
+        txs.push(template.finalize(chain, inputs, outputs)?);
+    }
+    Ok(txs)
+}
+
+/// account's unspent which can be signed now
+fn get_signable_unspents(chain: &Chain) -> Vec<(TxInput, TxOutput)> {
+    chain
+        .get_account_unspent_iter()
+        .filter(|(_, output)| chain.account.is_signable_addr(&output.0))
+        .collect()
+}
+
+/// the address is derived from the account
+fn is_account_addr(chain: &Chain, account_id: u32, addr: &Address) -> bool {
+    match chain.account.get_path_from_addr(addr) {
+        Some((id, _, _)) => id == account_id,
+        None => false,
+    }
+}
+
+/// unspent of the coinId sorted from large to small
+fn sorted_candidates(unspents: &[(TxInput, TxOutput)], coin_id: u32) -> Vec<(TxInput, TxOutput)> {
+    let mut candidates = unspents
+        .iter()
+        .filter(|(_, output)| output.1 == coin_id)
+        .cloned()
+        .collect::<Vec<(TxInput, TxOutput)>>();
+    candidates.sort_by(|a, b| b.1 .2.cmp(&a.1 .2));
+    candidates
+}
+
+/// sum of selected amount without overflow
+fn sum_amount(candidates: &[(TxInput, TxOutput)], selected: &[usize]) -> Result<u64, String> {
+    selected
+        .iter()
+        .try_fold(0u64, |sum, index| sum.checked_add(candidates[*index].1 .2))
+        .ok_or("amount overflow".to_owned())
+}
+
+/// effective values after the input's cost, zero if negative
+fn get_values(candidates: &[(TxInput, TxOutput)], input_cost: u64) -> Vec<u64> {
+    candidates
+        .iter()
+        .map(|(_, output)| output.2.saturating_sub(input_cost))
+        .collect()
+}
+
+/// owner addresses of inputs, lock address is signed by the owner
+fn get_signers(inputs: &[(TxInput, TxOutput)]) -> Vec<Address> {
+    let mut signers: Vec<Address> = Vec::with_capacity(inputs.len());
+    for (_, output) in inputs.iter() {
+        let owner = get_owner_addr(&output.0);
+        if !signers.contains(&owner) {
+            signers.push(owner);
+        }
+    }
+    signers
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod builder {
+    use crate::chain::builder::*;
+    use crate::chain::Chain;
+    use crate::tx::*;
+    use bigint::U256;
+    use std::collections::HashMap;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// chain whose account 0 own the unspent `(coin_id, amount)`
+    fn chain_fixture(dir: &Path, unspents: &[(u32, u64)]) -> Chain {
+        let sk = Some(b"1qwq53lmi8rapvcciqmuiorxdie5irwmw1dccbegkze9vjdpy7mz6nsd6j991a6b".to_vec());
+        let mut chain = Chain::new(dir, &sk, 100, false, false).unwrap();
+        let addr = chain.get_account_address(0, true).unwrap();
+        let mut cur = chain.tables.transaction();
+        for (index, (coin_id, amount)) in unspents.iter().enumerate() {
+            let txhash = U256::from(index as u64 + 1);
+            cur.write_addr_index(&TxOutput(addr, *coin_id, *amount), &txhash, 0)
+                .unwrap();
+        }
+        cur.commit().unwrap();
+        chain
+    }
+
+    /// inputs = outputs + fee(coinId 0) and gas_amount cover the signed size
+    fn assert_finalized(tx: &TxVerifiable) {
+        let mut sums: HashMap<u32, i128> = HashMap::new();
+        for output in tx.inputs_cache.iter() {
+            *sums.entry(output.1).or_insert(0) += output.2 as i128;
+        }
+        for output in tx.body.outputs.iter() {
+            *sums.entry(output.1).or_insert(0) -= output.2 as i128;
+        }
+        *sums.entry(0).or_insert(0) -= (tx.body.gas_price * tx.body.gas_amount as u64) as i128;
+        assert!(sums.values().all(|sum| *sum == 0), "unbalanced {:?}", sums);
+        let size = tx.body.get_size() as u64 + SIGN_SIZE * tx.signature.len() as u64;
+        assert!(size <= tx.body.gas_amount as u64);
+        assert_eq!(tx.body.inputs.len(), tx.inputs_cache.len());
+    }
+
+    fn transfer(destinations: Vec<TxOutput>, gas_price: u64) -> TxBuilder {
+        TxBuilder {
+            version: 0,
+            time: 0,
+            deadline: 10800,
+            destinations,
+            gas_price,
+            message: TxMessage::Nothing,
+            change_account: 0,
+            dust_limit: 0,
+        }
+    }
+
+    #[test]
+    fn build_transfer() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let unspents = vec![(0, 100000), (0, 50000), (0, 20000), (1, 700), (1, 300)];
+        let mut chain = chain_fixture(dir.as_ref(), &unspents);
+
+        // coinId 0 & 1 with change
+        let dest = [1u8; 21];
+        let builder = transfer(vec![TxOutput(dest, 0, 60000), TxOutput(dest, 1, 800)], 10);
+        let tx = builder.build(&mut chain).unwrap();
+        assert_finalized(&tx);
+        assert!(tx.body.outputs.contains(&TxOutput(dest, 0, 60000)));
+        assert!(tx.body.outputs.contains(&TxOutput(dest, 1, 800)));
+        let changes = tx
+            .body
+            .outputs
+            .iter()
+            .filter(|output| output.0 != dest)
+            .collect::<Vec<&TxOutput>>();
+        assert!(changes.iter().any(|output| output.1 == 1 && output.2 == 200));
+        assert!(changes.iter().any(|output| output.1 == 0));
+        let change_addr = changes[0].0;
+        assert!(changes.iter().all(|output| output.0 == change_addr));
+        assert!(chain.account.get_path_from_addr(&change_addr).is_some());
+
+        // a new change address each tx
+        let tx = builder.build(&mut chain).unwrap();
+        assert_finalized(&tx);
+        assert!(tx.body.outputs.iter().all(|output| output.0 != change_addr));
+
+        // not enough
+        let builder = transfer(vec![TxOutput(dest, 1, 1001)], 10);
+        assert!(builder.build(&mut chain).is_err());
+        let builder = transfer(vec![TxOutput(dest, 0, 170000)], 10);
+        assert!(builder.build(&mut chain).is_err());
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn input_limit() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let mut unspents = vec![(1, 1); 200];
+        unspents.extend(vec![(0, 1000); 100]);
+        let mut chain = chain_fixture(dir.as_ref(), &unspents);
+        let dest = [1u8; 21];
+
+        // 60 inputs of coinId 0 is ok
+        let tx = transfer(vec![TxOutput(dest, 0, 60000)], 0)
+            .build(&mut chain)
+            .unwrap();
+        assert_finalized(&tx);
+        assert_eq!(tx.body.inputs.len(), 60);
+
+        // 200 inputs of coinId 1 and 60 inputs of coinId 0
+        let builder = transfer(vec![TxOutput(dest, 0, 60000), TxOutput(dest, 1, 200)], 0);
+        let err = builder.build(&mut chain).unwrap_err();
+        assert!(err.contains("require 260 inputs over 255"), "{}", err);
+        let tx = transfer(vec![TxOutput(dest, 0, 50000), TxOutput(dest, 1, 200)], 0)
+            .build(&mut chain)
+            .unwrap();
+        assert_finalized(&tx);
+        assert_eq!(tx.body.inputs.len(), 250);
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn dust_inputs() {
+        let tmp = tempdir().unwrap();
+        let dest = [1u8; 21];
+        let mut builder = transfer(vec![TxOutput(dest, 1, 500)], 1);
+        builder.dust_limit = 1;
+
+        // coinId 1 dust are spent with the payment
+        let dir = tmp.path().join("database0");
+        let mut chain = chain_fixture(dir.as_ref(), &[(0, 10000), (1, 500), (1, 1), (1, 1), (1, 1)]);
+        let tx = builder.build(&mut chain).unwrap();
+        assert_finalized(&tx);
+        assert_eq!(tx.body.inputs.len(), 5);
+        assert!(tx
+            .body
+            .outputs
+            .iter()
+            .any(|output| output.1 == 1 && output.2 == 3));
+        chain.tables.close_and_destroy();
+
+        // coinId 0 leftover cannot pay the dust's fee, spend no dust
+        let dir = tmp.path().join("database1");
+        let mut unspents = vec![(0, 400), (1, 500)];
+        unspents.extend(vec![(1, 1); 10]);
+        let mut chain = chain_fixture(dir.as_ref(), &unspents);
+        let tx = builder.build(&mut chain).unwrap();
+        assert_finalized(&tx);
+        assert_eq!(tx.body.inputs.len(), 2);
+        assert!(!tx
+            .body
+            .outputs
+            .iter()
+            .any(|output| output.1 == 1 && output.0 != dest));
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn propose_consolidation() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let mut unspents = vec![(1, 1); 300];
+        unspents.extend(vec![(0, 100000); 5]);
+        let mut chain = chain_fixture(dir.as_ref(), &unspents);
+        let dest = [1u8; 21];
+
+        // no merge required
+        let builder = transfer(vec![TxOutput(dest, 1, 200)], 10);
+        assert!(builder.propose_consolidation(&mut chain).unwrap().is_empty());
+
+        // merge coinId 1 paid by coinId 0
+        let builder = transfer(vec![TxOutput(dest, 1, 280)], 10);
+        let txs = builder.propose_consolidation(&mut chain).unwrap();
+        for tx in txs.iter() {
+            assert_finalized(tx);
+            assert!(tx.body.inputs.len() <= MAX_INPUTS);
+            assert!(tx.body.outputs.iter().all(|output| output.0 != dest));
+        }
+        assert_eq!(txs.len(), 1);
+        assert!(txs[0]
+            .body
+            .outputs
+            .iter()
+            .any(|output| output.1 == 1 && output.2 == 254));
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn consolidate_small_unspent() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let mut unspents = vec![(0, 1000); 10];
+        unspents.extend(vec![(1, 10); 10]);
+        unspents.push((0, 100000));
+        let mut chain = chain_fixture(dir.as_ref(), &unspents);
+        let template = transfer(vec![], 1);
+
+        // coinId 0 pay own fee, merged output reach min_amount
+        let txs = chain.build_consolidation(0, 0, 255, 5000, &template).unwrap();
+        assert_eq!(txs.len(), 2);
+        for tx in txs.iter() {
+            assert_finalized(tx);
+            assert!(tx.inputs_cache.iter().all(|output| output.2 == 1000));
+        }
+        assert_eq!(txs[0].body.inputs.len(), 6);
+        assert!(5000 <= txs[0].body.outputs[0].2);
+        assert_eq!(txs[1].body.inputs.len(), 4);
+
+        // coinId 1 paid by coinId 0, the next tx spend the fee input's change
+        let txs = chain.build_consolidation(0, 1, 255, 50, &template).unwrap();
+        assert_eq!(txs.len(), 2);
+        for tx in txs.iter() {
+            assert_finalized(tx);
+            assert_eq!(tx.body.inputs.len(), 6);
+            assert!(tx.body.outputs.contains(&TxOutput(tx.body.outputs[0].0, 1, 50)));
+        }
+        let change = txs[0]
+            .body
+            .outputs
+            .iter()
+            .position(|output| output.1 == 0)
+            .unwrap();
+        assert!(txs[1].body.inputs.contains(&TxInput(txs[0].hash, change as u8)));
+        chain.tables.close_and_destroy();
+
+        // the rest cannot pay fee
+        let dir = tmp.path().join("database1");
+        let mut chain = chain_fixture(dir.as_ref(), &vec![(0, 100); 7]);
+        let txs = chain.build_consolidation(0, 0, 5, 100000, &template).unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].body.inputs.len(), 5);
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn sweep_to_target() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let mut unspents = vec![(0, 100000), (2, 300)];
+        unspents.extend(vec![(1, 1); 300]);
+        let mut chain = chain_fixture(dir.as_ref(), &unspents);
+        let target = [1u8; 21];
+        let template = transfer(vec![], 10);
+
+        // a coinId 0 input for each tx is required
+        let err = chain
+            .build_sweep(&SweepSource::Account(0), &target, &template)
+            .unwrap_err();
+        assert!(err.contains("not enough coinId 0"), "{}", err);
+        chain.tables.close_and_destroy();
+
+        let dir = tmp.path().join("database1");
+        unspents.push((0, 50000));
+        let mut chain = chain_fixture(dir.as_ref(), &unspents);
+        let txs = chain
+            .build_sweep(&SweepSource::Account(0), &target, &template)
+            .unwrap();
+        assert_eq!(txs.len(), 2);
+        let mut swept: HashMap<u32, u64> = HashMap::new();
+        let mut fee = 0;
+        for tx in txs.iter() {
+            assert_finalized(tx);
+            assert!(tx.body.outputs.iter().all(|output| output.0 == target));
+            for output in tx.body.outputs.iter() {
+                *swept.entry(output.1).or_insert(0) += output.2;
+            }
+            fee += tx.body.gas_price * tx.body.gas_amount as u64;
+        }
+        assert_eq!(swept[&0] + fee, 150000);
+        assert_eq!(swept[&1], 300);
+        assert_eq!(swept[&2], 300);
+
+        // nothing in other account
+        assert!(chain
+            .build_sweep(&SweepSource::Account(1), &target, &template)
+            .is_err());
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn coin_selection() {
+        let values = vec![50, 30, 20, 10, 5];
+
+        // exact match without change
+        let selected = branch_and_bound(&values, 35, 0, 255, &|_| true).unwrap();
+        assert_eq!(selected, vec![1, 4]);
+        let selected = branch_and_bound(&values, 60, 0, 255, &|_| true).unwrap();
+        assert_eq!(selected.iter().map(|i| values[*i]).sum::<u64>(), 60);
+
+        // least waste in the window
+        let selected = branch_and_bound(&values, 33, 3, 255, &|_| true).unwrap();
+        assert_eq!(selected.iter().map(|i| values[*i]).sum::<u64>(), 35);
+        assert_eq!(branch_and_bound(&values, 33, 1, 255, &|_| true), None);
+
+        // rejected by accept & input limit
+        assert_eq!(branch_and_bound(&values, 35, 0, 255, &|_| false), None);
+        assert_eq!(branch_and_bound(&values, 115, 0, 4, &|_| true), None);
+
+        // largest-first fallback
+        assert_eq!(largest_first(&values, 33, 255), Some(vec![0]));
+        assert_eq!(largest_first(&values, 81, 255), Some(vec![0, 1, 2]));
+        assert_eq!(largest_first(&values, 81, 2), None);
+        assert_eq!(largest_first(&values, 116, 255), None);
+
+        // no overflow by large values
+        let values = vec![u64::max_value() - 5, 10];
+        assert_eq!(
+            branch_and_bound(&values, u64::max_value() - 3, 0, 255, &|_| true),
+            None
+        );
+        assert_eq!(
+            branch_and_bound(&values, 10, u64::max_value(), 255, &|_| true),
+            Some(vec![1])
+        );
+        assert_eq!(largest_first(&values, u64::max_value() - 3, 255), None);
+    }
+}
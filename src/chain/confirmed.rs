@@ -28,10 +28,10 @@ impl Confirmed {
         let (block, txs) = tables
             .read_full_block(&hash)?
             .expect("already write to table but not found block");
-        let score = block.calc_score();
         let mut input_size = 0;
         // let mut output_size = 0;
-        while let Some(body) = txs.iter().next() {
+        let mut iter = txs.iter();
+        while let Some(body) = iter.next() {
             input_size += body.inputs.len();
             // output_size += body.outputs.len();
         }
@@ -42,14 +42,29 @@ impl Confirmed {
             inputs.extend(tx.body.inputs.into_iter());
             // outputs.extend(tx.body.outputs.into_iter());
         }
-        Ok(Confirmed {
+        Ok(Confirmed::from_block(hash, block, coinbase, inputs))
+    }
+
+    /// note: used when the block isn't committed to tables yet
+    fn from_txs(block: Block, txs: &[TxVerifiable]) -> Self {
+        let hash = block.header.hash();
+        let inputs = txs
+            .iter()
+            .skip(1)
+            .flat_map(|tx| tx.body.inputs.iter().cloned())
+            .collect();
+        Confirmed::from_block(hash, block, txs[0].clone(), inputs)
+    }
+
+    fn from_block(hash: U256, block: Block, coinbase: TxVerifiable, inputs: Vec<TxInput>) -> Self {
+        let score = block.calc_score();
+        Confirmed {
             hash,
             block,
             score,
             coinbase,
             inputs,
-            // outputs,
-        })
+        }
     }
 }
 
@@ -292,15 +307,15 @@ impl ConfirmedBuilder {
     pub fn push_new_block(
         &mut self,
         block: Block,
-        tables: &Tables,
+        txs: &[TxVerifiable],
     ) -> Result<(BlockHashVec, BlockHashVec), String> {
         // get best_chain before to compare after
         let mut best_chain_before = self.get_best_chain();
 
         // insert new block
-        let hash = block.header.hash();
-        let confirmed = Confirmed::new(tables, hash)?;
-        self.tree.insert(hash, confirmed);
+        // note: the block is written by the uncommitted cursor, don't read from tables
+        let confirmed = Confirmed::from_txs(block, txs);
+        self.tree.insert(confirmed.hash, confirmed);
 
         // get best_chain after
         let mut best_chain_after = self.get_best_chain();
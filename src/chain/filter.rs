@@ -0,0 +1,245 @@
+use crate::tx::TxVerifiable;
+use crate::utils::*;
+use bigint::U256;
+#[allow(deprecated)]
+use std::hash::{Hasher, SipHasher};
+
+/// Golomb-Rice coding bit parameter (same as BIP158 basic filter)
+static FILTER_P: u8 = 19;
+
+/// inverse of false positive rate (same as BIP158 basic filter)
+static FILTER_M: u64 = 784931;
+
+type Address = [u8; 21];
+
+/// SipHash-2-4 keyed by the first 16 bytes of blockhash
+#[allow(deprecated)]
+fn hash_to_range(key: &[u8; 32], item: &[u8], range: u64) -> u64 {
+    let k0 = bytes_to_u64(&key[0..8]);
+    let k1 = bytes_to_u64(&key[8..16]);
+    let mut hasher = SipHasher::new_with_keys(k0, k1);
+    hasher.write(item);
+    // map uniformly to [0, range)
+    ((hasher.finish() as u128 * range as u128) >> 64) as u64
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    pos: usize, // written bits
+}
+
+impl BitWriter {
+    fn write_bit(&mut self, bit: bool) {
+        if self.pos % 8 == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 0x80 >> (self.pos % 8);
+        }
+        self.pos += 1;
+    }
+
+    fn write_bits(&mut self, value: u64, len: u8) {
+        for i in (0..len).rev() {
+            self.write_bit((value >> i) & 1 == 1);
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize, // read bits
+}
+
+impl BitReader<'_> {
+    fn read_bit(&mut self) -> Result<bool, String> {
+        let byte = self
+            .bytes
+            .get(self.pos / 8)
+            .ok_or("filter is shorter than N".to_owned())?;
+        let bit = byte & (0x80 >> (self.pos % 8)) != 0;
+        self.pos += 1;
+        Ok(bit)
+    }
+
+    fn read_bits(&mut self, len: u8) -> Result<u64, String> {
+        let mut value = 0u64;
+        for _ in 0..len {
+            value = (value << 1) | self.read_bit()? as u64;
+        }
+        Ok(value)
+    }
+}
+
+/// compact block filter (BIP158-style Golomb-coded set)
+///
+/// items are output addresses, spent input outpoints and spent input addresses
+#[derive(Clone, PartialEq, Debug)]
+pub struct BlockFilter {
+    pub n: u32,
+    pub data: Vec<u8>,
+}
+
+impl BlockFilter {
+    pub fn from_block(blockhash: &U256, txs: &Vec<TxVerifiable>) -> Self {
+        let mut items: Vec<Vec<u8>> = Vec::new();
+        for tx in txs.iter() {
+            for output in tx.body.outputs.iter() {
+                items.push(output.0.to_vec());
+            }
+            for input in tx.body.inputs.iter() {
+                items.push(input.to_bytes().to_vec());
+            }
+            // note: coinbase of PoW don't have inputs_cache
+            for output in tx.inputs_cache.iter() {
+                items.push(output.0.to_vec());
+            }
+        }
+        items.sort();
+        items.dedup();
+        BlockFilter::from_items(blockhash, &items)
+    }
+
+    pub fn from_items(blockhash: &U256, items: &[Vec<u8>]) -> Self {
+        let n = items.len() as u32;
+        let key = u256_to_bytes(blockhash);
+        let range = n as u64 * FILTER_M;
+        let mut values = items
+            .iter()
+            .map(|item| hash_to_range(&key, item, range))
+            .collect::<Vec<u64>>();
+        values.sort_unstable();
+
+        // Golomb-Rice coding of the deltas
+        let mut writer = BitWriter {
+            bytes: Vec::new(),
+            pos: 0,
+        };
+        let mut last = 0u64;
+        for value in values {
+            let delta = value - last;
+            let mut quotient = delta >> FILTER_P;
+            while 0 < quotient {
+                writer.write_bit(true);
+                quotient -= 1;
+            }
+            writer.write_bit(false);
+            writer.write_bits(delta, FILTER_P);
+            last = value;
+        }
+        BlockFilter {
+            n,
+            data: writer.bytes,
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 4 {
+            return Err("filter is more than 4 bytes".to_owned());
+        }
+        Ok(BlockFilter {
+            n: bytes_to_u32(&bytes[0..4]),
+            data: bytes[4..].to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // [N u32][golomb coded bits]
+        let mut bytes = Vec::with_capacity(4 + self.data.len());
+        bytes.extend_from_slice(&u32_to_bytes(self.n));
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// check any item is included, false positive rate is 1/M
+    pub fn match_any(&self, blockhash: &U256, items: &[Vec<u8>]) -> Result<bool, String> {
+        if self.n == 0 || items.is_empty() {
+            return Ok(false);
+        }
+        let key = u256_to_bytes(blockhash);
+        let range = self.n as u64 * FILTER_M;
+        let mut queries = items
+            .iter()
+            .map(|item| hash_to_range(&key, item, range))
+            .collect::<Vec<u64>>();
+        queries.sort_unstable();
+
+        // merge two sorted lists
+        let mut reader = BitReader {
+            bytes: &self.data,
+            pos: 0,
+        };
+        let mut value = 0u64;
+        let mut queries = queries.into_iter().peekable();
+        for _ in 0..self.n {
+            let mut quotient = 0u64;
+            while reader.read_bit()? {
+                quotient += 1;
+            }
+            value += (quotient << FILTER_P) + reader.read_bits(FILTER_P)?;
+            while let Some(query) = queries.peek() {
+                if *query < value {
+                    queries.next();
+                } else if *query == value {
+                    return Ok(true);
+                } else {
+                    break;
+                }
+            }
+            if queries.peek().is_none() {
+                return Ok(false);
+            }
+        }
+        Ok(false)
+    }
+
+    /// check any address is used by the block's outputs or spent inputs
+    pub fn match_addrs(&self, blockhash: &U256, addrs: &[Address]) -> Result<bool, String> {
+        let items = addrs.iter().map(|addr| addr.to_vec()).collect::<Vec<Vec<u8>>>();
+        self.match_any(blockhash, &items)
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod filter {
+    use crate::chain::filter::BlockFilter;
+    use bigint::U256;
+
+    #[test]
+    fn golomb_coded_set() {
+        let blockhash = U256::from(123456789u64);
+        let items = (0..100u32)
+            .map(|i| {
+                let mut addr = [0u8; 21];
+                addr[17..21].copy_from_slice(&i.to_le_bytes());
+                addr
+            })
+            .collect::<Vec<[u8; 21]>>();
+        let vecs = items.iter().map(|addr| addr.to_vec()).collect::<Vec<Vec<u8>>>();
+        let filter = BlockFilter::from_items(&blockhash, &vecs);
+        let filter = BlockFilter::from_bytes(&filter.to_bytes()).unwrap();
+        assert_eq!(filter.n, 100);
+
+        // all included items match
+        for addr in items.iter() {
+            assert_eq!(filter.match_addrs(&blockhash, &[addr.clone()]), Ok(true));
+        }
+
+        // unrelated items almost never match
+        let mut unrelated = [0u8; 21];
+        unrelated[0] = 255;
+        let mut matched = 0;
+        for i in 0..1000u32 {
+            unrelated[17..21].copy_from_slice(&i.to_le_bytes());
+            if filter.match_addrs(&blockhash, &[unrelated]).unwrap() {
+                matched += 1;
+            }
+        }
+        assert!(matched < 5);
+
+        // empty filter
+        let empty = BlockFilter::from_items(&blockhash, &[]);
+        assert_eq!(empty.match_addrs(&blockhash, &[items[0]]), Ok(false));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::balance::BalanceMovement;
+use crate::chain::utils::big_endian_to_u32;
 use crate::chain::{account::AccountAddrIter, confirmed::BlockHashVec, unconfirmed::UnconfirmedIter, Chain};
-use crate::tx::{TxInput, TxOutput};
+use crate::tx::{to_lock_addr, MintCoin, TxInput, TxOutput};
 use crate::utils::*;
 use bigint::U256;
 use rocksdb::DBIterator;
@@ -40,7 +41,35 @@ impl Iterator for AddrIter<'_> {
     }
 }
 
-/// return address specified unspent
+/// iterate tables mint history: (height, index, txhash, amount, mint)
+pub struct MintIter<'a> {
+    pub coin_id: u32,
+    pub iter: DBIterator<'a>,
+}
+
+impl Iterator for MintIter<'_> {
+    type Item = (u32, u32, U256, i64, MintCoin);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // [coin_id u32][height u32][index u32] -> [txhash 32b][amount i64][mint ?]
+        match self.iter.next() {
+            Some((key, value)) => {
+                if big_endian_to_u32(&key[0..4]) == self.coin_id {
+                    let height = big_endian_to_u32(&key[4..4 + 4]);
+                    let index = big_endian_to_u32(&key[8..8 + 4]);
+                    let txhash = U256::from(&value[0..32]);
+                    let amount = bytes_to_i64(&value[32..32 + 8]);
+                    let mint = MintCoin::from_bytes(&value[40..]).expect("broken mint table?");
+                    Some((height, index, txhash, amount, mint))
+                } else {
+                    None
+                }
+            },
+            None => None,
+        }
+    }
+}
+
 /// note: confirmed_iter is ordered **old to new**
 pub struct UnspentIter<'a> {
     pub table_iter: AddrIter<'a>,
@@ -163,6 +192,8 @@ impl Iterator for UnspentIter<'_> {
 pub struct AccountUnspentIter<'a> {
     pub addr_iter: AccountAddrIter<'a>,
     pub unspent_iter: Option<UnspentIter<'a>>,
+    /// iterate lock address's unspent instead
+    pub lock: bool,
     pub chain: &'a Chain,
 }
 
@@ -179,6 +210,7 @@ impl Iterator for AccountUnspentIter<'_> {
             } else {
                 match self.addr_iter.next() {
                     Some(addr) => {
+                        let addr = if self.lock { to_lock_addr(&addr) } else { addr };
                         let best_chain_rev: BlockHashVec = self
                             .chain
                             .best_chain
@@ -0,0 +1,202 @@
+use crate::tx::{MintCoin, MintParams, MintSetting, TxMessage, TxType, TxVerifiable};
+use bigint::U256;
+
+type Address = [u8; 21];
+
+/// mint history of a coin (height, txhash, amount, mint)
+/// note: height is none if the tx isn't finalized
+pub type MintHistory = Vec<(Option<u32>, U256, i64, MintCoin)>;
+
+/// get mint message and issued amount (outputs - inputs of the coin) of the mint tx
+pub fn get_mint_info(tx: &TxVerifiable) -> Result<(MintCoin, i64), String> {
+    if tx.body.txtype != TxType::Mint {
+        return Err(format!("not mint tx {:?}", tx.body.txtype));
+    }
+    let coin = match &tx.body.message {
+        TxMessage::Byte(bytes) => MintCoin::from_bytes(bytes)?,
+        _ => return Err("mint tx's message is Byte type".to_owned()),
+    };
+    if coin.coin_id == 0 {
+        return Err("coinId 0 is base coin and cannot mint".to_owned());
+    }
+    // note: amounts are u64, sum up by i128 without overflow
+    let mut amount = 0i128;
+    for output in tx.body.outputs.iter().filter(|output| output.1 == coin.coin_id) {
+        amount += output.2 as i128;
+    }
+    for output in tx.inputs_cache.iter().filter(|output| output.1 == coin.coin_id) {
+        amount -= output.2 as i128;
+    }
+    if amount < i64::min_value() as i128 || i64::max_value() as i128 < amount {
+        return Err(format!("mint amount {} is out of range", amount));
+    }
+    Ok((coin, amount as i64))
+}
+
+/// supply after mint (or burn), none if out of range
+fn get_next_supply(supply: u64, amount: i64) -> Option<u64> {
+    let next = supply as i128 + amount as i128;
+    if 0 <= next && next <= u64::max_value() as i128 {
+        Some(next as u64)
+    } else {
+        None
+    }
+}
+
+/// newest coin state folded from mint history
+#[derive(Clone, PartialEq, Debug)]
+pub struct MintState {
+    pub coin_id: u32,
+    pub params: MintParams,
+    pub setting: MintSetting,
+    /// total supply
+    pub supply: u64,
+    /// last mint txhash
+    pub last_hash: U256,
+}
+
+impl MintState {
+    /// check issuer authority and setting rules
+    pub fn check(
+        state: Option<&MintState>,
+        coin: &MintCoin,
+        amount: i64,
+        signers: &[Address],
+    ) -> Result<(), String> {
+        match state {
+            // first mint
+            None => {
+                let params = coin
+                    .params
+                    .as_ref()
+                    .ok_or("first mint require params".to_owned())?;
+                if coin.setting.is_none() {
+                    return Err("first mint require setting".to_owned());
+                }
+                if !signers.contains(&params.owner) {
+                    return Err("first mint require owner's signature".to_owned());
+                }
+                if amount < 0 {
+                    return Err("first mint cannot burn".to_owned());
+                }
+            },
+            // additional mint
+            Some(state) => {
+                if !signers.contains(&state.params.owner) {
+                    return Err("mint require owner's signature".to_owned());
+                }
+                if 0 < amount && !state.setting.additional_issue {
+                    return Err("additional issue is disabled".to_owned());
+                }
+                if get_next_supply(state.supply, amount).is_none() {
+                    return Err("burn over total supply or supply overflow".to_owned());
+                }
+                if coin.params.is_some() && !state.setting.change_params {
+                    return Err("params change is disabled".to_owned());
+                }
+                if let Some(setting) = coin.setting.as_ref() {
+                    if (setting.additional_issue && !state.setting.additional_issue)
+                        || (setting.change_params && !state.setting.change_params)
+                    {
+                        return Err("cannot enable disabled setting".to_owned());
+                    }
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// apply already checked mint to the state
+    pub fn update(state: &mut Option<MintState>, coin: &MintCoin, amount: i64, txhash: &U256) {
+        match state {
+            None => {
+                *state = Some(MintState {
+                    coin_id: coin.coin_id,
+                    params: coin.params.clone().expect("first mint without params?"),
+                    setting: coin.setting.clone().expect("first mint without setting?"),
+                    supply: amount as u64,
+                    last_hash: txhash.clone(),
+                });
+            },
+            Some(state) => {
+                if let Some(params) = coin.params.as_ref() {
+                    state.params = params.clone();
+                }
+                if let Some(setting) = coin.setting.as_ref() {
+                    state.setting = setting.clone();
+                }
+                state.supply = get_next_supply(state.supply, amount).expect("checked supply overflow?");
+                state.last_hash = txhash.clone();
+            },
+        }
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod mint {
+    use crate::chain::mint::*;
+    use crate::tx::*;
+    use bigint::U256;
+
+    #[test]
+    fn authority() {
+        let owner = [1u8; 21];
+        let other = [2u8; 21];
+        let coin = MintCoin {
+            coin_id: 1,
+            params: Some(MintParams {
+                owner,
+                name: "coin".to_owned(),
+                unit: "C".to_owned(),
+                digit: 8,
+                description: "".to_owned(),
+            }),
+            setting: Some(MintSetting {
+                additional_issue: true,
+                change_params: false,
+            }),
+        };
+
+        // first mint
+        assert!(MintState::check(None, &coin, 100, &[other]).is_err());
+        assert!(MintState::check(None, &coin, 100, &[owner]).is_ok());
+        let mut state = None;
+        MintState::update(&mut state, &coin, 100, &U256::from(1u32));
+        assert_eq!(state.as_ref().unwrap().supply, 100);
+
+        // additional issue
+        let add = MintCoin {
+            coin_id: 1,
+            params: None,
+            setting: None,
+        };
+        assert!(MintState::check(state.as_ref(), &add, 50, &[other]).is_err());
+        assert!(MintState::check(state.as_ref(), &add, 50, &[owner]).is_ok());
+        assert!(MintState::check(state.as_ref(), &add, -101, &[owner]).is_err());
+
+        // supply overflow
+        let mut full = state.clone();
+        full.as_mut().unwrap().supply = u64::max_value() - 10;
+        assert!(MintState::check(full.as_ref(), &add, 10, &[owner]).is_ok());
+        assert!(MintState::check(full.as_ref(), &add, 11, &[owner]).is_err());
+
+        // params change is disabled
+        assert!(MintState::check(state.as_ref(), &coin, 0, &[owner]).is_err());
+
+        // disable additional issue
+        let disable = MintCoin {
+            coin_id: 1,
+            params: None,
+            setting: Some(MintSetting {
+                additional_issue: false,
+                change_params: false,
+            }),
+        };
+        assert!(MintState::check(state.as_ref(), &disable, 50, &[owner]).is_ok());
+        MintState::update(&mut state, &disable, 50, &U256::from(2u32));
+        assert_eq!(state.as_ref().unwrap().supply, 150);
+        assert!(MintState::check(state.as_ref(), &add, 1, &[owner]).is_err());
+        assert!(MintState::check(state.as_ref(), &coin, 0, &[owner]).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use crate::chain::account::BIP32_HARDEN;
+use crate::chain::secret::zeroize_priv_key;
+use bip39::{Language, Mnemonic, MnemonicType, Seed};
+use hdwallet::traits::Serialize;
+use hdwallet::{ExtendedPrivKey, KeyIndex};
+use zeroize::Zeroizing;
+
+/// generate a new english mnemonic phrase of 12, 15, 18, 21 or 24 words
+pub fn generate_mnemonic(words: usize) -> Result<String, String> {
+    let mnemonic_type = MnemonicType::for_word_count(words).map_err(|err| err.to_string())?;
+    let mnemonic = Mnemonic::new(mnemonic_type, Language::English);
+    Ok(mnemonic.into_phrase())
+}
+
+/// check words are in wordlist and the checksum is correct
+pub fn validate_mnemonic(phrase: &str) -> Result<(), String> {
+    Mnemonic::validate(phrase, Language::English).map_err(|err| err.to_string())
+}
+
+/// 64 bytes BIP39 seed (PBKDF2-HMAC-SHA512 2048 rounds), passphrase is empty if none
+///
+/// note: mnemonic, seed and the output are zeroized on drop
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+    let mnemonic = Mnemonic::from_phrase(phrase, Language::English).map_err(|err| err.to_string())?;
+    let seed = Seed::new(&mnemonic, passphrase);
+    Ok(Zeroizing::new(seed.as_bytes().to_vec()))
+}
+
+/// derive `m/44'/CoinType'` from the seed, same root_key of `AccountBuilder`
+///
+/// note: intermediate keys are zeroized, zeroize the output by `zeroize_priv_key` after use
+pub fn seed_to_root_key(seed: &[u8], coin_type: u32) -> Result<ExtendedPrivKey, String> {
+    let map_err = |err| format!("cannot derive root key: {:?}", err);
+    let mut master = ExtendedPrivKey::with_seed(seed).map_err(map_err)?;
+    let purpose = master.derive_private_key(KeyIndex::Hardened(BIP32_HARDEN + 44));
+    zeroize_priv_key(&mut master);
+    let mut purpose = purpose.map_err(map_err)?;
+    let root_key = purpose.derive_private_key(KeyIndex::Hardened(BIP32_HARDEN + coin_type));
+    zeroize_priv_key(&mut purpose);
+    root_key.map_err(map_err)
+}
+
+/// 64 bytes extended secret key `m/44'/CoinType'` used by `Chain::new`
+pub fn mnemonic_to_root_key(
+    phrase: &str,
+    passphrase: &str,
+    coin_type: u32,
+) -> Result<Zeroizing<Vec<u8>>, String> {
+    let seed = mnemonic_to_seed(phrase, passphrase)?;
+    let mut root_key = seed_to_root_key(&seed, coin_type)?;
+    let secret = Zeroizing::new(root_key.serialize());
+    zeroize_priv_key(&mut root_key);
+    Ok(secret)
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod mnemonic {
+    use crate::chain::mnemonic::*;
+
+    #[test]
+    fn mnemonic_seed() {
+        let phrase = generate_mnemonic(24).unwrap();
+        assert_eq!(phrase.split_whitespace().count(), 24);
+        assert!(validate_mnemonic(&phrase).is_ok());
+        assert!(generate_mnemonic(13).is_err());
+
+        // checksum
+        let valid =
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let invalid =
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+        assert!(validate_mnemonic(valid).is_ok());
+        assert!(validate_mnemonic(invalid).is_err());
+
+        // BIP39 test vector
+        let seed = mnemonic_to_seed(valid, "TREZOR").unwrap();
+        assert_eq!(hex::encode(&seed[0..8]), "c55257c360c07c72");
+        assert_ne!(*seed, *mnemonic_to_seed(valid, "").unwrap());
+
+        // deterministic `m/44'/CoinType'`
+        let key = mnemonic_to_root_key(valid, "", 0).unwrap();
+        assert_eq!(key.len(), 32 + 32);
+        assert_eq!(*key, *mnemonic_to_root_key(valid, "", 0).unwrap());
+        assert_ne!(*key, *mnemonic_to_root_key(valid, "", 1).unwrap());
+    }
+}
@@ -1,22 +1,34 @@
 pub mod account;
+pub mod builder;
 pub mod confirmed;
+pub mod filter;
 pub mod iters;
+pub mod mint;
+pub mod mnemonic;
+pub mod secret;
+pub mod spv;
 pub mod tables;
 pub mod unconfirmed;
 pub mod utils;
+pub mod validate;
 
 use crate::balance::Balances;
-use crate::block::{Block, BlockFlag};
+use crate::block::{Block, BlockFlag, BlockHeader};
 use crate::chain::confirmed::BlockHashVec;
 use crate::chain::{
     account::AccountBuilder,
+    builder::{SweepSource, TxBuilder},
     confirmed::ConfirmedBuilder,
+    filter::BlockFilter,
     iters::*,
+    mint::*,
     tables::*,
     unconfirmed::UnconfirmedBuilder,
 };
-use crate::tx::{TxInput, TxOutput, TxRecoded, TxVerifiable};
+use crate::tx::{is_lock_output, BlockTxs, PartialTx, TxInput, TxOutput, TxRecoded, TxType, TxVerifiable};
+use crate::utils::merkle_branch;
 use bigint::U256;
+use std::collections::HashMap;
 use std::path::Path;
 
 lazy_static! {
@@ -28,6 +40,9 @@ lazy_static! {
 }
 type Address = [u8; 21];
 
+// note: commit rescan result per this blocks
+const RESCAN_BATCH_BLOCKS: usize = 100;
+
 pub struct Chain {
     pub tables: Tables,
     pub confirmed: ConfirmedBuilder,
@@ -72,16 +87,19 @@ impl Chain {
         };
 
         // account
-        let account = if tables.initialized {
-            let sk = sk.as_ref().ok_or("account init require sk".to_owned())?;
+        // note: no sk means watch-only node
+        let account = if tables.initialized && sk.is_some() {
+            let sk = sk.as_ref().unwrap();
             let mut cur = tables.transaction();
-            let account = AccountBuilder::new(sk, &mut cur);
+            let account = AccountBuilder::new(sk, &mut cur).map_err(|err| format!("{:?}", err));
             cur.commit().unwrap();
             account
+        } else if tables.initialized {
+            Ok(AccountBuilder::new_watch_only())
         } else {
             AccountBuilder::restore_from_tables(&tables, sk)
         }
-        .map_err(|err| format!("account gene error: {:?}", err))?;
+        .map_err(|err| format!("account gene error: {}", err))?;
 
         Ok(Chain {
             tables,
@@ -93,9 +111,12 @@ impl Chain {
     }
 
     pub fn push_new_block(&mut self, block: Block, txs: &Vec<TxVerifiable>) -> Result<(), String> {
-        // note: block check is already finished
+        // note: block check is already finished by `validate::check_block`
         // note: data is broken! if return error..
 
+        // remove secret from memory if unlock timeout
+        self.account.lock_if_expired();
+
         // start transaction
         let mut cur = self.tables.transaction();
 
@@ -105,7 +126,7 @@ impl Chain {
         // add block to confirmed and return fork info
         // best_chain is ordered `new to old`
         let (best_chain_before, best_chain_after): (_, _) =
-            self.confirmed.push_new_block(block, &cur.tables)?;
+            self.confirmed.push_new_block(block, txs)?;
 
         // revert fork (tx: confirmed -> unconfirmed)
         for blockhash in best_chain_before.iter() {
@@ -145,7 +166,7 @@ impl Chain {
 
                     // tx
                     let mut indexed_txs: _ = Vec::with_capacity(block.txs_hash.len());
-                    for txhash in block.txs_hash.iter() {
+                    for (index, txhash) in block.txs_hash.iter().enumerate() {
                         // block's tx is coinbase tx and non-coinbase txs
                         let _tx = cur.tables.read_txcache(txhash)?;
                         let tx = if txhash == &coinbase.hash {
@@ -161,6 +182,7 @@ impl Chain {
 
                         // addr index
                         let mut is_account_tx = false;
+                        let mut is_lock_tx = false;
                         let full_index = cur.tables.table_opts.addr_index;
                         for (input, output) in tx.body.inputs.iter().zip(tx.inputs_cache.iter()) {
                             if full_index || self.account.is_account_address(&output.0) {
@@ -173,15 +195,23 @@ impl Chain {
                                 is_account_tx = true;
                                 cur.write_addr_index(output, txhash, index as u8)?;
                             }
+                            if is_lock_output(output) {
+                                is_lock_tx = true;
+                            }
                         }
 
-                        // mint FIXME: unimplemented
+                        // mint
+                        if tx.body.txtype == TxType::Mint {
+                            let (coin, amount) = get_mint_info(tx)?;
+                            cur.write_mint(&coin, block.height, index as u32, txhash, amount)?;
+                        }
 
                         // remove tx
                         cur.remove_from_txcache(txhash)?;
 
                         // tx index
-                        if cur.tables.table_opts.tx_index || is_account_tx {
+                        // note: lock tx's height is required to check unlock
+                        if cur.tables.table_opts.tx_index || is_account_tx || is_lock_tx {
                             indexed_txs.push(txhash.clone());
                         }
                     }
@@ -237,6 +267,10 @@ impl Chain {
         Ok(self.tables.read_block(hash)?)
     }
 
+    pub fn get_block_filter(&self, hash: &U256) -> Result<Option<BlockFilter>, String> {
+        self.tables.read_block_filter(hash)
+    }
+
     pub fn get_best_block_ref(&self) -> &Block {
         let hash = self.best_chain.first().unwrap();
         self.confirmed.get_block_ref(hash).unwrap()
@@ -286,6 +320,42 @@ impl Chain {
         Err("not found txhash's height on chain".to_owned())
     }
 
+    /// return (header, branch, index) to prove the tx is included by the block
+    pub fn get_tx_proof(&self, hash: &U256) -> Result<Option<(BlockHeader, Vec<U256>, usize)>, String> {
+        // note: only account tx or require tx_index flag true
+
+        // from confirmed
+        let mut blockhash = None;
+        for _blockhash in self.best_chain.iter() {
+            let block = self.confirmed.get_block_ref(_blockhash).unwrap();
+            if block.txs_hash.contains(hash) {
+                blockhash = Some(_blockhash.clone());
+                break;
+            }
+        }
+
+        // from tables
+        if blockhash.is_none() {
+            blockhash = match self.tables.read_tx_height(hash) {
+                Ok(height) => self.tables.read_block_index(height)?,
+                Err(_) => return Ok(None), // not found or unconfirmed
+            };
+        }
+
+        let blockhash = blockhash.ok_or("tx_index found but block_index not found".to_owned())?;
+        let (block, _txs) = self
+            .tables
+            .read_full_block(&blockhash)?
+            .ok_or("block_index found but block not found".to_owned())?;
+        let index = block
+            .txs_hash
+            .iter()
+            .position(|txhash| txhash == hash)
+            .ok_or("tx_index found but the block doesn't include tx".to_owned())?;
+        let branch = merkle_branch(block.txs_hash.clone(), index);
+        Ok(Some((block.header, branch, index)))
+    }
+
     pub fn get_output_of_input(&self, input: &TxInput, ignore: bool) -> Result<Option<TxOutput>, String> {
         // find output of unused input (to fill input_cache)
         // note: return none if already used or not exist
@@ -379,6 +449,17 @@ impl Chain {
         AccountUnspentIter {
             addr_iter: self.account.get_account_addr_iter(),
             unspent_iter: None,
+            lock: false,
+            chain: self,
+        }
+    }
+
+    /// iterate account's fund-lock unspent (for FlkPos staking)
+    pub fn get_account_lock_iter(&self) -> AccountUnspentIter {
+        AccountUnspentIter {
+            addr_iter: self.account.get_account_addr_iter(),
+            unspent_iter: None,
+            lock: true,
             chain: self,
         }
     }
@@ -396,6 +477,80 @@ impl Chain {
         }
     }
 
+    /// add account listened by xpub, it cannot sign
+    pub fn add_watch_only_account(&mut self, account_id: u32, xpub: &[u8]) -> Result<(), String> {
+        let mut cur = self.tables.transaction();
+        self.account.add_watch_only_account(account_id, xpub, &mut cur)?;
+        cur.commit().unwrap();
+        Ok(())
+    }
+
+    /// merge small unspent of the account into outputs of `min_amount` or more for staking
+    pub fn build_consolidation(
+        &mut self,
+        account_id: u32,
+        coin_id: u32,
+        max_inputs: usize,
+        min_amount: u64,
+        template: &TxBuilder,
+    ) -> Result<Vec<TxVerifiable>, String> {
+        // note: template's destinations are ignored
+        builder::build_consolidation(self, template, account_id, coin_id, max_inputs, min_amount)
+    }
+
+    /// move all funds of the account or addresses to the target
+    pub fn build_sweep(
+        &mut self,
+        source: &SweepSource,
+        target: &Address,
+        template: &TxBuilder,
+    ) -> Result<Vec<TxVerifiable>, String> {
+        builder::build_sweep(self, template, source, target)
+    }
+
+    /// add derivation paths and signatures of account's inputs, return new signatures count
+    pub fn sign_partial_tx(&self, partial: &mut PartialTx) -> Result<usize, String> {
+        let msg = partial.body.to_bytes();
+        let mut count = 0;
+        for (cache, path) in partial.inputs_cache.iter().zip(partial.paths.iter_mut()) {
+            if let Some(cache) = cache {
+                if path.is_none() {
+                    *path = self.account.get_path_from_addr(&cache.0);
+                }
+            }
+        }
+        for owner in partial.get_missing_signers() {
+            if self.account.is_signable_addr(&owner) {
+                let signature = self.account.get_single_sign_by_addr(&owner, &msg)?;
+                partial.signature.push(signature);
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// encrypt wallet secret by passphrase, the wallet is locked after this
+    pub fn encrypt_wallet(&mut self, passphrase: &[u8]) -> Result<(), String> {
+        let mut cur = self.tables.transaction();
+        self.account.encrypt_secret(passphrase, &mut cur)?;
+        cur.commit().unwrap();
+        Ok(())
+    }
+
+    /// unlock wallet for timeout seconds (0 is no timeout)
+    pub fn unlock_wallet(&mut self, passphrase: &[u8], timeout: u32) -> Result<(), String> {
+        self.account.unlock(passphrase, timeout)
+    }
+
+    pub fn lock_wallet(&mut self) -> Result<(), String> {
+        self.account.lock()
+    }
+
+    pub fn is_wallet_locked(&mut self) -> bool {
+        self.account.lock_if_expired();
+        self.account.is_locked()
+    }
+
     /// return (confirmed, unconfirmed) balance
     pub fn get_account_balance(&self, account_id: u32, confirm: u32) -> Result<(Balances, Balances), String> {
         // note: incoming is confirmed when `confirm` height passed
@@ -485,6 +640,161 @@ impl Chain {
     }
 }
 
+// rescan account
+impl Chain {
+    /// find account txs from finalized blocks and return the count
+    ///
+    /// note: restored account don't know old txs, rebuild balance, movement and addr_index
+    /// note: already recoded movement is skipped, so you can rescan many times
+    pub fn rescan_accounts(&mut self, from_height: u32) -> Result<usize, String> {
+        // finalized blockhash list (old to new)
+        let blockhashs = self
+            .tables
+            .read_block_index_iter(from_height)
+            .map(|(_key, value)| U256::from(value.as_ref()))
+            .collect::<Vec<U256>>();
+
+        // account's outputs found on rescan [txhash 32b][output_index u8] -> output
+        let mut account_outputs: HashMap<[u8; 33], TxOutput> = HashMap::new();
+        let mut count = 0;
+        for chunk in blockhashs.chunks(RESCAN_BATCH_BLOCKS) {
+            // note: commit each chunk not to hold big write batch
+            let mut cur = self.tables.transaction();
+            let full_index = cur.tables.table_opts.addr_index;
+            for blockhash in chunk.iter() {
+                let (block, txs) = cur
+                    .tables
+                    .read_full_block(blockhash)?
+                    .ok_or("block_index found but block not found".to_owned())?;
+
+                // note: coinbase have inputs_cache, others may be cached on txcache
+                let BlockTxs(coinbase, recoded) = txs;
+                let mut txs = vec![(coinbase.hash, coinbase.body, Some(coinbase.inputs_cache))];
+                for tx in recoded.into_iter() {
+                    let cache = cur.tables.read_txcache(&tx.hash)?.map(|tx| tx.inputs_cache);
+                    txs.push((tx.hash, tx.body, cache));
+                }
+
+                let mut indexed_txs = Vec::new();
+                for (position, (txhash, body, cache)) in txs.into_iter().enumerate() {
+                    // resolve outputs of inputs
+                    let mut resolved = Vec::with_capacity(body.inputs.len());
+                    match cache {
+                        Some(cache) if cache.len() == body.inputs.len() => {
+                            for (input, output) in body.inputs.iter().zip(cache.into_iter()) {
+                                account_outputs.remove(&input.to_bytes());
+                                resolved.push((input.clone(), output));
+                            }
+                        },
+                        _ => {
+                            for input in body.inputs.iter() {
+                                let output = match account_outputs.remove(&input.to_bytes()) {
+                                    Some(output) => Some(output),
+                                    // note: output before `from_height` is found only if cached or indexed
+                                    None => cur.tables.read_spent_output(input)?,
+                                };
+                                if let Some(output) = output {
+                                    resolved.push((input.clone(), output));
+                                }
+                            }
+                        },
+                    }
+
+                    // account
+                    let inputs_cache = resolved
+                        .iter()
+                        .map(|(_input, output)| output.clone())
+                        .collect::<Vec<TxOutput>>();
+                    if !self.account.rescan_tx(
+                        txhash,
+                        &body,
+                        &inputs_cache,
+                        block.height,
+                        position as u32,
+                        &mut cur,
+                    )? {
+                        continue;
+                    }
+                    count += 1;
+                    indexed_txs.push(txhash);
+
+                    // addr index
+                    for (input, output) in resolved.iter() {
+                        if !full_index && self.account.is_account_address(&output.0) {
+                            cur.remove_addr_index(&output.0, input)?;
+                        }
+                    }
+                    for (index, output) in body.outputs.iter().enumerate() {
+                        if self.account.is_account_address(&output.0) {
+                            account_outputs.insert(TxInput(txhash, index as u8).to_bytes(), output.clone());
+                            if !full_index {
+                                cur.write_addr_index(output, &txhash, index as u8)?;
+                            }
+                        }
+                    }
+                }
+
+                // tx index
+                if !cur.tables.table_opts.tx_index && 0 < indexed_txs.len() {
+                    cur.write_tx_index(blockhash, &indexed_txs)?;
+                }
+            }
+
+            // update accounts at once
+            self.account.update_all_account_status(&mut cur);
+            cur.commit()
+                .map_err(|err| format!("rescan commit failed: {}", err))?;
+        }
+        Ok(count)
+    }
+}
+
+// get mint coin info
+impl Chain {
+    /// mint history of the coin from old to new on the best_chain
+    pub fn get_mint_history(&self, coin_id: u32, best_chain: &BlockHashVec) -> Result<MintHistory, String> {
+        // from tables
+        let mut history: MintHistory = self
+            .tables
+            .read_mint_iter(coin_id)
+            .map(|(height, _index, txhash, amount, coin)| (Some(height), txhash, amount, coin))
+            .collect();
+
+        // from confirmed (ordered old to new)
+        for blockhash in best_chain.iter().rev() {
+            let block = self.confirmed.get_block_ref(blockhash).unwrap();
+            // note: skip coinbase tx
+            for txhash in block.txs_hash.iter().skip(1) {
+                let tx = self
+                    .tables
+                    .read_txcache(txhash)?
+                    .ok_or("not found confirmed tx on txcache".to_owned())?;
+                if tx.body.txtype != TxType::Mint {
+                    continue;
+                }
+                let (coin, amount) = get_mint_info(&tx)?;
+                if coin.coin_id == coin_id {
+                    history.push((None, tx.hash, amount, coin));
+                }
+            }
+        }
+        Ok(history)
+    }
+
+    /// newest state of the coin on the best_chain, none if not minted yet
+    pub fn get_mint_state(
+        &self,
+        coin_id: u32,
+        best_chain: &BlockHashVec,
+    ) -> Result<Option<MintState>, String> {
+        let mut state = None;
+        for (_height, txhash, amount, coin) in self.get_mint_history(coin_id, best_chain)? {
+            MintState::update(&mut state, &coin, amount, &txhash);
+        }
+        Ok(state)
+    }
+}
+
 #[allow(unused_imports)]
 #[cfg(test)]
 mod chain {
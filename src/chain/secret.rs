@@ -0,0 +1,116 @@
+use chacha20poly1305::aead::generic_array::GenericArray;
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::ChaCha20Poly1305;
+use hdwallet::ExtendedPrivKey;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use scrypt::{scrypt, ScryptParams};
+use std::sync::atomic::{compiler_fence, Ordering};
+use zeroize::{Zeroize, Zeroizing};
+
+/// format version of encrypted secret
+static SECRET_VERSION: u8 = 1;
+
+/// scrypt cost `N = 2^15, r = 8, p = 1` (about 32MB memory)
+pub static SCRYPT_LOG_N: u8 = 15;
+
+/// passphrase encrypted wallet secret (scrypt + ChaCha20Poly1305)
+#[derive(Clone, PartialEq, Debug)]
+pub struct EncryptedSecret {
+    log_n: u8,
+    salt: [u8; 16],
+    nonce: [u8; 12],
+    cipher: Vec<u8>,
+}
+
+/// derive 32 bytes key from passphrase
+fn derive_key(passphrase: &[u8], salt: &[u8], log_n: u8) -> Result<Zeroizing<[u8; 32]>, String> {
+    let params = ScryptParams::new(log_n, 8, 1).map_err(|err| format!("scrypt params: {:?}", err))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    scrypt(passphrase, salt, &params, key.as_mut()).map_err(|err| format!("scrypt failed: {:?}", err))?;
+    Ok(key)
+}
+
+impl EncryptedSecret {
+    pub fn encrypt(secret: &[u8], passphrase: &[u8], log_n: u8) -> Result<Self, String> {
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let key = derive_key(passphrase, &salt, log_n)?;
+        let aead = ChaCha20Poly1305::new(GenericArray::from_slice(key.as_ref()));
+        let cipher = aead
+            .encrypt(GenericArray::from_slice(&nonce), secret)
+            .map_err(|_| "encrypt failed".to_owned())?;
+        Ok(EncryptedSecret {
+            log_n,
+            salt,
+            nonce,
+            cipher,
+        })
+    }
+
+    /// note: the output is zeroized on drop
+    pub fn decrypt(&self, passphrase: &[u8]) -> Result<Zeroizing<Vec<u8>>, String> {
+        let key = derive_key(passphrase, &self.salt, self.log_n)?;
+        let aead = ChaCha20Poly1305::new(GenericArray::from_slice(key.as_ref()));
+        aead.decrypt(GenericArray::from_slice(&self.nonce), self.cipher.as_slice())
+            .map(Zeroizing::new)
+            .map_err(|_| "wrong passphrase or broken secret".to_owned())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        // [version u8][log_n u8][salt 16b][nonce 12b][cipher Xb]
+        if bytes.len() < 1 + 1 + 16 + 12 + 16 {
+            return Err("encrypted secret is too short".to_owned());
+        }
+        if bytes[0] != SECRET_VERSION {
+            return Err(format!("unknown encrypted secret version {}", bytes[0]));
+        }
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        salt.copy_from_slice(&bytes[2..2 + 16]);
+        nonce.copy_from_slice(&bytes[18..18 + 12]);
+        Ok(EncryptedSecret {
+            log_n: bytes[1],
+            salt,
+            nonce,
+            cipher: bytes[30..].to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // [version u8][log_n u8][salt 16b][nonce 12b][cipher Xb]
+        let mut vec = Vec::with_capacity(1 + 1 + 16 + 12 + self.cipher.len());
+        vec.push(SECRET_VERSION);
+        vec.push(self.log_n);
+        vec.extend_from_slice(&self.salt);
+        vec.extend_from_slice(&self.nonce);
+        vec.extend_from_slice(&self.cipher);
+        vec
+    }
+}
+
+/// overwrite secret key & chain code in place before drop
+pub fn zeroize_priv_key(key: &mut ExtendedPrivKey) {
+    key.chain_code.zeroize();
+    // note: SecretKey don't have mutable access, overwrite by a valid key
+    key.private_key = secp256k1::key::ONE_KEY;
+    compiler_fence(Ordering::SeqCst);
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod secret {
+    use crate::chain::secret::EncryptedSecret;
+
+    #[test]
+    fn encrypt_decrypt() {
+        // note: low cost for test
+        let secret = b"1qwq53lmi8rapvcciqmuiorxdie5irwmw1dccbegkze9vjdpy7mz6nsd6j991a6b".to_vec();
+        let encrypted = EncryptedSecret::encrypt(&secret, b"passphrase", 4).unwrap();
+        let encrypted = EncryptedSecret::from_bytes(&encrypted.to_bytes()).unwrap();
+        assert_eq!(*encrypted.decrypt(b"passphrase").unwrap(), secret);
+        assert!(encrypted.decrypt(b"wrong").is_err());
+    }
+}
@@ -0,0 +1,414 @@
+use crate::block::*;
+use crate::chain::GENESIS_PREVIOUS_HASH;
+use crate::tx::{TxBody, TxOutput};
+use crate::utils::*;
+use bigint::U256;
+use std::collections::HashMap;
+
+/// stored size of a header [header 80b][flag u8][bias f32][work 32b]
+static SPV_HEADER_SIZE: usize = 80 + 1 + 4 + 32;
+
+/// header with accumulated score from genesis
+struct SpvHeader {
+    block: Block, // note: txs_hash is always empty
+    /// work is proved by the header only (PoW or genesis)
+    verified: bool,
+    /// sum of verified headers' score
+    total_score: f64,
+}
+
+/// all received headers include forks
+struct HeaderMap(HashMap<U256, SpvHeader>);
+
+impl HeaderReader for HeaderMap {
+    fn read_header(&self, hash: &U256) -> Result<Option<Block>, String> {
+        Ok(self.0.get(hash).map(|spv| spv.block.clone()))
+    }
+}
+
+/// header-only chain for light clients (SPV)
+///
+/// verify bits, bias and work of each header without full `Tables`
+pub struct HeaderChain {
+    headers: HeaderMap,
+    diff: DifficultyBuilder,
+    /// best chain's blockhash ordered by height (old to new)
+    main: Vec<U256>,
+}
+
+impl HeaderChain {
+    pub fn new(params: BlockTimeParams) -> Self {
+        HeaderChain {
+            headers: HeaderMap(HashMap::new()),
+            diff: DifficultyBuilder::new(params),
+            main: Vec::new(),
+        }
+    }
+
+    /// restore from `to_bytes` output, headers are verified again
+    ///
+    /// note: staking headers' work is restored as it is because stake isn't stored
+    pub fn from_bytes(params: BlockTimeParams, bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() % SPV_HEADER_SIZE != 0 {
+            return Err(format!("spv headers are {} bytes each", SPV_HEADER_SIZE));
+        }
+        let mut chain = HeaderChain::new(params);
+        for chunk in bytes.chunks(SPV_HEADER_SIZE) {
+            let header = BlockHeader::from_bytes(&chunk[0..80]);
+            let flag = BlockFlag::from_int(chunk[80])?;
+            let bias = bytes_to_f32(&chunk[81..81 + 4]);
+            let work_hash = U256::from(&chunk[85..85 + 32]);
+            let (height, bias) = chain.check_header(&header, &flag, bias)?;
+            let (work_hash, verified) = if is_verifiable(&flag) {
+                (get_work_hash(&flag, &header, None, None)?, true)
+            } else {
+                (work_hash, false)
+            };
+            if bits_to_target(header.bits)? <= work_hash {
+                return Err("not enough work".to_owned());
+            }
+            let block = Block::new(work_hash, height, flag, bias, header, vec![]);
+            chain.insert(block, verified);
+        }
+        Ok(chain)
+    }
+
+    /// dump all headers (include forks) ordered by height
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut headers = self
+            .headers
+            .0
+            .values()
+            .map(|spv| &spv.block)
+            .collect::<Vec<&Block>>();
+        headers.sort_by_key(|block| block.height);
+        let mut bytes = Vec::with_capacity(SPV_HEADER_SIZE * headers.len());
+        for block in headers {
+            bytes.extend_from_slice(&block.header.to_bytes());
+            bytes.push(block.flag.to_int());
+            bytes.extend_from_slice(&f32_to_bytes(block.bias));
+            bytes.extend_from_slice(&u256_to_bytes(&block.work_hash));
+        }
+        bytes
+    }
+
+    /// verify and push a new header, return blockhash
+    ///
+    /// PoS header require `stake` (coinbase, input_cache, coinbase's merkle branch) to check work
+    ///
+    /// note: staking headers are pushed as unverified because the stake's existence
+    /// and the coinbase's signature cannot be proved without full chain,
+    /// unverified headers don't add score to the best chain and confirmations
+    pub fn push_header(
+        &mut self,
+        header: BlockHeader,
+        flag: BlockFlag,
+        bias: f32,
+        stake: Option<(&TxBody, &TxOutput, &[U256])>,
+    ) -> Result<U256, String> {
+        let hash = header.hash();
+        let (height, bias) = self.check_header(&header, &flag, bias)?;
+
+        // work
+        let work_hash = match flag {
+            BlockFlag::CoinPos | BlockFlag::CapPos | BlockFlag::FlkPos => {
+                let (coinbase, input_cache, branch) =
+                    stake.ok_or(format!("{:?} header require stake", flag))?;
+                // coinbase is always first tx
+                let coinbase_hash = U256::from(coinbase.hash().as_slice());
+                if !verify_merkle_branch(&header.merkleroot, &coinbase_hash, branch, 0) {
+                    return Err("coinbase isn't included by the header".to_owned());
+                }
+                get_work_hash(&flag, &header, Some(coinbase), Some(input_cache))?
+            },
+            _ => get_work_hash(&flag, &header, None, None)?,
+        };
+        let target = bits_to_target(header.bits)?;
+        if target <= work_hash {
+            return Err("not enough work".to_owned());
+        }
+
+        let verified = is_verifiable(&flag);
+        let block = Block::new(work_hash, height, flag, bias, header, vec![]);
+        self.insert(block, verified);
+        Ok(hash)
+    }
+
+    /// check header's link, bits and bias and return (height, bias calculated by self)
+    fn check_header(
+        &mut self,
+        header: &BlockHeader,
+        flag: &BlockFlag,
+        bias: f32,
+    ) -> Result<(u32, f32), String> {
+        let hash = header.hash();
+        if self.headers.0.contains_key(&hash) {
+            return Err(format!("already known header {}", u256_to_hex(&hash)));
+        }
+        let height = self.next_height(header, flag)?;
+
+        // bits & bias
+        let bits = self
+            .diff
+            .calc_next_bits(&header.previous_hash, flag, &self.headers)?;
+        if header.bits != bits {
+            return Err(format!("bits mismatch, expected {} but {}", bits, header.bits));
+        }
+        let expected_bias = self
+            .diff
+            .calc_next_bias(&header.previous_hash, flag, &self.headers)?;
+        if bias != expected_bias {
+            return Err(format!("bias mismatch, expected {} but {}", expected_bias, bias));
+        }
+        Ok((height, expected_bias))
+    }
+
+    /// height of the header, error if orphan
+    fn next_height(&self, header: &BlockHeader, flag: &BlockFlag) -> Result<u32, String> {
+        if header.previous_hash == *GENESIS_PREVIOUS_HASH {
+            if flag != &BlockFlag::Genesis {
+                return Err("first header is only genesis".to_owned());
+            }
+            if 0 < self.main.len() {
+                return Err("genesis header is already known".to_owned());
+            }
+            Ok(0)
+        } else if flag == &BlockFlag::Genesis {
+            Err("genesis header's previous_hash is ffff..ffff".to_owned())
+        } else {
+            match self.headers.0.get(&header.previous_hash) {
+                Some(previous) => Ok(previous.block.height + 1),
+                None => Err(format!("orphan header of {}", u256_to_hex(&header.previous_hash))),
+            }
+        }
+    }
+
+    /// insert a linked header and update main chain by the score
+    fn insert(&mut self, block: Block, verified: bool) {
+        let hash = block.header.hash();
+        let previous_score = self
+            .headers
+            .0
+            .get(&block.header.previous_hash)
+            .map(|previous| previous.total_score)
+            .unwrap_or(0.0);
+        // note: same score as `ConfirmedBuilder`, sum of `calc_score` from root
+        // note: unverified header only extend the best chain without score
+        let total_score = if verified {
+            previous_score + block.calc_score()
+        } else {
+            previous_score
+        };
+        let height = block.height;
+        let previous_hash = block.header.previous_hash;
+        self.headers.0.insert(hash, SpvHeader {
+            block,
+            verified,
+            total_score,
+        });
+
+        // note: keep first seen if same score
+        let is_best = match self.main.last() {
+            Some(best) if best == &previous_hash => self.headers.0[best].total_score <= total_score,
+            Some(best) => self.headers.0[best].total_score < total_score,
+            None => true,
+        };
+        if is_best {
+            // replace main chain from the fork point
+            self.main.truncate(height as usize + 1);
+            self.main.resize(height as usize + 1, U256::zero());
+            let mut target_hash = hash;
+            for i in (0..=height as usize).rev() {
+                if self.main[i] == target_hash {
+                    break;
+                }
+                self.main[i] = target_hash;
+                target_hash = self.headers.0[&target_hash].block.header.previous_hash;
+            }
+        }
+    }
+
+    pub fn get_best_hash(&self) -> Option<&U256> {
+        self.main.last()
+    }
+
+    pub fn get_best_height(&self) -> Option<u32> {
+        self.main.len().checked_sub(1).map(|height| height as u32)
+    }
+
+    pub fn get_hash_by_height(&self, height: u32) -> Option<&U256> {
+        self.main.get(height as usize)
+    }
+
+    /// get header (include forks) with (height, flag, bias)
+    pub fn get_header(&self, hash: &U256) -> Option<(&BlockHeader, u32, &BlockFlag, f32)> {
+        self.headers.0.get(hash).map(|spv| {
+            (
+                &spv.block.header,
+                spv.block.height,
+                &spv.block.flag,
+                spv.block.bias,
+            )
+        })
+    }
+
+    /// the header's work is proved (not staking header)
+    pub fn is_verified(&self, hash: &U256) -> bool {
+        self.headers.0.get(hash).map(|spv| spv.verified).unwrap_or(false)
+    }
+
+    pub fn is_main_chain(&self, hash: &U256) -> bool {
+        match self.headers.0.get(hash) {
+            Some(spv) => self.main.get(spv.block.height as usize) == Some(hash),
+            None => false,
+        }
+    }
+
+    /// verify tx inclusion by the main chain's block, return confirmations
+    ///
+    /// note: confirmations count only verified headers
+    pub fn verify_tx(
+        &self,
+        blockhash: &U256,
+        txhash: &U256,
+        branch: &[U256],
+        index: usize,
+    ) -> Result<u32, String> {
+        if !self.is_main_chain(blockhash) {
+            return Err("the block isn't on main chain".to_owned());
+        }
+        let spv = &self.headers.0[blockhash];
+        if !spv.verified {
+            return Err("the block is unverified staking header".to_owned());
+        }
+        let block = &spv.block;
+        if !verify_merkle_branch(&block.header.merkleroot, txhash, branch, index) {
+            return Err("the tx isn't included by the block".to_owned());
+        }
+        let confirmations = self.main[block.height as usize..]
+            .iter()
+            .filter(|hash| self.headers.0[hash].verified)
+            .count();
+        Ok(confirmations as u32)
+    }
+}
+
+/// the flag's work is proved only by the header
+fn is_verifiable(flag: &BlockFlag) -> bool {
+    match flag {
+        BlockFlag::Genesis | BlockFlag::YesPow | BlockFlag::X11Pow | BlockFlag::X16sPow => true,
+        _ => false,
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod spv {
+    use super::*;
+    use crate::block::*;
+    use crate::chain::spv::HeaderChain;
+    use crate::chain::GENESIS_PREVIOUS_HASH;
+    use crate::utils::*;
+    use bigint::U256;
+
+    fn mine_header(previous_hash: &U256, merkleroot: u32, bits: u32) -> BlockHeader {
+        let mut header = BlockHeader {
+            version: 0,
+            previous_hash: previous_hash.clone(),
+            merkleroot: U256::from(merkleroot),
+            time: 0,
+            bits,
+            nonce: 0,
+        };
+        let target = bits_to_target(bits).unwrap();
+        while target <= get_work_hash(&BlockFlag::X11Pow, &header, None, None).unwrap() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    #[test]
+    fn header_chain() {
+        let mut chain = HeaderChain::new(vec![(BlockFlag::X11Pow, 60, 30, 60)]);
+
+        // genesis
+        let genesis = BlockHeader {
+            version: 0,
+            previous_hash: *GENESIS_PREVIOUS_HASH,
+            merkleroot: U256::from(1u32),
+            time: 0,
+            bits: 0x1f0fffff,
+            nonce: 0,
+        };
+        let genesis_hash = chain
+            .push_header(genesis.clone(), BlockFlag::Genesis, 1.0, None)
+            .unwrap();
+        assert!(chain.push_header(genesis, BlockFlag::Genesis, 1.0, None).is_err());
+
+        // bits mismatch
+        let bad = mine_header(&genesis_hash, 2, 0x1f0ffff0);
+        assert!(chain.push_header(bad, BlockFlag::X11Pow, 1.0, None).is_err());
+
+        // fork: first seen is best
+        let a = mine_header(&genesis_hash, 3, 0x1f0fffff);
+        let b = mine_header(&genesis_hash, 4, 0x1f0fffff);
+        let a_hash = chain.push_header(a, BlockFlag::X11Pow, 1.0, None).unwrap();
+        let b_hash = chain.push_header(b, BlockFlag::X11Pow, 1.0, None).unwrap();
+        assert_eq!(chain.get_best_hash(), Some(&a_hash));
+
+        // reorg by longer chain
+        let c = mine_header(&b_hash, 5, 0x1f0fffff);
+        let c_hash = chain.push_header(c, BlockFlag::X11Pow, 1.0, None).unwrap();
+        assert_eq!(chain.get_best_height(), Some(2));
+        assert!(chain.is_main_chain(&b_hash));
+        assert!(!chain.is_main_chain(&a_hash));
+
+        // single tx block's merkleroot is the txhash
+        assert_eq!(chain.verify_tx(&c_hash, &U256::from(5u32), &[], 0), Ok(1));
+        assert!(chain.verify_tx(&a_hash, &U256::from(3u32), &[], 0).is_err());
+
+        // restore
+        let params = vec![(BlockFlag::X11Pow, 60, 30, 60)];
+        let bytes = chain.to_bytes();
+        let restored = HeaderChain::from_bytes(params.clone(), &bytes).unwrap();
+        assert_eq!(restored.get_best_hash(), Some(&c_hash));
+
+        // restore verify headers again
+        let mut broken = bytes.clone();
+        let size = 80 + 1 + 4 + 32;
+        broken[size + 72] ^= 0x01; // bits of the second header
+        assert!(HeaderChain::from_bytes(params, &broken).is_err());
+
+        // unverified header extends the best chain without score and confirmations
+        let staked = BlockHeader {
+            version: 0,
+            previous_hash: c_hash.clone(),
+            merkleroot: U256::from(6u32),
+            time: 0,
+            bits: 0x1f0fffff,
+            nonce: 0,
+        };
+        let staked_hash = staked.hash();
+        let staked = Block::new(U256::zero(), 3, BlockFlag::CoinPos, 1.0, staked, vec![]);
+        chain.insert(staked, false);
+        assert_eq!(chain.get_best_hash(), Some(&staked_hash));
+        assert!(!chain.is_verified(&staked_hash));
+        assert_eq!(chain.verify_tx(&c_hash, &U256::from(5u32), &[], 0), Ok(1));
+        assert!(chain.verify_tx(&staked_hash, &U256::from(6u32), &[], 0).is_err());
+
+        // unverified fork cannot replace the best
+        let fork = BlockHeader {
+            version: 0,
+            previous_hash: c_hash.clone(),
+            merkleroot: U256::from(7u32),
+            time: 0,
+            bits: 0x1f0fffff,
+            nonce: 0,
+        };
+        let fork_hash = fork.hash();
+        let fork = Block::new(U256::zero(), 3, BlockFlag::CoinPos, 1.0, fork, vec![]);
+        chain.insert(fork, false);
+        assert_eq!(chain.get_best_hash(), Some(&staked_hash));
+        assert!(!chain.is_main_chain(&fork_hash));
+    }
+}
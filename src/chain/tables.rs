@@ -1,8 +1,8 @@
 use crate::balance::BalanceMovement;
 use crate::block::*;
-use crate::chain::{iters::*, utils::*};
+use crate::chain::{filter::BlockFilter, iters::*, utils::*};
 use crate::pickle::*;
-use crate::tx::{BlockTxs, TxBody, TxInput, TxOutput, TxRecoded, TxVerifiable};
+use crate::tx::{BlockTxs, MintCoin, TxBody, TxInput, TxOutput, TxRecoded, TxVerifiable};
 use crate::utils::*;
 use bigint::U256;
 use remove_dir_all::remove_dir_all;
@@ -16,6 +16,8 @@ use std::time::Instant;
 
 static TABLE_VERSION: &str = "0.1.0";
 static KVS_NAME: &str = "rocksdb";
+/// account table's key of encrypted wallet secret, distinct from 4 bytes account_id
+static ACCOUNT_SECRET_KEY: &[u8] = b"secret";
 type Address = [u8; 21];
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -130,6 +132,7 @@ pub struct Tables {
     tx_index: DB,
     addr_index: DB,
     mint: DB,
+    filter: DB,
 
     // tx cache
     txcache: DB,
@@ -176,6 +179,10 @@ impl Tables {
         let tx_index = DB::open(&rocks_opts, dir.join("tx_index")).unwrap();
         let addr_index = DB::open(&rocks_opts, dir.join("addr_index")).unwrap();
         let mint = DB::open(&rocks_opts, dir.join("mint")).unwrap();
+        // note: filter table is added after 0.1.0, create if missing and build lazily
+        let mut filter_opts = Options::default();
+        filter_opts.create_if_missing(true);
+        let filter = DB::open(&filter_opts, dir.join("filter")).unwrap();
         let account = DB::open(&rocks_opts, dir.join("account")).unwrap();
         let movement = DB::open(&rocks_opts, dir.join("movement")).unwrap();
         let txcache = DB::open(&rocks_opts, dir.join("txcache")).unwrap();
@@ -191,6 +198,7 @@ impl Tables {
             tx_index,
             addr_index,
             mint,
+            filter,
             account,
             movement,
             txcache,
@@ -223,6 +231,7 @@ impl Tables {
             tx_index: WriteBatch::default(),
             addr_index: WriteBatch::default(),
             mint: WriteBatch::default(),
+            filter: WriteBatch::default(),
             account: WriteBatch::default(),
             movement: WriteBatch::default(),
             txcache: WriteBatch::default(),
@@ -362,12 +371,78 @@ impl Tables {
         }
     }
 
-    pub fn read_mint_iter(&self, coin_id: u32) -> DBIterator {
-        // [coin_id u32][height u32][index u32] -> [txhash 32b][params ?][setting ?]
+    pub fn read_mint_iter(&self, coin_id: u32) -> MintIter {
+        // [coin_id u32][height u32][index u32] -> [txhash 32b][amount i64][mint ?]
+        // note: iterate from old to new
         let mut key = [0u8; 4 + 4 + 4];
-        write_slice(&mut key[0..4], &u32_to_bytes(coin_id));
-        self.mint.iterator(IteratorMode::From(&key, Direction::Forward));
-        unimplemented!("read mint")
+        write_slice(&mut key[0..4], &big_endian_from_u32(coin_id));
+        MintIter {
+            coin_id,
+            iter: self.mint.iterator(IteratorMode::From(&key, Direction::Forward)),
+        }
+    }
+
+    pub fn read_block_filter(&self, hash: &U256) -> Result<Option<BlockFilter>, String> {
+        // [blockhash 32b] -> [N u32][golomb coded bits]
+        let key = u256_to_bytes(hash);
+        match self.filter.get(&key) {
+            Ok(Some(value)) => return Ok(Some(BlockFilter::from_bytes(&value)?)),
+            Ok(None) => (),
+            Err(err) => return Err(format!("database exception: {}", err.to_string())),
+        }
+
+        // note: blocks written before filter table don't have filter, build and store it
+        let txs = match self.read_full_block(hash)? {
+            Some((_block, txs)) => txs,
+            None => return Ok(None),
+        };
+        let mut verifiable = Vec::with_capacity(txs.len());
+        verifiable.push(txs.0);
+        for tx in txs.1.into_iter() {
+            let mut inputs_cache = Vec::with_capacity(tx.body.inputs.len());
+            for input in tx.body.inputs.iter() {
+                match self.read_spent_output(input)? {
+                    Some(output) => inputs_cache.push(output),
+                    None => return Err(format!("not found spent output of {:?}", input)),
+                }
+            }
+            verifiable.push(TxVerifiable {
+                hash: tx.hash,
+                body: tx.body,
+                signature: tx.signature,
+                inputs_cache,
+            });
+        }
+        let filter = BlockFilter::from_block(hash, &verifiable);
+        self.filter
+            .put(&key, &filter.to_bytes())
+            .map_err(|err| err.to_string())?;
+        Ok(Some(filter))
+    }
+
+    /// find output of spent input from txcache or tx_index
+    ///
+    /// note: return None if the tx isn't cached nor indexed
+    pub fn read_spent_output(&self, input: &TxInput) -> Result<Option<TxOutput>, String> {
+        let outputs = match self.read_txcache(&input.0)? {
+            Some(tx) => tx.body.outputs,
+            None => match self.read_tx(&input.0)? {
+                Some(tx) => tx.body.outputs,
+                None => return Ok(None),
+            },
+        };
+        match outputs.get(input.1 as usize) {
+            Some(output) => Ok(Some(output.clone())),
+            None => Err(format!("spent output index is out of range {:?}", input)),
+        }
+    }
+
+    pub fn read_account_secret(&self) -> Result<Option<Vec<u8>>, String> {
+        // [b"secret" 6b] -> [encrypted secret xb]
+        match self.account.get(ACCOUNT_SECRET_KEY) {
+            Ok(value) => Ok(value.map(|value| value.to_vec())),
+            Err(err) => Err(format!("database exception: {}", err.to_string())),
+        }
     }
 
     pub fn read_account_iter(&self) -> DBIterator {
@@ -385,6 +460,17 @@ impl Tables {
         self.movement.iterator(mode)
     }
 
+    pub fn have_the_movement(&self, height: u32, position: u32) -> Result<bool, String> {
+        // 8 bytes key: [height u32][position u32] -> [movement bytes xb]
+        let mut key = [0u8; 4 + 4];
+        write_slice(&mut key[0..4], &u32_to_bytes(height));
+        write_slice(&mut key[4..4 + 4], &u32_to_bytes(position));
+        match self.movement.get(key.as_ref()) {
+            Ok(value) => Ok(value.is_some()),
+            Err(err) => Err(format!("database exception: {}", err.to_string())),
+        }
+    }
+
     pub fn read_temporary_movement(&self, hash: &U256) -> Result<Option<BalanceMovement>, String> {
         // 32 bytes key: [txhash 32b] -> [movement bytes xb]
         // or
@@ -429,6 +515,7 @@ pub struct TableCursor<'a> {
     tx_index: WriteBatch,
     addr_index: WriteBatch,
     mint: WriteBatch,
+    filter: WriteBatch,
     account: WriteBatch,
     movement: WriteBatch,
     txcache: WriteBatch,
@@ -448,6 +535,7 @@ impl TableCursor<'_> {
         self.tables.tx_index.write_opt(self.tx_index, &writeopts)?;
         self.tables.addr_index.write_opt(self.addr_index, &writeopts)?;
         self.tables.mint.write_opt(self.mint, &writeopts)?;
+        self.tables.filter.write_opt(self.filter, &writeopts)?;
         self.tables.account.write_opt(self.account, &writeopts)?;
         self.tables.movement.write_opt(self.movement, &writeopts)?;
         self.tables.txcache.write_opt(self.txcache, &writeopts)?;
@@ -461,8 +549,13 @@ impl TableCursor<'_> {
 
         let key = sha256double(&block.header.to_bytes());
         let value = pickle_full_block(block, txs)?;
+        self.block.put(&key, &value).map_err(|err| err.to_string())?;
 
-        self.block.put(&key, &value).map_err(|err| err.to_string())
+        // [blockhash 32b] -> [N u32][golomb coded bits]
+        let filter = BlockFilter::from_block(&U256::from(key.as_slice()), txs);
+        self.filter
+            .put(&key, &filter.to_bytes())
+            .map_err(|err| err.to_string())
     }
 
     pub fn write_block_index(&mut self, height: u32, header: &BlockHeader) -> Result<(), String> {
@@ -584,9 +677,26 @@ impl TableCursor<'_> {
             .map_err(|err| err.to_string())
     }
 
-    pub fn write_mint(&mut self) -> Result<(), String> {
-        // [coin_id u32][height u32][index u32] -> [txhash 32b][params ?][setting ?]
-        unimplemented!("write mint")
+    pub fn write_mint(
+        &mut self,
+        mint: &MintCoin,
+        height: u32,
+        index: u32,
+        txhash: &U256,
+        amount: i64,
+    ) -> Result<(), String> {
+        // [coin_id u32][height u32][index u32] -> [txhash 32b][amount i64][mint ?]
+        let mut key = [0u8; 4 + 4 + 4];
+        write_slice(&mut key[0..4], &big_endian_from_u32(mint.coin_id));
+        write_slice(&mut key[4..4 + 4], &big_endian_from_u32(height));
+        write_slice(&mut key[8..8 + 4], &big_endian_from_u32(index));
+
+        let mut value = Vec::with_capacity(32 + 8 + 6);
+        value.extend_from_slice(&u256_to_bytes(txhash));
+        value.extend_from_slice(&i64_to_bytes(amount));
+        value.extend_from_slice(&mint.to_bytes()?);
+
+        self.mint.put(key.as_ref(), &value).map_err(|err| err.to_string())
     }
 
     pub fn write_account_state(&mut self, account_id: u32, bytes: &[u8]) -> Result<(), String> {
@@ -597,6 +707,13 @@ impl TableCursor<'_> {
             .map_err(|err| err.to_string())
     }
 
+    pub fn write_account_secret(&mut self, bytes: &[u8]) -> Result<(), String> {
+        // [b"secret" 6b] -> [encrypted secret xb]
+        self.account
+            .put(ACCOUNT_SECRET_KEY, bytes)
+            .map_err(|err| err.to_string())
+    }
+
     pub fn write_temporary_movement(&mut self, movement: &BalanceMovement) -> Result<(), String> {
         // [txhash 32b] -> [movement bytes xb]
         let key = u256_to_bytes(&movement.hash);
@@ -606,6 +723,23 @@ impl TableCursor<'_> {
             .map_err(|err| err.to_string())
     }
 
+    pub fn write_movement(
+        &mut self,
+        height: u32,
+        position: u32,
+        movement: &BalanceMovement,
+    ) -> Result<(), String> {
+        // [height u32][position u32] -> [movement bytes xb]
+        // note: for rescan, the tx is already finalized without temporary movement
+        let mut key = [0u8; 4 + 4];
+        write_slice(&mut key[0..4], &u32_to_bytes(height));
+        write_slice(&mut key[4..4 + 4], &u32_to_bytes(position));
+        let value = movement.to_bytes();
+        self.movement
+            .put(key.as_ref(), &value)
+            .map_err(|err| err.to_string())
+    }
+
     pub fn update_movement_status(&mut self, hash: &U256, height: u32, position: u32) -> Result<(), String> {
         // change key [txhash 32b] to [height u32][position u32]
         let old_key = u256_to_bytes(hash).to_vec();
@@ -218,6 +218,7 @@ impl DoubleEndedStreamingIterator for TxsIter<'_> {
 }
 
 /// unconfirmed txs list
+#[derive(Clone)]
 pub struct UnconfirmedTxs {
     /// unconfirmed hash vec
     pub txs: Vec<U256>,
@@ -0,0 +1,1344 @@
+use crate::block::{bits_to_target, get_work_hash, Block, BlockFlag, DifficultyBuilder, RewardBuilder};
+use crate::chain::confirmed::BlockHashVec;
+use crate::chain::mint::{get_mint_info, MintState};
+use crate::chain::{Chain, GENESIS_PREVIOUS_HASH};
+use crate::signature::{verify_batch_cached, verify_signature};
+use crate::tx::{
+    get_owner_addr,
+    is_lock_output,
+    TxInput,
+    TxOutput,
+    TxType,
+    TxVerifiable,
+    LOCK_MATURE_HEIGHT,
+    LOCK_UNLOCK_HEIGHT,
+};
+use crate::utils::*;
+use bigint::U256;
+use std::fmt;
+
+type Address = [u8; 21];
+
+/// reason why the block is rejected
+#[derive(Debug, PartialEq)]
+pub enum BlockRejected {
+    /// block don't have any tx
+    EmptyTxs,
+    /// txs_hash and txs mismatch (index)
+    TxsMismatch(usize),
+    /// inputs and inputs_cache length mismatch (txhash)
+    InputsCacheMismatch(U256),
+    /// merkleroot is not calculated from txs_hash
+    BadMerkleroot,
+    /// previous block is not found on confirmed (or already finalized)
+    UnknownPrevious,
+    /// height isn't previous's height + 1 (expected)
+    BadHeight(u32),
+    /// genesis block's format is wrong
+    BadGenesis,
+    /// bits isn't calculated by difficulty params (expected)
+    BadBits(u32),
+    /// bias isn't calculated by difficulty params (expected)
+    BadBias(f32),
+    /// cannot calculate work hash
+    BadWorkHash(String),
+    /// work hash don't met a target
+    NotEnoughWork,
+    /// coinbase format is wrong
+    BadCoinbase(String),
+    /// coinbase output over reward (limit, real)
+    OverReward(u64, u64),
+    /// signature verification failed (txhash)
+    BadSignature(U256),
+    /// input not found or inputs_cache is wrong (txhash, input)
+    BadInput(U256, TxInput),
+    /// input already used (txhash, input)
+    DoubleSpend(U256, TxInput),
+    /// block time is older than previous block
+    BadBlockTime,
+    /// tx's time or deadline don't cover block time (txhash)
+    BadTxTime(U256),
+    /// tx break a rule (txhash, reason)
+    BadTx(U256, TxRejected),
+    /// tables access error etc..
+    Internal(String),
+}
+
+impl fmt::Display for BlockRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockRejected::EmptyTxs => write!(f, "block don't have any tx"),
+            BlockRejected::TxsMismatch(index) => write!(f, "txs_hash and txs mismatch at {}", index),
+            BlockRejected::InputsCacheMismatch(hash) => {
+                write!(f, "inputs and inputs_cache mismatch {}", u256_to_hex(hash))
+            },
+            BlockRejected::BadMerkleroot => write!(f, "merkleroot mismatch"),
+            BlockRejected::UnknownPrevious => write!(f, "previous block is not found"),
+            BlockRejected::BadHeight(height) => write!(f, "wrong height, expected {}", height),
+            BlockRejected::BadGenesis => write!(f, "wrong genesis block"),
+            BlockRejected::BadBits(bits) => write!(f, "wrong bits, expected {}", bits),
+            BlockRejected::BadBias(bias) => write!(f, "wrong bias, expected {}", bias),
+            BlockRejected::BadWorkHash(err) => write!(f, "cannot calc work hash: {}", err),
+            BlockRejected::NotEnoughWork => write!(f, "work hash don't met a target"),
+            BlockRejected::BadCoinbase(err) => write!(f, "wrong coinbase: {}", err),
+            BlockRejected::OverReward(limit, real) => write!(f, "over reward limit={} real={}", limit, real),
+            BlockRejected::BadSignature(hash) => write!(f, "signature failed {}", u256_to_hex(hash)),
+            BlockRejected::BadInput(hash, input) => {
+                write!(f, "wrong input {:?} of {}", input, u256_to_hex(hash))
+            },
+            BlockRejected::DoubleSpend(hash, input) => {
+                write!(f, "input {:?} already used by {}", input, u256_to_hex(hash))
+            },
+            BlockRejected::BadBlockTime => write!(f, "block time is older than previous"),
+            BlockRejected::BadTxTime(hash) => write!(f, "tx time is out of range {}", u256_to_hex(hash)),
+            BlockRejected::BadTx(hash, reason) => write!(f, "wrong tx {}: {}", u256_to_hex(hash), reason),
+            BlockRejected::Internal(err) => write!(f, "internal error: {}", err),
+        }
+    }
+}
+
+/// reason why the tx is rejected
+#[derive(Debug, PartialEq)]
+pub enum TxRejected {
+    /// txhash isn't calculated from body
+    BadHash,
+    /// txtype isn't accepted as normal tx
+    BadType(TxType),
+    /// no input or no output
+    EmptyInputs,
+    EmptyOutputs,
+    /// same input is used twice
+    DuplicateInput(TxInput),
+    /// inputs and inputs_cache length mismatch
+    InputsCacheMismatch,
+    /// input not found or inputs_cache is wrong
+    BadInput(TxInput),
+    /// input already used
+    DoubleSpend(TxInput),
+    /// lock output is used before unlock height
+    Locked(TxInput),
+    /// signature verification failed (index)
+    BadSignature(usize),
+    /// input's address isn't signed
+    NotSigned(Address),
+    /// fee isn't `gas_price * gas_amount`
+    BadFee,
+    /// coin is created or destroyed (coinId)
+    Unbalanced(u32),
+    /// mint format or authority is wrong
+    BadMint(String),
+    /// time is after deadline
+    BadTime,
+    /// deadline is older than best block
+    Expired,
+    /// tables access error etc..
+    Internal(String),
+}
+
+impl fmt::Display for TxRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxRejected::BadHash => write!(f, "txhash mismatch"),
+            TxRejected::BadType(txtype) => write!(f, "not accepted txtype {:?}", txtype),
+            TxRejected::EmptyInputs => write!(f, "tx don't have any input"),
+            TxRejected::EmptyOutputs => write!(f, "tx don't have any output"),
+            TxRejected::DuplicateInput(input) => write!(f, "input {:?} is duplicated", input),
+            TxRejected::InputsCacheMismatch => write!(f, "inputs and inputs_cache length mismatch"),
+            TxRejected::BadInput(input) => write!(f, "input {:?} not found or cache is wrong", input),
+            TxRejected::DoubleSpend(input) => write!(f, "input {:?} is already used", input),
+            TxRejected::Locked(input) => write!(f, "input {:?} is still locked", input),
+            TxRejected::BadSignature(index) => write!(f, "signature verification failed at {}", index),
+            TxRejected::NotSigned(addr) => write!(f, "address {} isn't signed", hex::encode(addr)),
+            TxRejected::BadFee => write!(f, "fee isn't gas_price * gas_amount"),
+            TxRejected::Unbalanced(coin_id) => write!(f, "unbalanced coinId={}", coin_id),
+            TxRejected::BadMint(err) => write!(f, "wrong mint: {}", err),
+            TxRejected::BadTime => write!(f, "time is after deadline"),
+            TxRejected::Expired => write!(f, "deadline is already passed"),
+            TxRejected::Internal(err) => write!(f, "internal error: {}", err),
+        }
+    }
+}
+
+/// check inputs = outputs (+ fee of coinId 0) for each coin, return the wrong coinId
+///
+/// note: sum up by u64 with overflow check
+fn check_balance(inputs: &[TxOutput], outputs: &[TxOutput], fee: u64) -> Result<(), u32> {
+    // (coinId, income, outcome)
+    let mut sums: Vec<(u32, u64, u64)> = vec![(0, 0, fee)];
+    for output in inputs.iter() {
+        match sums.iter_mut().find(|sum| sum.0 == output.1) {
+            Some(sum) => sum.1 = sum.1.checked_add(output.2).ok_or(output.1)?,
+            None => sums.push((output.1, output.2, 0)),
+        }
+    }
+    for output in outputs.iter() {
+        match sums.iter_mut().find(|sum| sum.0 == output.1) {
+            Some(sum) => sum.2 = sum.2.checked_add(output.2).ok_or(output.1)?,
+            None => sums.push((output.1, 0, output.2)),
+        }
+    }
+    match sums.iter().find(|(_, income, outcome)| income != outcome) {
+        Some((coin_id, _, _)) => Err(*coin_id),
+        None => Ok(()),
+    }
+}
+
+/// stateless rules of normal tx and return fee
+///
+/// note: `verified` is true if signatures are already verified in a batch
+fn check_tx_rules(tx: &TxVerifiable, verified: bool) -> Result<u64, TxRejected> {
+    let body = &tx.body;
+
+    // txtype
+    // note: minted coin is created or destroyed by the tx
+    let minted = match body.txtype {
+        TxType::Transfer => None,
+        TxType::Mint => Some(get_mint_info(tx).map_err(TxRejected::BadMint)?.0.coin_id),
+        ref txtype => return Err(TxRejected::BadType(txtype.clone())),
+    };
+
+    // inputs & outputs
+    if body.inputs.len() == 0 {
+        return Err(TxRejected::EmptyInputs);
+    }
+    if body.outputs.len() == 0 {
+        return Err(TxRejected::EmptyOutputs);
+    }
+    for (index, input) in body.inputs.iter().enumerate() {
+        if body.inputs[..index].contains(input) {
+            return Err(TxRejected::DuplicateInput(input.clone()));
+        }
+    }
+    if body.inputs.len() != tx.inputs_cache.len() {
+        return Err(TxRejected::InputsCacheMismatch);
+    }
+
+    // time
+    if body.deadline < body.time {
+        return Err(TxRejected::BadTime);
+    }
+
+    // signature
+    if !verified {
+        let hash = u256_to_bytes(&tx.hash);
+        let binary = body.to_bytes();
+        let items = tx
+            .signature
+            .iter()
+            .map(|signature| (&hash[..], signature, binary.as_slice()))
+            .collect::<Vec<_>>();
+        verify_batch_cached(&items).map_err(TxRejected::BadSignature)?;
+    }
+    let signers = tx
+        .signature
+        .iter()
+        .map(|signature| signature.get_address(0))
+        .collect::<Vec<Address>>();
+    // note: lock output is signed by the owner's address
+    for output in tx.inputs_cache.iter() {
+        if !signers.contains(&get_owner_addr(&output.0)) {
+            return Err(TxRejected::NotSigned(output.0));
+        }
+    }
+
+    // fee
+    if body.gas_amount < 0 {
+        return Err(TxRejected::BadFee);
+    }
+    let fee = body
+        .gas_price
+        .checked_mul(body.gas_amount as u64)
+        .ok_or(TxRejected::BadFee)?;
+
+    // balance: inputs = outputs + fee(coinId 0)
+    match minted {
+        Some(coin_id) => {
+            let is_counted = |output: &&TxOutput| output.1 != coin_id;
+            let inputs = tx
+                .inputs_cache
+                .iter()
+                .filter(is_counted)
+                .cloned()
+                .collect::<Vec<_>>();
+            let outputs = body
+                .outputs
+                .iter()
+                .filter(is_counted)
+                .cloned()
+                .collect::<Vec<_>>();
+            check_balance(&inputs, &outputs, fee)
+        },
+        None => check_balance(&tx.inputs_cache, &body.outputs, fee),
+    }
+    .map_err(TxRejected::Unbalanced)?;
+
+    Ok(fee)
+}
+
+/// check mint tx's issuer authority on the best_chain
+fn check_mint(chain: &Chain, tx: &TxVerifiable, best_chain: &BlockHashVec) -> Result<(), TxRejected> {
+    let (coin, amount) = get_mint_info(tx).map_err(TxRejected::BadMint)?;
+    let state = chain
+        .get_mint_state(coin.coin_id, best_chain)
+        .map_err(TxRejected::Internal)?;
+    // note: signature is already verified
+    let signers = tx
+        .signature
+        .iter()
+        .map(|signature| signature.get_address(0))
+        .collect::<Vec<Address>>();
+    MintState::check(state.as_ref(), &coin, amount, &signers).map_err(TxRejected::BadMint)
+}
+
+/// find the tx height from tables or best_chain
+///
+/// note: tables have lock tx's height even if tx_index is false
+fn find_tx_height(chain: &Chain, hash: &U256, best_chain: &BlockHashVec) -> Option<u32> {
+    if let Ok(height) = chain.tables.read_tx_height(hash) {
+        return Some(height);
+    }
+    for blockhash in best_chain.iter() {
+        let block = chain.confirmed.get_block_ref(blockhash).unwrap();
+        if block.txs_hash.contains(hash) {
+            return Some(block.height);
+        }
+    }
+    None
+}
+
+/// check lock outputs used by the tx are unlocked at the height
+fn check_lock(
+    chain: &Chain,
+    tx: &TxVerifiable,
+    height: u32,
+    best_chain: &BlockHashVec,
+) -> Result<(), TxRejected> {
+    for (input, cache) in tx.body.inputs.iter().zip(tx.inputs_cache.iter()) {
+        if !is_lock_output(cache) {
+            continue;
+        }
+        match find_tx_height(chain, &input.0, best_chain) {
+            Some(lock_height) if lock_height + LOCK_UNLOCK_HEIGHT < height => (),
+            _ => return Err(TxRejected::Locked(input.clone())),
+        }
+    }
+    Ok(())
+}
+
+/// standalone check of the tx going to be unconfirmed
+///
+/// note: check on best_chain, not include block context
+pub fn validate_tx(chain: &Chain, tx: &TxVerifiable) -> Result<(), TxRejected> {
+    if tx.hash != U256::from(tx.body.hash().as_slice()) {
+        return Err(TxRejected::BadHash);
+    }
+
+    // stateless rules
+    check_tx_rules(tx, false)?;
+
+    // deadline & lock
+    if let Some(blockhash) = chain.best_chain.first() {
+        let best_block = chain.confirmed.get_block_ref(blockhash).unwrap();
+        if tx.body.deadline < best_block.header.time {
+            return Err(TxRejected::Expired);
+        }
+        check_lock(chain, tx, best_block.height + 1, &chain.best_chain)?;
+    } else if let Some(index) = tx.inputs_cache.iter().position(is_lock_output) {
+        return Err(TxRejected::Locked(tx.body.inputs[index].clone()));
+    }
+
+    // inputs
+    for (input, cache) in tx.body.inputs.iter().zip(tx.inputs_cache.iter()) {
+        match chain
+            .get_output_of_input(input, true)
+            .map_err(TxRejected::Internal)?
+        {
+            Some(ref output) if output == cache => (),
+            _ => return Err(TxRejected::BadInput(input.clone())),
+        }
+        if !chain
+            .is_unused_input(input, &tx.hash, &None, &chain.best_chain)
+            .map_err(TxRejected::Internal)?
+        {
+            return Err(TxRejected::DoubleSpend(input.clone()));
+        }
+    }
+
+    // mint
+    if tx.body.txtype == TxType::Mint {
+        check_mint(chain, tx, &chain.best_chain)?;
+    }
+
+    // all check passed
+    Ok(())
+}
+
+/// find output of input from the block's previous txs or chain
+fn find_output(chain: &Chain, txs: &[TxVerifiable], input: &TxInput) -> Result<Option<TxOutput>, String> {
+    // note: tx can use output created by the previous tx in the same block, not the later
+    for tx in txs.iter() {
+        if tx.hash == input.0 {
+            return Ok(tx.body.outputs.get(input.1 as usize).cloned());
+        }
+    }
+    // note: ignore used flag because double spend is checked after
+    chain.get_output_of_input(input, true)
+}
+
+/// check inputs_cache is correct and inputs are unused, the tx is `index` of the block
+fn check_inputs(
+    chain: &Chain,
+    index: usize,
+    best_block: &Option<(Block, Vec<TxVerifiable>)>,
+    best_chain: &BlockHashVec,
+) -> Result<(), BlockRejected> {
+    let (_block, txs) = best_block.as_ref().unwrap();
+    let tx = &txs[index];
+    if tx.body.inputs.len() != tx.inputs_cache.len() {
+        return Err(BlockRejected::InputsCacheMismatch(tx.hash));
+    }
+    for (input, cache) in tx.body.inputs.iter().zip(tx.inputs_cache.iter()) {
+        // inputs_cache
+        match find_output(chain, &txs[..index], input).map_err(BlockRejected::Internal)? {
+            Some(ref output) if output == cache => (),
+            _ => return Err(BlockRejected::BadInput(tx.hash, input.clone())),
+        }
+        // double spend
+        if !chain
+            .is_unused_input(input, &tx.hash, best_block, best_chain)
+            .map_err(BlockRejected::Internal)?
+        {
+            return Err(BlockRejected::DoubleSpend(tx.hash, input.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// full consensus check of the best_block (Block(n+1) candidate)
+///
+/// note: expect `(Block, Vec<TxVerifiable>)` as best_block, None is rejected as empty
+pub fn check_block(
+    chain: &Chain,
+    diff: &mut DifficultyBuilder,
+    reward: &RewardBuilder,
+    best_block: &Option<(Block, Vec<TxVerifiable>)>,
+) -> Result<(), BlockRejected> {
+    let (block, txs) = match best_block {
+        Some((block, txs)) => (block, txs),
+        None => return Err(BlockRejected::EmptyTxs),
+    };
+
+    // txs
+    if txs.len() == 0 {
+        return Err(BlockRejected::EmptyTxs);
+    }
+    if block.txs_hash.len() != txs.len() {
+        return Err(BlockRejected::TxsMismatch(block.txs_hash.len().min(txs.len())));
+    }
+    for (index, (hash, tx)) in block.txs_hash.iter().zip(txs.iter()).enumerate() {
+        if hash != &tx.hash || tx.hash != U256::from(tx.body.hash().as_slice()) {
+            return Err(BlockRejected::TxsMismatch(index));
+        }
+    }
+
+    // merkleroot
+    if calc_merkleroot_hash(block.txs_hash.clone()) != block.header.merkleroot {
+        return Err(BlockRejected::BadMerkleroot);
+    }
+
+    // genesis block is exception
+    if block.flag == BlockFlag::Genesis || block.header.previous_hash == *GENESIS_PREVIOUS_HASH {
+        if block.flag != BlockFlag::Genesis
+            || block.header.previous_hash != *GENESIS_PREVIOUS_HASH
+            || block.height != 0
+            || txs.iter().any(|tx| tx.body.txtype != TxType::Genesis)
+        {
+            return Err(BlockRejected::BadGenesis);
+        }
+        return Ok(());
+    }
+
+    // previous block
+    // note: not allow to fork from finalized block
+    let (previous_height, previous_time) = match chain.confirmed.get_block_ref(&block.header.previous_hash) {
+        Some(previous) => (previous.height, previous.header.time),
+        None => return Err(BlockRejected::UnknownPrevious),
+    };
+    if block.height != previous_height + 1 {
+        return Err(BlockRejected::BadHeight(previous_height + 1));
+    }
+    if block.header.time < previous_time {
+        return Err(BlockRejected::BadBlockTime);
+    }
+
+    // bits & bias
+    let previous_hash = &block.header.previous_hash;
+    let bits = diff
+        .calc_next_bits(previous_hash, &block.flag, &chain.tables)
+        .map_err(BlockRejected::Internal)?;
+    if block.header.bits != bits {
+        return Err(BlockRejected::BadBits(bits));
+    }
+    let bias = diff
+        .calc_next_bias(previous_hash, &block.flag, &chain.tables)
+        .map_err(BlockRejected::Internal)?;
+    if block.bias != bias {
+        return Err(BlockRejected::BadBias(bias));
+    }
+
+    // coinbase
+    let coinbase = &txs[0];
+    let is_pow = match block.flag {
+        BlockFlag::YesPow | BlockFlag::X11Pow | BlockFlag::X16sPow => true,
+        _ => false,
+    };
+    let coinbase_type = if is_pow { TxType::PoW } else { TxType::PoS };
+    if coinbase.body.txtype != coinbase_type {
+        return Err(BlockRejected::BadCoinbase(format!(
+            "{:?} block require {:?} coinbase",
+            block.flag, coinbase_type
+        )));
+    }
+    if txs.iter().skip(1).any(|tx| tx.body.is_coinbase()) {
+        return Err(BlockRejected::BadCoinbase("coinbase is only first tx".to_owned()));
+    }
+    if coinbase.body.outputs.iter().any(|output| output.1 != 0) {
+        return Err(BlockRejected::BadCoinbase(
+            "coinbase output coinId is 0".to_owned(),
+        ));
+    }
+    let (input_len, sign_len) = match block.flag {
+        BlockFlag::CoinPos | BlockFlag::FlkPos => (1, 1),
+        BlockFlag::CapPos => (0, 1),
+        _ => (0, 0),
+    };
+    if coinbase.body.inputs.len() != input_len || coinbase.signature.len() != sign_len {
+        return Err(BlockRejected::BadCoinbase(format!(
+            "{:?} coinbase require {} input and {} signature",
+            block.flag, input_len, sign_len
+        )));
+    }
+
+    // staking signature
+    // note: staking signature is header's not tx's
+    let staker = if let Some(signature) = coinbase.signature.first() {
+        match verify_signature(signature, block.header.to_bytes().as_ref()) {
+            Ok(true) => Some(signature.get_address(0)),
+            _ => return Err(BlockRejected::BadSignature(coinbase.hash)),
+        }
+    } else {
+        None
+    };
+
+    // work
+    let input_cache = match block.flag {
+        BlockFlag::CoinPos | BlockFlag::FlkPos => coinbase.inputs_cache.first().cloned(),
+        // note: PoC coinbase don't have input, derive plot address from signature
+        BlockFlag::CapPos => staker.map(|addr| TxOutput(addr, 0, 0)),
+        _ => None,
+    };
+    if let Some(ref output) = input_cache {
+        if Some(get_owner_addr(&output.0)) != staker {
+            return Err(BlockRejected::BadCoinbase(
+                "signer isn't owner of input".to_owned(),
+            ));
+        }
+    }
+    let work_hash = get_work_hash(
+        &block.flag,
+        &block.header,
+        Some(&coinbase.body),
+        input_cache.as_ref(),
+    )
+    .map_err(BlockRejected::BadWorkHash)?;
+    if work_hash != block.work_hash {
+        return Err(BlockRejected::BadWorkHash("work_hash mismatch".to_owned()));
+    }
+    let target = bits_to_target(block.header.bits).map_err(BlockRejected::BadWorkHash)?;
+    if target <= work_hash {
+        return Err(BlockRejected::NotEnoughWork);
+    }
+
+    // best_chain of the block
+    let best_chain = chain
+        .confirmed
+        .get_best_chain_by(previous_hash)
+        .map_err(BlockRejected::Internal)?;
+
+    // fund lock
+    // note: FlkPos coinbase relock the mature lock output with reward
+    if block.flag == BlockFlag::FlkPos {
+        let lock = input_cache.as_ref().unwrap();
+        if coinbase.body.outputs.iter().any(|output| output.0 != lock.0) {
+            return Err(BlockRejected::BadCoinbase(
+                "FlkPos coinbase output is the input's lock address".to_owned(),
+            ));
+        }
+        match find_tx_height(chain, &coinbase.body.inputs[0].0, &best_chain) {
+            Some(height) if height + LOCK_MATURE_HEIGHT < block.height => (),
+            _ => return Err(BlockRejected::BadCoinbase("lock output isn't mature".to_owned())),
+        }
+    }
+
+    // signature of all txs in a batch
+    // note: coinbase is checked above, skip signatures verified on mempool
+    let binaries = txs
+        .iter()
+        .filter(|tx| !tx.body.is_coinbase())
+        .map(|tx| (tx, u256_to_bytes(&tx.hash), tx.body.to_bytes()))
+        .collect::<Vec<_>>();
+    let mut items = Vec::with_capacity(binaries.len());
+    let mut owners = Vec::with_capacity(binaries.len());
+    for (tx, hash, binary) in binaries.iter() {
+        for (index, signature) in tx.signature.iter().enumerate() {
+            items.push((&hash[..], signature, binary.as_slice()));
+            owners.push((tx.hash, index));
+        }
+    }
+    if let Err(position) = verify_batch_cached(&items) {
+        let (hash, index) = owners[position];
+        return Err(BlockRejected::BadTx(hash, TxRejected::BadSignature(index)));
+    }
+
+    // txs
+    let mut fees = 0u64;
+    let mut minted = Vec::new();
+    for (index, tx) in txs.iter().enumerate() {
+        // time & deadline
+        if block.header.time < tx.body.time || tx.body.deadline < block.header.time {
+            return Err(BlockRejected::BadTxTime(tx.hash));
+        }
+
+        // inputs
+        check_inputs(chain, index, best_block, &best_chain)?;
+
+        // skip coinbase because checked above
+        if tx.body.is_coinbase() {
+            continue;
+        }
+
+        // signature, balance & txtype
+        let fee = check_tx_rules(tx, true).map_err(|err| BlockRejected::BadTx(tx.hash, err))?;
+        fees = fees
+            .checked_add(fee)
+            .ok_or(BlockRejected::BadTx(tx.hash, TxRejected::BadFee))?;
+
+        // lock
+        check_lock(chain, tx, block.height, &best_chain).map_err(|err| BlockRejected::BadTx(tx.hash, err))?;
+
+        // mint
+        // note: only one mint tx of a coin is included by a block
+        if tx.body.txtype == TxType::Mint {
+            check_mint(chain, tx, &best_chain).map_err(|err| BlockRejected::BadTx(tx.hash, err))?;
+            let (coin, _amount) = get_mint_info(tx).unwrap();
+            if minted.contains(&coin.coin_id) {
+                let err = TxRejected::BadMint("duplicate mint in a block".to_owned());
+                return Err(BlockRejected::BadTx(tx.hash, err));
+            }
+            minted.push(coin.coin_id);
+        }
+    }
+
+    // reward
+    // note: amounts are given by the block, don't wrap
+    let limit = reward
+        .calc_block_reward(block.height)
+        .checked_add(fees)
+        .ok_or(BlockRejected::OverReward(u64::max_value(), fees))?;
+    let income = coinbase
+        .inputs_cache
+        .iter()
+        .try_fold(0u64, |sum, output| sum.checked_add(output.2))
+        .ok_or_else(|| BlockRejected::BadCoinbase("input amount overflow".to_owned()))?;
+    let outcome = coinbase
+        .body
+        .outputs
+        .iter()
+        .try_fold(0u64, |sum, output| sum.checked_add(output.2))
+        .ok_or(BlockRejected::OverReward(limit, u64::max_value()))?;
+    let real = outcome.saturating_sub(income);
+    if limit < real {
+        return Err(BlockRejected::OverReward(limit, real));
+    }
+
+    // all check passed
+    Ok(())
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod validate {
+    use crate::block::*;
+    use crate::chain::validate::*;
+    use crate::chain::*;
+    use crate::tx::*;
+    use crate::utils::*;
+    use bigint::U256;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    const COIN: u64 = 1_0000_0000;
+
+    /// chain on the genesis block which pay coinId 0 & 1 to account 0 and the lock address
+    ///
+    /// note: genesis outputs are written to utxo_index as if they're finalized
+    fn chain_fixture(dir: &Path) -> (Chain, [u8; 21], TxVerifiable) {
+        let sk = Some(b"1qwq53lmi8rapvcciqmuiorxdie5irwmw1dccbegkze9vjdpy7mz6nsd6j991a6b".to_vec());
+        let mut chain = Chain::new(dir, &sk, 100, false, false).unwrap();
+        let addr = chain.get_account_address(0, true).unwrap();
+        let mut body = TxBody::new(0, TxType::Genesis, 0, 0, 0, 0, TxMessage::Nothing);
+        body.outputs = vec![
+            TxOutput(addr, 0, 10000 * COIN),
+            TxOutput(addr, 0, 10000 * COIN),
+            TxOutput(addr, 1, 1000),
+            TxOutput(to_lock_addr(&addr), 0, 10000 * COIN),
+        ];
+        let genesis = TxVerifiable {
+            hash: U256::from(body.hash().as_slice()),
+            body,
+            signature: vec![],
+            inputs_cache: vec![],
+        };
+        let header = BlockHeader {
+            version: 0,
+            previous_hash: *GENESIS_PREVIOUS_HASH,
+            merkleroot: calc_merkleroot_hash(vec![genesis.hash]),
+            time: 100,
+            bits: 0x1f0fffff,
+            nonce: 0,
+        };
+        let block = Block::new(U256::zero(), 0, BlockFlag::Genesis, 1.0, header, vec![
+            genesis.hash,
+        ]);
+        let mut cur = chain.tables.transaction();
+        cur.write_utxo_index(&genesis.body).unwrap();
+        cur.commit().unwrap();
+        chain.push_new_block(block, &vec![genesis.clone()]).unwrap();
+        (chain, addr, genesis)
+    }
+
+    /// sign the body by owners of the inputs
+    fn sign_tx(chain: &Chain, body: TxBody, inputs_cache: Vec<TxOutput>) -> TxVerifiable {
+        let msg = body.to_bytes();
+        let mut owners = inputs_cache
+            .iter()
+            .map(|output| get_owner_addr(&output.0))
+            .collect::<Vec<_>>();
+        owners.dedup();
+        let signature = owners
+            .iter()
+            .map(|owner| chain.account.get_single_sign_by_addr(owner, &msg).unwrap())
+            .collect();
+        TxVerifiable {
+            hash: U256::from(body.hash().as_slice()),
+            body,
+            signature,
+            inputs_cache,
+        }
+    }
+
+    /// transfer tx which pay `fee` by gas_price 1
+    fn transfer(
+        chain: &Chain,
+        inputs: Vec<(TxInput, TxOutput)>,
+        outputs: Vec<TxOutput>,
+        fee: i64,
+    ) -> TxVerifiable {
+        let mut body = TxBody::new(0, TxType::Transfer, 100, 100 + 10800, 1, fee, TxMessage::Nothing);
+        let (inputs, inputs_cache) = inputs.into_iter().unzip();
+        body.inputs = inputs;
+        body.outputs = outputs;
+        sign_tx(chain, body, inputs_cache)
+    }
+
+    fn coinbase(txtype: TxType, outputs: Vec<TxOutput>, time: u32) -> TxVerifiable {
+        let mut body = TxBody::new(0, txtype, time, time + 10800, 0, 0, TxMessage::Nothing);
+        body.outputs = outputs;
+        TxVerifiable {
+            hash: U256::from(body.hash().as_slice()),
+            body,
+            signature: vec![],
+            inputs_cache: vec![],
+        }
+    }
+
+    /// search nonce which met the target of the bits
+    fn seal(block: &mut Block) {
+        let target = bits_to_target(block.header.bits).unwrap();
+        loop {
+            block.work_hash = get_work_hash(&block.flag, &block.header, None, None).unwrap();
+            if block.work_hash < target {
+                break;
+            }
+            block.header.nonce += 1;
+        }
+    }
+
+    /// the next block on the best block, PoW is mined and PoS coinbase is signed by the staker
+    ///
+    /// note: PoS coinbase's deadline is changed until the work met the target
+    fn new_block(
+        chain: &Chain,
+        diff: &mut DifficultyBuilder,
+        flag: BlockFlag,
+        mut txs: Vec<TxVerifiable>,
+        time: u32,
+    ) -> Option<(Block, Vec<TxVerifiable>)> {
+        let previous = chain.get_best_block_ref();
+        let previous_hash = previous.header.hash();
+        let bits = diff.calc_next_bits(&previous_hash, &flag, &chain.tables).unwrap();
+        let bias = diff.calc_next_bias(&previous_hash, &flag, &chain.tables).unwrap();
+        let header = BlockHeader {
+            version: 0,
+            previous_hash,
+            merkleroot: U256::zero(),
+            time,
+            bits,
+            nonce: 0,
+        };
+        let mut block = Block::new(U256::zero(), previous.height + 1, flag, bias, header, vec![]);
+        let is_pos = block.flag == BlockFlag::CoinPos || block.flag == BlockFlag::FlkPos;
+        if is_pos {
+            let target = bits_to_target(bits).unwrap();
+            let coinbase = &mut txs[0];
+            loop {
+                let input = coinbase.inputs_cache.first();
+                block.work_hash =
+                    get_work_hash(&block.flag, &block.header, Some(&coinbase.body), input).unwrap();
+                if block.work_hash < target {
+                    break;
+                }
+                coinbase.body.deadline += 1;
+            }
+            coinbase.hash = U256::from(coinbase.body.hash().as_slice());
+        }
+        block.txs_hash = txs.iter().map(|tx| tx.hash).collect();
+        block.header.merkleroot = calc_merkleroot_hash(block.txs_hash.clone());
+        if is_pos {
+            let staker = get_owner_addr(&txs[0].inputs_cache[0].0);
+            let signature = chain
+                .account
+                .get_single_sign_by_addr(&staker, &block.header.to_bytes())
+                .unwrap();
+            txs[0].signature = vec![signature];
+        } else {
+            seal(&mut block);
+        }
+        Some((block, txs))
+    }
+
+    #[test]
+    fn structure() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let sk = Some(b"1qwq53lmi8rapvcciqmuiorxdie5irwmw1dccbegkze9vjdpy7mz6nsd6j991a6b".to_vec());
+        let chain = Chain::new(dir.as_ref(), &sk, 100, false, false).unwrap();
+        let mut diff = DifficultyBuilder::new(vec![(BlockFlag::YesPow, 60, 30, 60)]);
+        let reward = RewardBuilder::new(100_0000_0000_0000_0000);
+
+        let coinbase = TxBody::new(0, TxType::PoW, 0, 10800, 0, 0, TxMessage::Nothing);
+        let hash = U256::from(coinbase.hash().as_slice());
+        let header = BlockHeader {
+            version: 0,
+            previous_hash: U256::zero(),
+            merkleroot: U256::zero(), // wrong
+            time: 0,
+            bits: 0,
+            nonce: 0,
+        };
+        let block = Block::new(U256::zero(), 1, BlockFlag::YesPow, 1.0, header, vec![hash]);
+        let coinbase = TxVerifiable {
+            hash,
+            body: coinbase,
+            signature: vec![],
+            inputs_cache: vec![],
+        };
+
+        // no block
+        let result = check_block(&chain, &mut diff, &reward, &None);
+        assert_eq!(result, Err(BlockRejected::EmptyTxs));
+
+        // merkleroot
+        let mut best_block = Some((block, vec![coinbase]));
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        assert_eq!(result, Err(BlockRejected::BadMerkleroot));
+
+        // unknown previous
+        best_block.as_mut().unwrap().0.header.merkleroot = hash;
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        assert_eq!(result, Err(BlockRejected::UnknownPrevious));
+
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn balance() {
+        let addr = [0u8; 21];
+        let inputs = vec![TxOutput(addr, 0, 10000), TxOutput(addr, 1, 500)];
+        let outputs = vec![TxOutput(addr, 0, 9000), TxOutput(addr, 1, 500)];
+        assert_eq!(check_balance(&inputs, &outputs, 1000), Ok(()));
+        assert_eq!(check_balance(&inputs, &outputs, 999), Err(0));
+        assert_eq!(check_balance(&inputs, &outputs[..1], 1000), Err(1));
+
+        // overflow is not wrapped
+        let outputs = vec![
+            TxOutput(addr, 0, u64::max_value()),
+            TxOutput(addr, 0, 10001),
+            TxOutput(addr, 1, 500),
+        ];
+        assert_eq!(check_balance(&inputs, &outputs, 0), Err(0));
+    }
+
+    #[test]
+    fn tx_rules() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let sk = Some(b"1qwq53lmi8rapvcciqmuiorxdie5irwmw1dccbegkze9vjdpy7mz6nsd6j991a6b".to_vec());
+        let chain = Chain::new(dir.as_ref(), &sk, 100, false, false).unwrap();
+
+        // fee 100*10 = 10000 - 9000
+        let addr = [0u8; 21];
+        let mut body = TxBody::new(0, TxType::Transfer, 0, 10800, 100, 10, TxMessage::Nothing);
+        body.inputs.push(TxInput(U256::from(1u32), 0));
+        body.outputs.push(TxOutput(addr, 0, 9000));
+        let mut tx = TxVerifiable {
+            hash: U256::zero(),
+            body,
+            signature: vec![],
+            inputs_cache: vec![TxOutput(addr, 0, 10000)],
+        };
+
+        // hash
+        assert_eq!(validate_tx(&chain, &tx), Err(TxRejected::BadHash));
+
+        // signature
+        tx.hash = U256::from(tx.body.hash().as_slice());
+        assert_eq!(validate_tx(&chain, &tx), Err(TxRejected::NotSigned(addr)));
+
+        // txtype
+        tx.body.txtype = TxType::PoW;
+        tx.hash = U256::from(tx.body.hash().as_slice());
+        assert_eq!(validate_tx(&chain, &tx), Err(TxRejected::BadType(TxType::PoW)));
+
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn accept_block() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let (mut chain, addr, genesis) = chain_fixture(dir.as_ref());
+        let mut diff = DifficultyBuilder::new(vec![(BlockFlag::X11Pow, 60, 30, 60)]);
+        let reward = RewardBuilder::new(100_0000_0000_0000_0000);
+
+        // tx1 use the output of tx0 in the same block
+        let input0 = (TxInput(genesis.hash, 0), genesis.body.outputs[0].clone());
+        let tx0 = transfer(
+            &chain,
+            vec![input0],
+            vec![TxOutput(addr, 0, 10000 * COIN - 1000)],
+            1000,
+        );
+        let input1 = (TxInput(tx0.hash, 0), tx0.body.outputs[0].clone());
+        let tx1 = transfer(
+            &chain,
+            vec![input1],
+            vec![TxOutput(addr, 0, 10000 * COIN - 2000)],
+            1000,
+        );
+        let limit = reward.calc_block_reward(1) + 2000;
+        let outputs = vec![TxOutput(addr, 0, limit)];
+        let txs = vec![
+            coinbase(TxType::PoW, outputs.clone(), 200),
+            tx0.clone(),
+            tx1.clone(),
+        ];
+        let best_block = new_block(&chain, &mut diff, BlockFlag::X11Pow, txs, 200);
+        assert_eq!(check_block(&chain, &mut diff, &reward, &best_block), Ok(()));
+
+        // output of the later tx isn't usable
+        let txs = vec![coinbase(TxType::PoW, outputs, 200), tx1.clone(), tx0.clone()];
+        let reversed = new_block(&chain, &mut diff, BlockFlag::X11Pow, txs, 200);
+        let result = check_block(&chain, &mut diff, &reward, &reversed);
+        assert_eq!(
+            result,
+            Err(BlockRejected::BadInput(tx1.hash, TxInput(tx0.hash, 0)))
+        );
+
+        // next block on the accepted
+        let (block, txs) = best_block.unwrap();
+        chain.push_new_block(block, &txs).unwrap();
+        let outputs = vec![TxOutput(addr, 0, reward.calc_block_reward(2))];
+        let txs = vec![coinbase(TxType::PoW, outputs, 300)];
+        let best_block = new_block(&chain, &mut diff, BlockFlag::X11Pow, txs, 300);
+        assert_eq!(check_block(&chain, &mut diff, &reward, &best_block), Ok(()));
+
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn block_header() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let (chain, addr, _genesis) = chain_fixture(dir.as_ref());
+        let mut diff = DifficultyBuilder::new(vec![(BlockFlag::X11Pow, 60, 30, 60)]);
+        let reward = RewardBuilder::new(100_0000_0000_0000_0000);
+        let outputs = vec![TxOutput(addr, 0, reward.calc_block_reward(1))];
+        let txs = vec![coinbase(TxType::PoW, outputs, 200)];
+        let base = new_block(&chain, &mut diff, BlockFlag::X11Pow, txs, 200);
+        assert_eq!(check_block(&chain, &mut diff, &reward, &base), Ok(()));
+
+        // txs_hash
+        let mut best_block = base.clone();
+        best_block.as_mut().unwrap().0.txs_hash.push(U256::from(1u32));
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        assert_eq!(result, Err(BlockRejected::TxsMismatch(1)));
+
+        // genesis flag on the chain
+        let mut best_block = base.clone();
+        best_block.as_mut().unwrap().0.flag = BlockFlag::Genesis;
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        assert_eq!(result, Err(BlockRejected::BadGenesis));
+
+        // height
+        let mut best_block = base.clone();
+        best_block.as_mut().unwrap().0.height = 2;
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        assert_eq!(result, Err(BlockRejected::BadHeight(1)));
+
+        // time is older than genesis
+        let mut best_block = base.clone();
+        best_block.as_mut().unwrap().0.header.time = 50;
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        assert_eq!(result, Err(BlockRejected::BadBlockTime));
+
+        // bits
+        let mut best_block = base.clone();
+        let bits = best_block.as_ref().unwrap().0.header.bits;
+        best_block.as_mut().unwrap().0.header.bits = bits - 1;
+        seal(&mut best_block.as_mut().unwrap().0);
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        assert_eq!(result, Err(BlockRejected::BadBits(bits)));
+
+        // bias
+        let mut best_block = base.clone();
+        best_block.as_mut().unwrap().0.bias = 2.0;
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        assert_eq!(result, Err(BlockRejected::BadBias(1.0)));
+
+        // flag without difficulty params
+        let mut best_block = base.clone();
+        best_block.as_mut().unwrap().0.flag = BlockFlag::CoinPos;
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        let err = "not found block time params of CoinPos".to_owned();
+        assert_eq!(result, Err(BlockRejected::Internal(err)));
+
+        // work_hash isn't the header's
+        let mut best_block = base.clone();
+        best_block.as_mut().unwrap().0.work_hash = base.as_ref().unwrap().0.work_hash + U256::from(1u32);
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        let err = "work_hash mismatch".to_owned();
+        assert_eq!(result, Err(BlockRejected::BadWorkHash(err)));
+
+        // nonce don't met the target
+        let mut best_block = base.clone();
+        let block = &mut best_block.as_mut().unwrap().0;
+        let target = bits_to_target(block.header.bits).unwrap();
+        while block.work_hash < target {
+            block.header.nonce += 1;
+            block.work_hash = get_work_hash(&block.flag, &block.header, None, None).unwrap();
+        }
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        assert_eq!(result, Err(BlockRejected::NotEnoughWork));
+
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn block_coinbase() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let (chain, addr, genesis) = chain_fixture(dir.as_ref());
+        let mut diff = DifficultyBuilder::new(vec![
+            (BlockFlag::X11Pow, 60, 30, 60),
+            (BlockFlag::CoinPos, 60, 30, 60),
+        ]);
+        let reward = RewardBuilder::new(100_0000_0000_0000_0000);
+        let limit = reward.calc_block_reward(1);
+
+        // PoS coinbase in PoW block
+        let txs = vec![coinbase(TxType::PoS, vec![TxOutput(addr, 0, limit)], 200)];
+        let best_block = new_block(&chain, &mut diff, BlockFlag::X11Pow, txs, 200);
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        let err = "X11Pow block require PoW coinbase".to_owned();
+        assert_eq!(result, Err(BlockRejected::BadCoinbase(err)));
+
+        // over reward
+        let txs = vec![coinbase(TxType::PoW, vec![TxOutput(addr, 0, limit + 1)], 200)];
+        let best_block = new_block(&chain, &mut diff, BlockFlag::X11Pow, txs, 200);
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        assert_eq!(result, Err(BlockRejected::OverReward(limit, limit + 1)));
+
+        // CoinPos staking the genesis output
+        let stake = genesis.body.outputs[0].clone();
+        let mut staking = coinbase(TxType::PoS, vec![TxOutput(addr, 0, stake.2 + limit)], 200);
+        staking.body.inputs.push(TxInput(genesis.hash, 0));
+        staking.inputs_cache.push(stake);
+        let best_block = new_block(&chain, &mut diff, BlockFlag::CoinPos, vec![staking], 200);
+        assert_eq!(check_block(&chain, &mut diff, &reward, &best_block), Ok(()));
+
+        // staking signature isn't the header's
+        let mut wrong = best_block.clone();
+        let coinbase = &mut wrong.as_mut().unwrap().1[0];
+        coinbase.signature = vec![chain.account.get_single_sign_by_addr(&addr, b"hello").unwrap()];
+        let hash = coinbase.hash;
+        let result = check_block(&chain, &mut diff, &reward, &wrong);
+        assert_eq!(result, Err(BlockRejected::BadSignature(hash)));
+
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn block_txs() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let (mut chain, addr, genesis) = chain_fixture(dir.as_ref());
+        let mut diff = DifficultyBuilder::new(vec![(BlockFlag::X11Pow, 60, 30, 60)]);
+        let reward = RewardBuilder::new(100_0000_0000_0000_0000);
+        let limit = reward.calc_block_reward(1) + 1000;
+        let cb = coinbase(TxType::PoW, vec![TxOutput(addr, 0, limit)], 200);
+        let mut check = |txs: Vec<TxVerifiable>, chain: &Chain| {
+            let best_block = new_block(chain, &mut diff, BlockFlag::X11Pow, txs, 200);
+            check_block(chain, &mut diff, &reward, &best_block)
+        };
+
+        let input0 = TxInput(genesis.hash, 0);
+        let unspent0 = (input0.clone(), genesis.body.outputs[0].clone());
+        let tx = transfer(
+            &chain,
+            vec![unspent0.clone()],
+            vec![TxOutput(addr, 0, 10000 * COIN - 1000)],
+            1000,
+        );
+        assert_eq!(check(vec![cb.clone(), tx.clone()], &chain), Ok(()));
+
+        // inputs_cache length
+        let mut wrong = tx.clone();
+        wrong.inputs_cache.push(genesis.body.outputs[1].clone());
+        let result = check(vec![cb.clone(), wrong], &chain);
+        assert_eq!(result, Err(BlockRejected::InputsCacheMismatch(tx.hash)));
+
+        // inputs_cache isn't the output
+        let mut wrong = tx.clone();
+        wrong.inputs_cache[0].2 += 1;
+        let result = check(vec![cb.clone(), wrong], &chain);
+        assert_eq!(result, Err(BlockRejected::BadInput(tx.hash, input0.clone())));
+
+        // double spend in the block
+        let other = transfer(
+            &chain,
+            vec![unspent0.clone()],
+            vec![TxOutput(addr, 0, 10000 * COIN - 500)],
+            500,
+        );
+        let result = check(vec![cb.clone(), tx.clone(), other.clone()], &chain);
+        assert_eq!(result, Err(BlockRejected::DoubleSpend(tx.hash, input0.clone())));
+
+        // tx time is after block time
+        let mut body = tx.body.clone();
+        body.time = 300;
+        let late = sign_tx(&chain, body, tx.inputs_cache.clone());
+        let result = check(vec![cb.clone(), late.clone()], &chain);
+        assert_eq!(result, Err(BlockRejected::BadTxTime(late.hash)));
+
+        // signature of other message
+        let mut wrong = tx.clone();
+        wrong.signature = vec![chain.account.get_single_sign_by_addr(&addr, b"hello").unwrap()];
+        let result = check(vec![cb.clone(), wrong], &chain);
+        assert_eq!(
+            result,
+            Err(BlockRejected::BadTx(tx.hash, TxRejected::BadSignature(0)))
+        );
+
+        // coinId 1 is created
+        let unspent2 = (TxInput(genesis.hash, 2), genesis.body.outputs[2].clone());
+        let outputs = vec![TxOutput(addr, 0, 10000 * COIN - 1000), TxOutput(addr, 1, 2000)];
+        let wrong = transfer(&chain, vec![unspent0.clone(), unspent2.clone()], outputs, 1000);
+        let result = check(vec![cb.clone(), wrong.clone()], &chain);
+        assert_eq!(
+            result,
+            Err(BlockRejected::BadTx(wrong.hash, TxRejected::Unbalanced(1)))
+        );
+
+        // mint tx without coin
+        let mut body = wrong.body.clone();
+        body.txtype = TxType::Mint;
+        let wrong = sign_tx(&chain, body, wrong.inputs_cache.clone());
+        let result = check(vec![cb.clone(), wrong.clone()], &chain);
+        let err = TxRejected::BadMint("mint tx's message is Byte type".to_owned());
+        assert_eq!(result, Err(BlockRejected::BadTx(wrong.hash, err)));
+
+        // lock output isn't unlocked
+        let input3 = TxInput(genesis.hash, 3);
+        let unspent3 = (input3.clone(), genesis.body.outputs[3].clone());
+        let wrong = transfer(
+            &chain,
+            vec![unspent3],
+            vec![TxOutput(addr, 0, 10000 * COIN - 1000)],
+            1000,
+        );
+        let result = check(vec![cb.clone(), wrong.clone()], &chain);
+        assert_eq!(
+            result,
+            Err(BlockRejected::BadTx(wrong.hash, TxRejected::Locked(input3)))
+        );
+
+        // double spend on the chain
+        let (block, txs) =
+            new_block(&chain, &mut diff, BlockFlag::X11Pow, vec![cb.clone(), tx], 200).unwrap();
+        chain.push_new_block(block, &txs).unwrap();
+        let limit = reward.calc_block_reward(2) + 500;
+        let cb = coinbase(TxType::PoW, vec![TxOutput(addr, 0, limit)], 300);
+        let best_block = new_block(&chain, &mut diff, BlockFlag::X11Pow, vec![cb, other.clone()], 300);
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        assert_eq!(result, Err(BlockRejected::DoubleSpend(other.hash, input0)));
+
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn tx_rejected() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let (mut chain, addr, genesis) = chain_fixture(dir.as_ref());
+        let unspent0 = (TxInput(genesis.hash, 0), genesis.body.outputs[0].clone());
+        let outputs = vec![TxOutput(addr, 0, 10000 * COIN - 1000)];
+        let tx = transfer(&chain, vec![unspent0.clone()], outputs.clone(), 1000);
+        assert_eq!(validate_tx(&chain, &tx), Ok(()));
+
+        // modify the body and sign again
+        let resign = |f: &dyn Fn(&mut TxBody, &mut Vec<TxOutput>)| {
+            let mut body = tx.body.clone();
+            let mut inputs_cache = tx.inputs_cache.clone();
+            f(&mut body, &mut inputs_cache);
+            sign_tx(&chain, body, inputs_cache)
+        };
+
+        let wrong = resign(&|body, cache| {
+            body.inputs.clear();
+            cache.clear()
+        });
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::EmptyInputs));
+        let wrong = resign(&|body, _| body.outputs.clear());
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::EmptyOutputs));
+        let wrong = resign(&|body, cache| {
+            body.inputs.push(body.inputs[0].clone());
+            cache.push(cache[0].clone())
+        });
+        let result = validate_tx(&chain, &wrong);
+        assert_eq!(result, Err(TxRejected::DuplicateInput(unspent0.0.clone())));
+        let wrong = resign(&|_, cache| cache.push(cache[0].clone()));
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::InputsCacheMismatch));
+        let wrong = resign(&|body, _| body.deadline = body.time - 1);
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::BadTime));
+        let wrong = resign(&|body, _| body.gas_amount = -1);
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::BadFee));
+        let wrong = resign(&|body, _| body.gas_price = u64::max_value());
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::BadFee));
+        let wrong = resign(&|body, _| body.gas_amount = 999);
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::Unbalanced(0)));
+        let wrong = resign(&|body, _| body.txtype = TxType::Mint);
+        let err = "mint tx's message is Byte type".to_owned();
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::BadMint(err)));
+
+        // genesis time is 100
+        let wrong = resign(&|body, _| {
+            body.time = 0;
+            body.deadline = 99
+        });
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::Expired));
+
+        // signature of other message
+        let mut wrong = tx.clone();
+        wrong.signature = vec![chain.account.get_single_sign_by_addr(&addr, b"hello").unwrap()];
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::BadSignature(0)));
+
+        // output not found
+        let input = TxInput(genesis.hash, 9);
+        let wrong = resign(&|body, _| body.inputs[0] = TxInput(genesis.hash, 9));
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::BadInput(input)));
+
+        // lock output
+        let input3 = TxInput(genesis.hash, 3);
+        let unspent3 = (input3.clone(), genesis.body.outputs[3].clone());
+        let wrong = transfer(&chain, vec![unspent3], outputs.clone(), 1000);
+        assert_eq!(validate_tx(&chain, &wrong), Err(TxRejected::Locked(input3)));
+
+        // double spend on the unconfirmed
+        let other = transfer(
+            &chain,
+            vec![unspent0.clone()],
+            vec![TxOutput(addr, 0, 10000 * COIN - 500)],
+            500,
+        );
+        chain.push_unconfirmed(&tx).unwrap();
+        assert_eq!(
+            validate_tx(&chain, &other),
+            Err(TxRejected::DoubleSpend(unspent0.0))
+        );
+
+        chain.tables.close_and_destroy();
+    }
+
+    #[test]
+    fn flk_pos_block() {
+        let tmp = tempdir().unwrap();
+        let dir = tmp.path().join("database");
+        let (mut chain, addr, genesis) = chain_fixture(dir.as_ref());
+        let mut diff = DifficultyBuilder::new(vec![(BlockFlag::X11Pow, 60, 30, 60), FLK_POS_PARAMS]);
+        let reward = RewardBuilder::new(100_0000_0000_0000_0000);
+
+        // stake the lock output of genesis and relock with reward
+        let lock = genesis.body.outputs[3].clone();
+        let flk_coinbase = |height: u32, time: u32, to: [u8; 21]| {
+            let amount = lock.2 + reward.calc_block_reward(height);
+            let mut coinbase = coinbase(TxType::PoS, vec![TxOutput(to, 0, amount)], time);
+            coinbase.body.inputs.push(TxInput(genesis.hash, 3));
+            coinbase.inputs_cache.push(lock.clone());
+            coinbase
+        };
+        let push_pow_block = |chain: &mut Chain, diff: &mut DifficultyBuilder| {
+            let height = chain.get_best_block_ref().height + 1;
+            let time = 100 + 60 * height;
+            let outputs = vec![TxOutput(addr, 0, reward.calc_block_reward(height))];
+            let txs = vec![coinbase(TxType::PoW, outputs, time)];
+            let (block, txs) = new_block(chain, diff, BlockFlag::X11Pow, txs, time).unwrap();
+            chain.push_new_block(block, &txs).unwrap();
+        };
+
+        // lock output isn't mature at height 20
+        for _ in 0..19 {
+            push_pow_block(&mut chain, &mut diff);
+        }
+        let txs = vec![flk_coinbase(20, 1300, lock.0)];
+        let best_block = new_block(&chain, &mut diff, BlockFlag::FlkPos, txs, 1300);
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        let err = "lock output isn't mature".to_owned();
+        assert_eq!(result, Err(BlockRejected::BadCoinbase(err)));
+
+        // mature at height 21
+        push_pow_block(&mut chain, &mut diff);
+        let txs = vec![flk_coinbase(21, 1360, lock.0)];
+        let best_block = new_block(&chain, &mut diff, BlockFlag::FlkPos, txs, 1360);
+        assert_eq!(check_block(&chain, &mut diff, &reward, &best_block), Ok(()));
+
+        // reward isn't relocked
+        let txs = vec![flk_coinbase(21, 1360, addr)];
+        let best_block = new_block(&chain, &mut diff, BlockFlag::FlkPos, txs, 1360);
+        let result = check_block(&chain, &mut diff, &reward, &best_block);
+        let err = "FlkPos coinbase output is the input's lock address".to_owned();
+        assert_eq!(result, Err(BlockRejected::BadCoinbase(err)));
+
+        chain.tables.close_and_destroy();
+    }
+}
@@ -2,7 +2,9 @@ pub mod pyaccount;
 pub mod pyaddr;
 pub mod pyblock;
 pub mod pychain;
+pub mod pypartial;
 pub mod pysigature;
+pub mod pyspv;
 pub mod pytx;
 pub mod pyunspent;
 pub mod pyvalidate;
@@ -22,7 +24,12 @@ fn bc4py_core(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<pytx::PyTxInputs>()?;
     m.add_class::<pytx::PyTxOutputs>()?;
     m.add_class::<pysigature::PySignature>()?;
+    m.add_class::<pysigature::PyMuSigSession>()?;
+    m.add_class::<pysigature::PyThresholdKeyGen>()?;
+    m.add_class::<pysigature::PyThresholdShare>()?;
     m.add_class::<pychain::PyChain>()?;
     m.add_class::<pyunspent::PyUnspent>()?;
+    m.add_class::<pyspv::PyHeaderChain>()?;
+    m.add_class::<pypartial::PyPartialTx>()?;
     Ok(())
 }
@@ -1,4 +1,4 @@
-use crate::tx::{params2bech, string2addr};
+use crate::tx::{params2bech, string2addr, to_lock_addr};
 use crate::utils::write_slice;
 use pyo3::basic::CompareOp;
 use pyo3::exceptions::ValueError;
@@ -97,6 +97,13 @@ impl PyAddress {
         // return 21 bytes
         PyBytes::new(py, &self.addr).to_object(py)
     }
+
+    fn to_lock_address(&self) -> PyAddress {
+        // fund-lock address of FlkPos staking
+        PyAddress {
+            addr: to_lock_addr(&self.addr),
+        }
+    }
 }
 
 impl fmt::Debug for PyAddress {
@@ -768,6 +768,28 @@ impl PyBlock {
         })
     }
 
+    pub fn from_verifiable_block(py: Python, block: Block, txs: Vec<TxVerifiable>) -> PyResult<Self> {
+        // moved (mined block's txs have inputs_cache)
+        let mut vec = Vec::with_capacity(txs.len());
+        for tx in txs.into_iter() {
+            vec.push(Py::new(py, PyTx::from_verifiable(py, tx)?)?);
+        }
+        let txs = PyTxs {
+            iter_index: None,
+            txs: PyTxsEnum::Objects(vec),
+        };
+        let txs: _ = PyCell::new(py, txs)?;
+        Ok(PyBlock {
+            work_hash: Some(block.work_hash),
+            height: block.height,
+            flag: block.flag,
+            bias: block.bias,
+            header: block.header,
+            txs: txs.into(),
+            create_time: get_current_time(),
+        })
+    }
+
     #[allow(dead_code)]
     fn clone_to_block(&self, py: Python) -> PyResult<Block> {
         // clone
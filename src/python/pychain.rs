@@ -1,15 +1,20 @@
 use crate::balance::*;
-use crate::chain::Chain;
+use crate::chain::builder::{SweepSource, TxBuilder};
+use crate::chain::mnemonic::{generate_mnemonic, mnemonic_to_root_key, validate_mnemonic};
+use crate::chain::{filter::BlockFilter, Chain};
+use crate::python::pypartial::PyPartialTx;
 use crate::python::pyunspent::PyUnspent;
 use crate::python::{pyaccount::*, pyaddr::PyAddress, pyblock::PyBlock, pytx::PyTx};
-use crate::tx::{TxInput, TxOutput};
+use crate::tx::{MintCoin, MintParams, MintSetting, TxInput, TxMessage, TxOutput};
+use crate::utils::{u256_to_bytes, verify_merkle_branch};
 use bigint::U256;
 use pyo3::exceptions::{AssertionError, TypeError, ValueError};
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
+use pyo3::types::{PyBytes, PyDict, PyType};
 use std::cmp::{Ordering, PartialOrd};
 use std::path::Path;
 use std::sync::{Arc, Mutex, MutexGuard};
+use zeroize::Zeroizing;
 
 type Address = [u8; 21];
 pub type SharedChain = Arc<Mutex<Chain>>;
@@ -35,6 +40,7 @@ impl PyChain {
 
         let sk = sk.map(|bytes| bytes.as_bytes().to_vec());
         // sk is BIP32 root secret extended key => m/44'/CoinType'
+        // no sk means watch-only node, add accounts by `add_watch_only_account`
         // account generation require secret key because harden derive => m/44'/CoinType'/account_id'
         // account address derive do NOT require sk => m/44'/CoinType'/account_id'/isInner/index
 
@@ -46,6 +52,40 @@ impl PyChain {
         }
     }
 
+    #[classmethod]
+    fn from_mnemonic(
+        _cls: &PyType,
+        root_dir: &str,
+        mnemonic: &str,
+        passphrase: Option<&str>,
+        coin_type: u32,
+        deadline: u32,
+        tx_index: bool,
+        addr_index: bool,
+    ) -> PyResult<Self> {
+        // BIP39 mnemonic => seed => m/44'/CoinType' (same layout with `sk`)
+        let sk = mnemonic_to_root_key(mnemonic, passphrase.unwrap_or(""), coin_type)
+            .map_err(|err| ValueError::py_err(format!("invalid mnemonic: {}", err)))?;
+        let sk = Zeroizing::new(Some(sk.to_vec()));
+        let dir = Path::new(root_dir).join("database");
+        match Chain::new(dir.as_ref(), &sk, deadline, tx_index, addr_index) {
+            Ok(chain) => Ok(PyChain {
+                chain: Arc::new(Mutex::new(chain)),
+            }),
+            Err(err) => Err(ValueError::py_err(err)),
+        }
+    }
+
+    #[staticmethod]
+    fn generate_mnemonic(words: usize) -> PyResult<String> {
+        generate_mnemonic(words).map_err(|err| ValueError::py_err(err))
+    }
+
+    #[staticmethod]
+    fn validate_mnemonic(mnemonic: &str) -> bool {
+        validate_mnemonic(mnemonic).is_ok()
+    }
+
     fn push_new_block(&self, py: Python, block: PyRef<PyBlock>) -> PyResult<()> {
         let mut chain = self.lock();
         if chain.tables.is_closed {
@@ -110,6 +150,57 @@ impl PyChain {
         }
     }
 
+    fn get_block_filter(&self, py: Python, hash: &PyBytes) -> PyResult<Option<PyObject>> {
+        // compact block filter over output addresses and spent inputs
+        let chain = self.lock();
+        let hash = hash.as_bytes();
+        if hash.len() != 32 {
+            return Err(TypeError::py_err("hash is 32 bytes"));
+        }
+        match chain
+            .get_block_filter(&U256::from(hash))
+            .map_err(|_err| ValueError::py_err(_err))?
+        {
+            Some(filter) => Ok(Some(PyBytes::new(py, &filter.to_bytes()).to_object(py))),
+            None => Ok(None),
+        }
+    }
+
+    #[staticmethod]
+    fn match_block_filter(hash: &PyBytes, filter: &PyBytes, addrs: Vec<PyRef<PyAddress>>) -> PyResult<bool> {
+        // note: false positive is possible but false negative isn't
+        let hash = hash.as_bytes();
+        if hash.len() != 32 {
+            return Err(TypeError::py_err("hash is 32 bytes"));
+        }
+        let filter = BlockFilter::from_bytes(filter.as_bytes()).map_err(|_err| ValueError::py_err(_err))?;
+        let addrs = addrs
+            .iter()
+            .map(|_addr| _addr.addr.clone())
+            .collect::<Vec<Address>>();
+        filter
+            .match_addrs(&U256::from(hash), &addrs)
+            .map_err(|_err| ValueError::py_err(_err))
+    }
+
+    fn match_account_filter(&self, hash: &PyBytes) -> PyResult<bool> {
+        // check the block may include account tx, error if filter not found
+        let chain = self.lock();
+        let hash = hash.as_bytes();
+        if hash.len() != 32 {
+            return Err(TypeError::py_err("hash is 32 bytes"));
+        }
+        let hash = U256::from(hash);
+        let filter = chain
+            .get_block_filter(&hash)
+            .map_err(|_err| ValueError::py_err(_err))?
+            .ok_or_else(|| ValueError::py_err("not found block filter"))?;
+        chain
+            .account
+            .match_block_filter(&hash, &filter)
+            .map_err(|_err| ValueError::py_err(_err))
+    }
+
     fn get_best_block(&self, py: Python, full: bool) -> PyResult<PyBlock> {
         let chain = self.lock();
         match chain.best_chain.get(0) {
@@ -143,6 +234,51 @@ impl PyChain {
         }
     }
 
+    fn get_tx_proof(&self, py: Python, hash: &PyBytes) -> PyResult<Option<(PyObject, Vec<PyObject>, usize)>> {
+        // inclusion proof (header, branch, index) for light clients
+        let chain = self.lock();
+        let hash = hash.as_bytes();
+        if hash.len() != 32 {
+            return Err(TypeError::py_err("hash is 32 bytes"));
+        }
+        match chain
+            .get_tx_proof(&U256::from(hash))
+            .map_err(|_err| ValueError::py_err(_err))?
+        {
+            Some((header, branch, index)) => {
+                let header = PyBytes::new(py, header.to_bytes().as_ref()).to_object(py);
+                let branch = branch
+                    .iter()
+                    .map(|hash| PyBytes::new(py, u256_to_bytes(hash).as_ref()).to_object(py))
+                    .collect();
+                Ok(Some((header, branch, index)))
+            },
+            None => Ok(None),
+        }
+    }
+
+    #[staticmethod]
+    fn verify_merkle_branch(
+        root: &PyBytes,
+        hash: &PyBytes,
+        branch: Vec<&PyBytes>,
+        index: usize,
+    ) -> PyResult<bool> {
+        if root.as_bytes().len() != 32 || hash.as_bytes().len() != 32 {
+            return Err(TypeError::py_err("hash is 32 bytes"));
+        }
+        let mut hashs = Vec::with_capacity(branch.len());
+        for sibling in branch.iter() {
+            if sibling.as_bytes().len() != 32 {
+                return Err(TypeError::py_err("branch hash is 32 bytes"));
+            }
+            hashs.push(U256::from(sibling.as_bytes()));
+        }
+        let root = U256::from(root.as_bytes());
+        let hash = U256::from(hash.as_bytes());
+        Ok(verify_merkle_branch(&root, &hash, &hashs, index))
+    }
+
     fn get_account_balance(&self, account_id: u32, confirm: u32) -> PyResult<PyAccount> {
         let (confirmed, unconfirmed) = self
             .lock()
@@ -174,11 +310,68 @@ impl PyChain {
         }
     }
 
+    fn add_watch_only_account(&self, account_id: u32, xpub: &PyBytes) -> PyResult<()> {
+        // xpub is BIP32 extended public key => m/44'/CoinType'/account_id'
+        let xpub = xpub.as_bytes();
+        if xpub.len() != 33 + 32 {
+            return Err(TypeError::py_err("xpub is 65 bytes (public key and chain code)"));
+        }
+        self.lock()
+            .add_watch_only_account(account_id, xpub)
+            .map_err(|_err| ValueError::py_err(format!("failed add watch-only account: {}", _err)))
+    }
+
+    fn is_watch_only_account(&self, account_id: u32) -> PyResult<bool> {
+        match self.lock().account.get_account_ref(account_id) {
+            Ok(account) => Ok(account.is_watch_only()),
+            Err(err) => Err(ValueError::py_err(err)),
+        }
+    }
+
+    fn encrypt_wallet(&self, passphrase: &str) -> PyResult<()> {
+        // note: wallet is locked after encryption, unlock by passphrase
+        self.lock()
+            .encrypt_wallet(passphrase.as_bytes())
+            .map_err(|err| ValueError::py_err(format!("failed encrypt wallet: {}", err)))
+    }
+
+    fn unlock_wallet(&self, passphrase: &str, timeout: u32) -> PyResult<()> {
+        // timeout is seconds, 0 is no timeout
+        self.lock()
+            .unlock_wallet(passphrase.as_bytes(), timeout)
+            .map_err(|err| ValueError::py_err(format!("failed unlock wallet: {}", err)))
+    }
+
+    fn lock_wallet(&self) -> PyResult<()> {
+        self.lock().lock_wallet().map_err(|err| ValueError::py_err(err))
+    }
+
+    #[getter]
+    fn get_is_wallet_locked(&self) -> bool {
+        self.lock().is_wallet_locked()
+    }
+
+    #[getter]
+    fn get_is_wallet_encrypted(&self) -> bool {
+        self.lock().account.is_encrypted()
+    }
+
     fn get_account_addr_path(&self, addr: PyRef<PyAddress>) -> Option<(u32, u32, u32)> {
         // find address derive path `m/44'/CoinType'/account'/is_inner/index`
         self.lock().account.get_path_from_addr(&addr.addr)
     }
 
+    fn rescan_accounts(&self, from_height: u32) -> PyResult<usize> {
+        // rebuild restored account's balance & movement from finalized blocks
+        let mut chain = self.lock();
+        if chain.tables.is_closed {
+            return Err(ValueError::py_err("already closed!"));
+        }
+        chain
+            .rescan_accounts(from_height)
+            .map_err(|_err| ValueError::py_err(format!("account rescan failed: {}", _err)))
+    }
+
     fn calc_unspent_by_amount(&self, balances: PyRef<PyBalance>) -> PyResult<Vec<PyUnspent>> {
         let chain = self.lock();
         let mut iter = chain.get_account_unspent_iter();
@@ -281,6 +474,130 @@ impl PyChain {
         }
     }
 
+    fn build_tx(
+        &self,
+        py: Python,
+        version: u32,
+        time: u32,
+        deadline: u32,
+        destinations: Vec<(PyRef<PyAddress>, u32, u64)>,
+        gas_price: u64,
+        change_account: u32,
+        message: Option<(u8, &PyBytes)>,
+        dust_limit: u64,
+    ) -> PyResult<PyTx> {
+        // select unspent, add change to a new address and sign all inputs
+        let builder = new_tx_builder(
+            version,
+            time,
+            deadline,
+            destinations,
+            gas_price,
+            change_account,
+            message,
+            dust_limit,
+        )?;
+        let tx = builder
+            .build(&mut self.lock())
+            .map_err(|err| ValueError::py_err(format!("failed build tx: {}", err)))?;
+        PyTx::from_verifiable(py, tx)
+    }
+
+    fn propose_consolidation(
+        &self,
+        py: Python,
+        version: u32,
+        time: u32,
+        deadline: u32,
+        destinations: Vec<(PyRef<PyAddress>, u32, u64)>,
+        gas_price: u64,
+        change_account: u32,
+        message: Option<(u8, &PyBytes)>,
+        dust_limit: u64,
+    ) -> PyResult<Vec<PyTx>> {
+        // merge txs to push before `build_tx` when the payment require over 255 inputs
+        let builder = new_tx_builder(
+            version,
+            time,
+            deadline,
+            destinations,
+            gas_price,
+            change_account,
+            message,
+            dust_limit,
+        )?;
+        let txs = builder
+            .propose_consolidation(&mut self.lock())
+            .map_err(|err| ValueError::py_err(format!("failed propose consolidation: {}", err)))?;
+        let mut pytxs = Vec::with_capacity(txs.len());
+        for tx in txs {
+            pytxs.push(PyTx::from_verifiable(py, tx)?);
+        }
+        Ok(pytxs)
+    }
+
+    fn build_consolidation(
+        &self,
+        py: Python,
+        account_id: u32,
+        coin_id: u32,
+        max_inputs: usize,
+        min_amount: u64,
+        version: u32,
+        time: u32,
+        deadline: u32,
+        gas_price: u64,
+    ) -> PyResult<Vec<PyTx>> {
+        // merge small unspent to outputs for staking, push them in order
+        let template = new_tx_builder(version, time, deadline, vec![], gas_price, account_id, None, 0)?;
+        let txs = self
+            .lock()
+            .build_consolidation(account_id, coin_id, max_inputs, min_amount, &template)
+            .map_err(|err| ValueError::py_err(format!("failed build consolidation: {}", err)))?;
+        let mut pytxs = Vec::with_capacity(txs.len());
+        for tx in txs {
+            pytxs.push(PyTx::from_verifiable(py, tx)?);
+        }
+        Ok(pytxs)
+    }
+
+    fn build_sweep(
+        &self,
+        py: Python,
+        source: &PyAny,
+        target: PyRef<PyAddress>,
+        version: u32,
+        time: u32,
+        deadline: u32,
+        gas_price: u64,
+    ) -> PyResult<Vec<PyTx>> {
+        // source is account_id or list of addresses
+        let source = match source.extract::<u32>() {
+            Ok(account_id) => SweepSource::Account(account_id),
+            Err(_) => {
+                let addrs: Vec<PyRef<PyAddress>> = source.extract()?;
+                SweepSource::Addresses(addrs.iter().map(|addr| addr.addr).collect())
+            },
+        };
+        let template = new_tx_builder(version, time, deadline, vec![], gas_price, 0, None, 0)?;
+        let txs = self
+            .lock()
+            .build_sweep(&source, &target.addr, &template)
+            .map_err(|err| ValueError::py_err(format!("failed build sweep: {}", err)))?;
+        let mut pytxs = Vec::with_capacity(txs.len());
+        for tx in txs {
+            pytxs.push(PyTx::from_verifiable(py, tx)?);
+        }
+        Ok(pytxs)
+    }
+
+    fn sign_partial_tx(&self, mut partial: PyRefMut<PyPartialTx>) -> PyResult<usize> {
+        // fill derivation paths and sign account's inputs, return new signatures count
+        self.lock()
+            .sign_partial_tx(&mut partial.partial)
+            .map_err(|err| ValueError::py_err(format!("failed sign partial tx: {}", err)))
+    }
+
     fn list_unspent_by_addr(
         &self,
         addrs: Vec<PyRef<PyAddress>>,
@@ -389,6 +706,91 @@ impl PyChain {
         Ok(result)
     }
 
+    fn get_mint_coin(&self, py: Python, coin_id: u32) -> PyResult<Option<PyObject>> {
+        // newest coin info on best_chain
+        let chain = self.lock();
+        let state = chain
+            .get_mint_state(coin_id, &chain.best_chain)
+            .map_err(|_err| ValueError::py_err(_err))?;
+        match state {
+            Some(state) => {
+                let dict = PyDict::new(py);
+                dict.set_item("coin_id", state.coin_id)?;
+                dict.set_item(
+                    "owner",
+                    Py::new(py, PyAddress {
+                        addr: state.params.owner,
+                    })?,
+                )?;
+                dict.set_item("name", state.params.name)?;
+                dict.set_item("unit", state.params.unit)?;
+                dict.set_item("digit", state.params.digit)?;
+                dict.set_item("description", state.params.description)?;
+                dict.set_item("additional_issue", state.setting.additional_issue)?;
+                dict.set_item("change_params", state.setting.change_params)?;
+                dict.set_item("supply", state.supply)?;
+                let hash = u256_to_bytes(&state.last_hash);
+                dict.set_item("last_hash", PyBytes::new(py, hash.as_ref()))?;
+                Ok(Some(dict.to_object(py)))
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn get_mint_history(&self, py: Python, coin_id: u32) -> PyResult<Vec<(Option<u32>, PyObject, i64)>> {
+        // supply history (height, txhash, amount) from old to new
+        // note: height is None if the tx isn't finalized
+        let chain = self.lock();
+        let history = chain
+            .get_mint_history(coin_id, &chain.best_chain)
+            .map_err(|_err| ValueError::py_err(_err))?;
+        Ok(history
+            .into_iter()
+            .map(|(height, txhash, amount, _coin)| {
+                let hash = u256_to_bytes(&txhash);
+                (height, PyBytes::new(py, hash.as_ref()).to_object(py), amount)
+            })
+            .collect())
+    }
+
+    #[staticmethod]
+    fn encode_mint_message(
+        py: Python,
+        coin_id: u32,
+        owner: Option<PyRef<PyAddress>>,
+        params: Option<(String, String, u8, String)>,
+        setting: Option<(bool, bool)>,
+    ) -> PyResult<PyObject> {
+        // params is (name, unit, digit, description) and require owner
+        // setting is (additional_issue, change_params)
+        let params = match (owner, params) {
+            (Some(owner), Some((name, unit, digit, description))) => Some(MintParams {
+                owner: owner.addr,
+                name,
+                unit,
+                digit,
+                description,
+            }),
+            (None, None) => None,
+            _ => {
+                return Err(ValueError::py_err(
+                    "owner and params are required at the same time",
+                ))
+            },
+        };
+        let setting = setting.map(|(additional_issue, change_params)| MintSetting {
+            additional_issue,
+            change_params,
+        });
+        let coin = MintCoin {
+            coin_id,
+            params,
+            setting,
+        };
+        let bytes = coin.to_bytes().map_err(ValueError::py_err)?;
+        Ok(PyBytes::new(py, &bytes).to_object(py))
+    }
+
     #[getter]
     fn get_is_closed(&self) -> bool {
         self.lock().tables.is_closed
@@ -417,3 +819,35 @@ impl PyChain {
         self.chain.clone()
     }
 }
+
+/// builder from python args, message is (type, body)
+fn new_tx_builder(
+    version: u32,
+    time: u32,
+    deadline: u32,
+    destinations: Vec<(PyRef<PyAddress>, u32, u64)>,
+    gas_price: u64,
+    change_account: u32,
+    message: Option<(u8, &PyBytes)>,
+    dust_limit: u64,
+) -> PyResult<TxBuilder> {
+    let message = match message {
+        Some((msg_type, msg_body)) => {
+            TxMessage::new(msg_type, msg_body.as_bytes().to_vec()).map_err(|err| ValueError::py_err(err))?
+        },
+        None => TxMessage::Nothing,
+    };
+    Ok(TxBuilder {
+        version,
+        time,
+        deadline,
+        destinations: destinations
+            .iter()
+            .map(|(addr, coin_id, amount)| TxOutput(addr.addr, *coin_id, *amount))
+            .collect(),
+        gas_price,
+        message,
+        change_account,
+        dust_limit,
+    })
+}
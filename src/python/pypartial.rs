@@ -0,0 +1,113 @@
+use crate::python::pytx::PyTx;
+use crate::tx::*;
+use crate::utils::*;
+use pyo3::exceptions::ValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyType};
+
+type Address = [u8; 21];
+
+/// partially signed tx for multi-party and offline signing
+#[pyclass]
+pub struct PyPartialTx {
+    pub partial: PartialTx,
+}
+
+#[pymethods]
+impl PyPartialTx {
+    #[new]
+    fn new(py: Python, tx: PyRef<PyTx>) -> PyResult<Self> {
+        // note: tx's signature and inputs_cache are copied if exist
+        let partial =
+            PartialTx::from_manual(tx.clone_to_manual(py)).map_err(|err| ValueError::py_err(err))?;
+        Ok(PyPartialTx { partial })
+    }
+
+    #[classmethod]
+    fn from_bytes(_cls: &PyType, binary: &PyBytes) -> PyResult<Self> {
+        let partial = PartialTx::from_bytes(binary.as_bytes()).map_err(|err| ValueError::py_err(err))?;
+        Ok(PyPartialTx { partial })
+    }
+
+    fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.partial.to_bytes()).to_object(py)
+    }
+
+    #[classmethod]
+    fn from_json(_cls: &PyType, string: &str) -> PyResult<Self> {
+        let partial = PartialTx::from_json(string).map_err(|err| ValueError::py_err(err))?;
+        Ok(PyPartialTx { partial })
+    }
+
+    fn to_json(&self) -> String {
+        self.partial.to_json()
+    }
+
+    fn merge(&mut self, other: PyRef<PyPartialTx>) -> PyResult<()> {
+        self.partial
+            .merge(&other.partial)
+            .map_err(|err| ValueError::py_err(err))
+    }
+
+    #[getter]
+    fn get_is_complete(&self) -> bool {
+        self.partial.is_complete()
+    }
+
+    fn finalize(&self, py: Python) -> PyResult<PyTx> {
+        let tx = self
+            .partial
+            .clone()
+            .finalize()
+            .map_err(|err| ValueError::py_err(format!("cannot finalize: {}", err)))?;
+        PyTx::from_verifiable(py, tx)
+    }
+
+    fn inspect(&self, py: Python) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        let partial = &self.partial;
+        dict.set_item("hash", u256_to_hex(&partial.hash()))?;
+        {
+            let inputs = partial
+                .body
+                .inputs
+                .iter()
+                .enumerate()
+                .map(|(i, input)| {
+                    let cache = partial.inputs_cache[i]
+                        .as_ref()
+                        .map(|cache| (addr2string(&cache.0), cache.1, cache.2));
+                    (u256_to_hex(&input.0), input.1, cache, partial.paths[i])
+                })
+                .collect::<Vec<(String, u8, Option<(String, u32, u64)>, Option<KeyPath>)>>();
+            dict.set_item("inputs", inputs)?;
+        }
+        {
+            let outputs = partial
+                .body
+                .outputs
+                .iter()
+                .map(|output| (addr2string(&output.0), output.1, output.2))
+                .collect::<Vec<(String, u32, u64)>>();
+            dict.set_item("outputs", outputs)?;
+        }
+        dict.set_item("gas_price", partial.body.gas_price)?;
+        dict.set_item("gas_amount", partial.body.gas_amount)?;
+        dict.set_item("fee", partial.get_fee())?;
+        dict.set_item("signatures", partial.signature.len())?;
+        {
+            let missing = partial
+                .get_missing_signers()
+                .iter()
+                .map(|addr| addr2string(addr))
+                .collect::<Vec<String>>();
+            dict.set_item("missing_signers", missing)?;
+        }
+        dict.set_item("complete", partial.is_complete())?;
+        Ok(dict.to_object(py))
+    }
+}
+
+fn addr2string(addr: &Address) -> String {
+    params2bech(addr[0], &addr[1..21]).unwrap().to_string()
+}
@@ -1,8 +1,9 @@
+use crate::python::pyaddr::PyAddress;
 use crate::signature::*;
 use pyo3::basic::CompareOp;
 use pyo3::exceptions::ValueError;
 use pyo3::prelude::*;
-use pyo3::types::{PyBytes, PyTuple};
+use pyo3::types::{PyBytes, PyTuple, PyType};
 use pyo3::PyObjectProtocol;
 
 #[pyclass]
@@ -106,4 +107,363 @@ impl PySignature {
     fn len(&self) -> usize {
         self.signs.len()
     }
+
+    #[staticmethod]
+    fn get_cache_stats() -> (u64, u64, usize, usize) {
+        // (hits, misses, length, capacity)
+        get_signature_cache().get_stats()
+    }
+
+    #[staticmethod]
+    fn set_cache_capacity(capacity: usize) {
+        get_signature_cache().set_capacity(capacity)
+    }
+
+    #[staticmethod]
+    fn clear_cache() {
+        get_signature_cache().clear()
+    }
+}
+
+type Point = [u8; 33];
+type Scalar = [u8; 32];
+
+fn bytes_to_array(bytes: &[u8], array: &mut [u8], name: &str) -> PyResult<()> {
+    if bytes.len() == array.len() {
+        array.clone_from_slice(bytes);
+        Ok(())
+    } else {
+        Err(ValueError::py_err(format!("{} is {} bytes", name, array.len())))
+    }
+}
+
+fn bytes_to_point(bytes: &PyBytes) -> PyResult<Point> {
+    let mut point = [0u8; 33];
+    bytes_to_array(bytes.as_bytes(), &mut point, "public key")?;
+    Ok(point)
+}
+
+fn bytes_to_scalar(bytes: &PyBytes) -> PyResult<Scalar> {
+    let mut scalar = [0u8; 32];
+    bytes_to_array(bytes.as_bytes(), &mut scalar, "scalar")?;
+    Ok(scalar)
+}
+
+fn list_to_points(pubkeys: Vec<&PyBytes>) -> PyResult<Vec<Point>> {
+    pubkeys.into_iter().map(bytes_to_point).collect()
+}
+
+/// n of n signing session of a signer
+#[pyclass]
+pub struct PyMuSigSession {
+    pub session: MuSigSession,
+}
+
+#[pymethods]
+impl PyMuSigSession {
+    #[new]
+    fn new(pubkeys: Vec<&PyBytes>, my_pk: &PyBytes, message: &PyBytes) -> PyResult<Self> {
+        let pubkeys = list_to_points(pubkeys)?;
+        let my_pk = bytes_to_point(my_pk)?;
+        let session = MuSigSession::new(&pubkeys, &my_pk, message.as_bytes())
+            .map_err(|err| ValueError::py_err(format!("failed to start session: {}", err)))?;
+        Ok(PyMuSigSession { session })
+    }
+
+    #[staticmethod]
+    fn aggregate_public_keys(py: Python, pubkeys: Vec<&PyBytes>) -> PyResult<PyObject> {
+        let apk = aggregate_public_keys(&list_to_points(pubkeys)?).map_err(|err| ValueError::py_err(err))?;
+        Ok(PyBytes::new(py, &apk).to_object(py))
+    }
+
+    #[staticmethod]
+    fn get_aggregate_address(ver: u8, pubkeys: Vec<&PyBytes>) -> PyResult<PyAddress> {
+        let addr =
+            get_aggregate_address(ver, &list_to_points(pubkeys)?).map_err(|err| ValueError::py_err(err))?;
+        Ok(PyAddress { addr })
+    }
+
+    #[classmethod]
+    fn from_bytes(_cls: &PyType, binary: &PyBytes) -> PyResult<Self> {
+        let session = MuSigSession::from_bytes(binary.as_bytes())
+            .map_err(|err| ValueError::py_err(format!("failed to restore session: {}", err)))?;
+        Ok(PyMuSigSession { session })
+    }
+
+    fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.session.to_bytes()).to_object(py)
+    }
+
+    #[getter]
+    fn get_apk(&self, py: Python) -> PyObject {
+        PyBytes::new(py, self.session.get_apk()).to_object(py)
+    }
+
+    #[getter]
+    fn get_pubkeys(&self, py: Python) -> PyObject {
+        let pubkeys = self
+            .session
+            .get_pubkeys()
+            .iter()
+            .map(|pk| PyBytes::new(py, pk).to_object(py))
+            .collect::<Vec<PyObject>>();
+        PyTuple::new(py, &pubkeys).to_object(py)
+    }
+
+    #[getter]
+    fn get_message(&self, py: Python) -> PyObject {
+        PyBytes::new(py, self.session.get_message()).to_object(py)
+    }
+
+    fn get_address(&self, ver: u8) -> PyAddress {
+        PyAddress {
+            addr: self.session.get_address(ver),
+        }
+    }
+
+    fn get_commitment(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.session.get_commitment()).to_object(py)
+    }
+
+    fn add_commitment(&mut self, pk: &PyBytes, commitment: &PyBytes) -> PyResult<()> {
+        self.session
+            .add_commitment(&bytes_to_point(pk)?, &bytes_to_scalar(commitment)?)
+            .map_err(|err| ValueError::py_err(err))
+    }
+
+    fn get_nonce(&self, py: Python) -> PyResult<PyObject> {
+        let nonce = self.session.get_nonce().map_err(|err| ValueError::py_err(err))?;
+        Ok(PyBytes::new(py, &nonce).to_object(py))
+    }
+
+    fn add_nonce(&mut self, pk: &PyBytes, nonce: &PyBytes) -> PyResult<()> {
+        self.session
+            .add_nonce(&bytes_to_point(pk)?, &bytes_to_point(nonce)?)
+            .map_err(|err| ValueError::py_err(err))
+    }
+
+    fn sign_partial(&mut self, py: Python, sk: &PyBytes) -> PyResult<PyObject> {
+        let s = self
+            .session
+            .sign_partial(&bytes_to_scalar(sk)?)
+            .map_err(|err| ValueError::py_err(err))?;
+        Ok(PyBytes::new(py, &s).to_object(py))
+    }
+
+    fn add_partial(&mut self, pk: &PyBytes, s: &PyBytes) -> PyResult<()> {
+        self.session
+            .add_partial(&bytes_to_point(pk)?, &bytes_to_scalar(s)?)
+            .map_err(|err| ValueError::py_err(err))
+    }
+
+    fn combine(&self) -> PyResult<PySignature> {
+        let sign = self.session.combine().map_err(|err| ValueError::py_err(err))?;
+        Ok(PySignature { signs: vec![sign] })
+    }
+}
+
+fn tuple_of_bytes<T: AsRef<[u8]>>(py: Python, items: &[T]) -> PyObject {
+    let items = items
+        .iter()
+        .map(|item| PyBytes::new(py, item.as_ref()).to_object(py))
+        .collect::<Vec<PyObject>>();
+    PyTuple::new(py, &items).to_object(py)
+}
+
+/// distributed key generation of n of m members
+#[pyclass]
+pub struct PyThresholdKeyGen {
+    pub keygen: ThresholdKeyGen,
+}
+
+#[pymethods]
+impl PyThresholdKeyGen {
+    #[new]
+    fn new(threshold: u8, index: u8, members: Vec<u8>) -> PyResult<Self> {
+        let keygen = ThresholdKeyGen::new(threshold, index, &members)
+            .map_err(|err| ValueError::py_err(format!("failed to start key generation: {}", err)))?;
+        Ok(PyThresholdKeyGen { keygen })
+    }
+
+    #[staticmethod]
+    fn new_nonce(threshold: u8, index: u8, members: Vec<u8>) -> PyResult<Self> {
+        let keygen = ThresholdKeyGen::new_nonce(threshold, index, &members)
+            .map_err(|err| ValueError::py_err(format!("failed to start nonce generation: {}", err)))?;
+        Ok(PyThresholdKeyGen { keygen })
+    }
+
+    #[classmethod]
+    fn from_bytes(_cls: &PyType, binary: &PyBytes) -> PyResult<Self> {
+        let keygen = ThresholdKeyGen::from_bytes(binary.as_bytes())
+            .map_err(|err| ValueError::py_err(format!("failed to restore key generation: {}", err)))?;
+        Ok(PyThresholdKeyGen { keygen })
+    }
+
+    fn to_bytes(&self, py: Python) -> PyResult<PyObject> {
+        let bytes = self.keygen.to_bytes().map_err(|err| ValueError::py_err(err))?;
+        Ok(PyBytes::new(py, &bytes).to_object(py))
+    }
+
+    #[getter]
+    fn get_threshold(&self) -> u8 {
+        self.keygen.get_threshold()
+    }
+
+    #[getter]
+    fn get_index(&self) -> u8 {
+        self.keygen.get_index()
+    }
+
+    #[getter]
+    fn get_members(&self) -> Vec<u8> {
+        self.keygen.get_members().clone()
+    }
+
+    #[getter]
+    fn get_is_nonce(&self) -> bool {
+        self.keygen.is_nonce()
+    }
+
+    fn get_commitments(&self, py: Python) -> PyObject {
+        tuple_of_bytes(py, &self.keygen.get_commitments())
+    }
+
+    fn get_share(&self, py: Python, to: u8) -> PyResult<PyObject> {
+        let share = self.keygen.get_share(to).map_err(|err| ValueError::py_err(err))?;
+        Ok(PyBytes::new(py, &share).to_object(py))
+    }
+
+    fn add_commitments(&mut self, from: u8, commitments: Vec<&PyBytes>) -> PyResult<()> {
+        self.keygen
+            .add_commitments(from, &list_to_points(commitments)?)
+            .map_err(|err| ValueError::py_err(err))
+    }
+
+    fn add_share(&mut self, from: u8, share: &PyBytes) -> PyResult<()> {
+        self.keygen
+            .add_share(from, &bytes_to_scalar(share)?)
+            .map_err(|err| ValueError::py_err(err))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.keygen.is_finished()
+    }
+
+    fn finish(&self) -> PyResult<PyThresholdShare> {
+        let share = self.keygen.finish().map_err(|err| ValueError::py_err(err))?;
+        Ok(PyThresholdShare { share })
+    }
+}
+
+/// a member's share of the shared key or the one-time nonce
+#[pyclass]
+pub struct PyThresholdShare {
+    pub share: ThresholdShare,
+}
+
+#[pymethods]
+impl PyThresholdShare {
+    #[classmethod]
+    fn from_bytes(_cls: &PyType, binary: &PyBytes) -> PyResult<Self> {
+        let share = ThresholdShare::from_bytes(binary.as_bytes())
+            .map_err(|err| ValueError::py_err(format!("failed to restore share: {}", err)))?;
+        Ok(PyThresholdShare { share })
+    }
+
+    fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.share.to_bytes()).to_object(py)
+    }
+
+    fn to_public(&self) -> PyThresholdShare {
+        PyThresholdShare {
+            share: self.share.to_public(),
+        }
+    }
+
+    #[getter]
+    fn get_threshold(&self) -> u8 {
+        self.share.get_threshold()
+    }
+
+    #[getter]
+    fn get_index(&self) -> u8 {
+        self.share.get_index()
+    }
+
+    #[getter]
+    fn get_members(&self) -> Vec<u8> {
+        self.share.get_members().clone()
+    }
+
+    #[getter]
+    fn get_public_key(&self, py: Python) -> PyObject {
+        PyBytes::new(py, self.share.get_public_key()).to_object(py)
+    }
+
+    #[getter]
+    fn get_is_nonce(&self) -> bool {
+        self.share.is_nonce()
+    }
+
+    #[getter]
+    fn get_has_secret(&self) -> bool {
+        self.share.has_secret()
+    }
+
+    fn get_address(&self, ver: u8) -> PyAddress {
+        PyAddress {
+            addr: self.share.get_address(ver),
+        }
+    }
+
+    fn get_public_share(&self, py: Python, index: u8) -> PyResult<PyObject> {
+        let point = self
+            .share
+            .get_public_share(index)
+            .map_err(|err| ValueError::py_err(err))?;
+        Ok(PyBytes::new(py, &point).to_object(py))
+    }
+
+    fn sign_partial(
+        &self,
+        py: Python,
+        mut nonce: PyRefMut<PyThresholdShare>,
+        message: &PyBytes,
+    ) -> PyResult<PyObject> {
+        let gamma = sign_threshold_partial(&self.share, &mut nonce.share, message.as_bytes())
+            .map_err(|err| ValueError::py_err(format!("failed to sign: {}", err)))?;
+        Ok(PyBytes::new(py, &gamma).to_object(py))
+    }
+
+    fn verify_partial(
+        &self,
+        nonce: PyRef<PyThresholdShare>,
+        index: u8,
+        gamma: &PyBytes,
+        message: &PyBytes,
+    ) -> PyResult<bool> {
+        verify_threshold_partial(
+            &self.share,
+            &nonce.share,
+            index,
+            &bytes_to_scalar(gamma)?,
+            message.as_bytes(),
+        )
+        .map_err(|err| ValueError::py_err(err))
+    }
+
+    fn combine(
+        &self,
+        nonce: PyRef<PyThresholdShare>,
+        partials: Vec<(u8, &PyBytes)>,
+        message: &PyBytes,
+    ) -> PyResult<PySignature> {
+        let mut _partials = Vec::with_capacity(partials.len());
+        for (index, gamma) in partials {
+            _partials.push((index, bytes_to_scalar(gamma)?));
+        }
+        let sign = combine_threshold(&self.share, &nonce.share, &_partials, message.as_bytes())
+            .map_err(|err| ValueError::py_err(format!("failed to combine: {}", err)))?;
+        Ok(PySignature { signs: vec![sign] })
+    }
 }
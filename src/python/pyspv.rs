@@ -0,0 +1,138 @@
+use crate::block::{BlockFlag, BlockHeader};
+use crate::chain::spv::HeaderChain;
+use crate::python::pyaddr::PyAddress;
+use crate::python::pytx::PyTx;
+use crate::tx::TxOutput;
+use crate::utils::u256_to_bytes;
+use bigint::U256;
+use pyo3::exceptions::ValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyType};
+
+/// header-only chain for light clients
+#[pyclass]
+pub struct PyHeaderChain {
+    chain: HeaderChain,
+}
+
+fn parse_params(params: &PyAny) -> PyResult<Vec<(BlockFlag, u32, u32, u32)>> {
+    let vec: Vec<(u8, u32, u32, u32)> = params.extract()?;
+    let mut params = Vec::with_capacity(vec.len());
+    for p in vec {
+        let flag = BlockFlag::from_int(p.0).map_err(|err| ValueError::py_err(err))?;
+        params.push((flag, p.1, p.2, p.3));
+    }
+    Ok(params)
+}
+
+fn parse_hash(hash: &PyBytes) -> PyResult<U256> {
+    let hash = hash.as_bytes();
+    if hash.len() != 32 {
+        return Err(ValueError::py_err("hash is 32 bytes"));
+    }
+    Ok(U256::from(hash))
+}
+
+#[pymethods]
+impl PyHeaderChain {
+    #[new]
+    fn new(params: &PyAny) -> PyResult<Self> {
+        Ok(PyHeaderChain {
+            chain: HeaderChain::new(parse_params(params)?),
+        })
+    }
+
+    #[classmethod]
+    fn from_bytes(_cls: &PyType, params: &PyAny, binary: &PyBytes) -> PyResult<Self> {
+        let chain = HeaderChain::from_bytes(parse_params(params)?, binary.as_bytes())
+            .map_err(|err| ValueError::py_err(err))?;
+        Ok(PyHeaderChain { chain })
+    }
+
+    fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.chain.to_bytes()).to_object(py)
+    }
+
+    fn push_header(
+        &mut self,
+        py: Python,
+        header: &PyBytes,
+        flag: u8,
+        bias: f32,
+        stake: Option<(PyRef<PyTx>, PyRef<PyAddress>, u64, Vec<&PyBytes>)>,
+    ) -> PyResult<PyObject> {
+        // stake is (coinbase, staked address, staked amount, coinbase's branch) for PoS header
+        let header = header.as_bytes();
+        if header.len() != 80 {
+            return Err(ValueError::py_err("header is 80 bytes"));
+        }
+        let header = BlockHeader::from_bytes(header);
+        let flag = BlockFlag::from_int(flag).map_err(|err| ValueError::py_err(err))?;
+        let hash = match stake {
+            Some((coinbase, addr, amount, branch)) => {
+                let coinbase = coinbase.clone_to_body(py);
+                let input_cache = TxOutput(addr.addr, 0, amount);
+                let mut hashs = Vec::with_capacity(branch.len());
+                for sibling in branch {
+                    hashs.push(parse_hash(sibling)?);
+                }
+                self.chain
+                    .push_header(header, flag, bias, Some((&coinbase, &input_cache, &hashs)))
+            },
+            None => self.chain.push_header(header, flag, bias, None),
+        }
+        .map_err(|err| ValueError::py_err(format!("header rejected: {}", err)))?;
+        Ok(PyBytes::new(py, u256_to_bytes(&hash).as_ref()).to_object(py))
+    }
+
+    fn get_best_hash(&self, py: Python) -> Option<PyObject> {
+        self.chain
+            .get_best_hash()
+            .map(|hash| PyBytes::new(py, u256_to_bytes(hash).as_ref()).to_object(py))
+    }
+
+    fn get_best_height(&self) -> Option<u32> {
+        self.chain.get_best_height()
+    }
+
+    fn get_hash_by_height(&self, py: Python, height: u32) -> Option<PyObject> {
+        self.chain
+            .get_hash_by_height(height)
+            .map(|hash| PyBytes::new(py, u256_to_bytes(hash).as_ref()).to_object(py))
+    }
+
+    fn get_header(&self, py: Python, hash: &PyBytes) -> PyResult<Option<(PyObject, u32, u8, f32)>> {
+        let hash = parse_hash(hash)?;
+        Ok(self.chain.get_header(&hash).map(|(header, height, flag, bias)| {
+            let header = PyBytes::new(py, header.to_bytes().as_ref()).to_object(py);
+            (header, height, flag.to_int(), bias)
+        }))
+    }
+
+    fn is_verified(&self, hash: &PyBytes) -> PyResult<bool> {
+        // staking header's work isn't proved
+        Ok(self.chain.is_verified(&parse_hash(hash)?))
+    }
+
+    fn is_main_chain(&self, hash: &PyBytes) -> PyResult<bool> {
+        Ok(self.chain.is_main_chain(&parse_hash(hash)?))
+    }
+
+    fn verify_tx(
+        &self,
+        blockhash: &PyBytes,
+        hash: &PyBytes,
+        branch: Vec<&PyBytes>,
+        index: usize,
+    ) -> PyResult<u32> {
+        let blockhash = parse_hash(blockhash)?;
+        let hash = parse_hash(hash)?;
+        let mut hashs = Vec::with_capacity(branch.len());
+        for sibling in branch {
+            hashs.push(parse_hash(sibling)?);
+        }
+        self.chain
+            .verify_tx(&blockhash, &hash, &hashs, index)
+            .map_err(|err| ValueError::py_err(err))
+    }
+}
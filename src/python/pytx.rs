@@ -4,7 +4,7 @@ use crate::python::pyaddr::PyAddress;
 use crate::python::pychain::PyChain;
 use crate::python::pysigature::PySignature;
 use crate::python::pyunspent::PyUnspent;
-use crate::signature::{signature_to_bytes, verify_signature};
+use crate::signature::{signature_to_bytes, verify_batch_cached, verify_signature};
 use crate::tx::*;
 use crate::utils::*;
 use bigint::U256;
@@ -579,19 +579,24 @@ impl PyTx {
         // calc signature -> address
         let cell: &PyCell<PySignature> = self.signature.as_ref().unwrap().as_ref(py);
         let signs = &cell.borrow().signs;
-        let binary = self.clone_to_body(py).to_bytes();
-        let mut verified_list = Vec::with_capacity(signs.len());
-        for signature in signs.iter() {
-            let result = verify_signature(signature, &binary);
-            if result.is_ok() && result.unwrap() {
-                verified_list.push(signature.get_address(0));
-            } else {
-                return Err(ValueError::py_err(format!(
-                    "verification failed at {:?} by {:?}",
-                    signature, result
-                )));
-            }
+        let body = self.clone_to_body(py);
+        let hash = body.hash();
+        let binary = body.to_bytes();
+        let items = signs
+            .iter()
+            .map(|signature| (hash.as_slice(), signature, binary.as_slice()))
+            .collect::<Vec<_>>();
+        if let Err(index) = verify_batch_cached(&items) {
+            let result = verify_signature(&signs[index], &binary);
+            return Err(ValueError::py_err(format!(
+                "verification failed at {:?} by {:?}",
+                signs[index], result
+            )));
         }
+        let verified_list = signs
+            .iter()
+            .map(|signature| signature.get_address(0))
+            .collect::<Vec<Address>>();
         // success
         self.verified_list.replace(verified_list);
         Ok(())
@@ -3,21 +3,29 @@ use crate::block::{
     target_to_bits,
     target_to_diff,
     BlockFlag,
+    BlockHeader,
+    BlockTemplate,
     DifficultyBuilder,
+    FlkPosWorker,
     GenerateBuilder,
+    GenerateFuture,
     PocWorker,
     PosWorker,
     PowWorker,
     RewardBuilder,
+    StratumServer,
+    WorkerResult,
 };
+use crate::chain::validate;
 use crate::python::pyblock::{PyBlock, PyTxs};
 use crate::python::pychain::{PyChain, SharedChain};
-use crate::python::pytx::PyTxInputs;
+use crate::python::pytx::{PyTx, PyTxInputs};
+use crate::tx::TxBody;
 use crate::utils::u256_to_bytes;
 use bigint::U256;
 use pyo3::exceptions::{AssertionError, ValueError};
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
+use pyo3::types::{PyBytes, PyDict};
 use std::path::Path;
 
 /// Block & Tx validation methods
@@ -32,6 +40,12 @@ pub struct PyValidate {
     gene: GenerateBuilder,
     /// temporary workers info while generating
     tmp_info: Option<Vec<String>>,
+    /// generating task thrown by `throw_generate_task`
+    future: Option<GenerateFuture>,
+    /// job server for external PoW miners
+    stratum: Option<StratumServer>,
+    /// last template given by `get_block_template`
+    template: Option<BlockTemplate>,
 }
 
 #[pymethods]
@@ -50,6 +64,9 @@ impl PyValidate {
             diff: DifficultyBuilder::new(params),
             gene: GenerateBuilder::new(),
             tmp_info: None,
+            future: None,
+            stratum: None,
+            template: None,
         })
     }
 
@@ -176,6 +193,26 @@ impl PyValidate {
         Ok(true)
     }
 
+    fn check_block(&mut self, py: Python, block: PyRef<PyBlock>) -> PyResult<Option<String>> {
+        // return None if the block is valid or rejected reason
+        let best_block = Some(block.clone_to_full_block(py)?);
+        let chain = self.chain.lock().unwrap();
+        match validate::check_block(&chain, &mut self.diff, &self.reward, &best_block) {
+            Ok(()) => Ok(None),
+            Err(reason) => Ok(Some(reason.to_string())),
+        }
+    }
+
+    fn check_tx(&self, py: Python, tx: PyRef<PyTx>) -> PyResult<Option<String>> {
+        // return None if the tx is acceptable as unconfirmed or rejected reason
+        let tx = tx.clone_to_verifiable(py)?;
+        let chain = self.chain.lock().unwrap();
+        match validate::validate_tx(&chain, &tx) {
+            Ok(()) => Ok(None),
+            Err(reason) => Ok(Some(reason.to_string())),
+        }
+    }
+
     fn is_orphan_block(&self, block: PyRef<PyBlock>) -> bool {
         // check the block is confirmed or finalized
         let chain = self.chain.lock().unwrap();
@@ -284,9 +321,21 @@ impl PyValidate {
         }
     }
 
-    fn push_pow_worker(&mut self, flag: u8, power_limit: u8, block_ver: u32, tx_ver: u32) -> PyResult<()> {
+    fn push_pow_worker(
+        &mut self,
+        flag: u8,
+        power_limit: u8,
+        block_ver: u32,
+        tx_ver: u32,
+        threads: Option<usize>,
+    ) -> PyResult<()> {
+        // note: threads is 1 if not specified
         let flag = BlockFlag::from_int(flag).map_err(|err| AssertionError::py_err(err))?;
-        let worker = PowWorker::new(&flag, power_limit, block_ver, tx_ver);
+        let threads = threads.unwrap_or(1);
+        if power_limit == 0 || threads == 0 || 256 < threads {
+            return Err(ValueError::py_err("power_limit is 1~255 and threads is 1~256"));
+        }
+        let worker = PowWorker::new(&flag, power_limit, block_ver, tx_ver, threads);
         self.gene
             .push_worker(worker)
             .map_err(|err| ValueError::py_err(err))?;
@@ -301,6 +350,14 @@ impl PyValidate {
         Ok(())
     }
 
+    fn push_flk_worker(&mut self) -> PyResult<()> {
+        let worker = FlkPosWorker::new();
+        self.gene
+            .push_worker(worker)
+            .map_err(|err| ValueError::py_err(err))?;
+        Ok(())
+    }
+
     fn push_poc_worker(&mut self, dirs: Vec<String>) -> PyResult<()> {
         let dirs = dirs.iter().map(|path| Path::new(path)).collect();
         let worker = PocWorker::new(dirs);
@@ -316,7 +373,199 @@ impl PyValidate {
         Ok(())
     }
 
-    fn generate_work(&mut self, py: Python) {
+    fn update_workers(&mut self, maxsize: u32, time: u32, deadline: u32) -> PyResult<()> {
+        // update workers by best block & unconfirmed txs
+        let mut chain = self.chain.lock().unwrap();
+        if chain.best_chain.is_empty() {
+            return Err(AssertionError::py_err("best block is not found"));
+        }
+        let unconfirmed = chain.unconfirmed.get_best_unconfirmed_list(maxsize);
+        if time < unconfirmed.time || unconfirmed.deadline <= deadline {
+            return Err(ValueError::py_err(format!(
+                "time and deadline don't cover unconfirmed txs time={} deadline={}",
+                unconfirmed.time, unconfirmed.deadline
+            )));
+        }
+        self.gene.update_unconfirmed_list(unconfirmed);
+        let best_block = chain.get_best_block_ref().clone();
+        let reward = self.reward.calc_block_reward(best_block.height + 1);
+        self.gene
+            .update_by_new_block(&mut chain, &best_block, reward, &mut self.diff)
+            .map_err(|err| ValueError::py_err(err))?;
+        self.gene.update_time_and_deadline(time, deadline);
+        Ok(())
+    }
+
+    fn start_generate_pool(&mut self) -> PyResult<()> {
+        // keep generating on background threads until found or updated
+        if self.future.is_some() {
+            return Err(AssertionError::py_err("already thrown generating task"));
+        }
+        self.gene
+            .start_pool(self.chain.clone())
+            .map_err(|err| AssertionError::py_err(err))
+    }
+
+    fn cancel_generate_pool(&mut self) {
+        // pause until next `update_workers`
+        self.gene.cancel_pool();
+    }
+
+    fn stop_generate_pool(&mut self, py: Python) {
+        // release python's GIL and wait for threads shutdown
+        let gene = &mut self.gene;
+        py.allow_threads(|| gene.stop_pool());
+    }
+
+    fn get_pool_result(&mut self, py: Python) -> PyResult<Option<PyBlock>> {
+        // non-blocking, return None if not found work yet
+        let chain = self.chain.lock().unwrap();
+        match self.gene.pool_result(&chain) {
+            Some((block, txs)) => Ok(Some(PyBlock::from_verifiable_block(py, block, txs)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn get_block_template(
+        &mut self,
+        py: Python,
+        flag: u8,
+        maxsize: u32,
+        time: u32,
+        deadline: u32,
+        block_ver: u32,
+        tx_ver: u32,
+    ) -> PyResult<PyObject> {
+        // template for external PoW miners, submit by `submit_block`
+        let flag = BlockFlag::from_int(flag).map_err(|err| AssertionError::py_err(err))?;
+        let mut chain = self.chain.lock().unwrap();
+        let unconfirmed = chain.unconfirmed.get_best_unconfirmed_list(maxsize);
+        let template = BlockTemplate::new(
+            &mut chain,
+            &mut self.diff,
+            &self.reward,
+            &flag,
+            unconfirmed,
+            time,
+            deadline,
+            block_ver,
+            tx_ver,
+        )
+        .map_err(|err| ValueError::py_err(err))?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("flag", template.flag.to_int())?;
+        dict.set_item("height", template.height)?;
+        dict.set_item("header", PyBytes::new(py, template.header.to_bytes().as_ref()))?;
+        dict.set_item(
+            "coinbase",
+            PyBytes::new(py, template.coinbase.to_bytes().as_slice()),
+        )?;
+        let txs: Vec<PyObject> = template
+            .unconfirmed
+            .txs
+            .iter()
+            .map(|hash| PyBytes::new(py, u256_to_bytes(hash).as_ref()).to_object(py))
+            .collect();
+        dict.set_item("txs", txs)?;
+        dict.set_item("bits", template.header.bits)?;
+        dict.set_item("bias", template.bias)?;
+        dict.set_item("reward", template.reward)?;
+        let branch: Vec<PyObject> = template
+            .branch
+            .iter()
+            .map(|hash| PyBytes::new(py, u256_to_bytes(hash).as_ref()).to_object(py))
+            .collect();
+        dict.set_item("branch", branch)?;
+        self.template.replace(template);
+        Ok(dict.to_object(py))
+    }
+
+    fn submit_block(&mut self, py: Python, header: &PyBytes, coinbase: &PyBytes) -> PyResult<PyBlock> {
+        // rebuild block from the last template and check it
+        let template = self
+            .template
+            .as_ref()
+            .ok_or_else(|| AssertionError::py_err("no template is given"))?;
+        let coinbase = TxBody::from_bytes(coinbase.as_bytes()).map_err(|err| ValueError::py_err(err))?;
+        let chain = self.chain.lock().unwrap();
+        let best_block = template
+            .to_block(&chain, header.as_bytes(), coinbase)
+            .map_err(|err| ValueError::py_err(err))?;
+        let best_block = Some(best_block);
+        validate::check_block(&chain, &mut self.diff, &self.reward, &best_block)
+            .map_err(|reason| ValueError::py_err(reason.to_string()))?;
+        let (block, txs) = best_block.unwrap();
+        PyBlock::from_verifiable_block(py, block, txs)
+    }
+
+    fn start_stratum(&mut self, addr: &str, share_bits: u32) -> PyResult<String> {
+        // return bound address
+        if self.stratum.is_some() {
+            return Err(AssertionError::py_err("already started stratum server"));
+        }
+        let server = StratumServer::start(addr, share_bits).map_err(|err| ValueError::py_err(err))?;
+        let addr = server.local_addr().to_string();
+        self.stratum.replace(server);
+        Ok(addr)
+    }
+
+    fn update_stratum_job(&mut self, flag: u8) -> PyResult<()> {
+        // note: call after `update_workers`, old jobs become stale
+        let flag = BlockFlag::from_int(flag).map_err(|err| AssertionError::py_err(err))?;
+        let server = self
+            .stratum
+            .as_ref()
+            .ok_or_else(|| AssertionError::py_err("stratum server isn't started"))?;
+        let (header, coinbase, txs) = self
+            .gene
+            .get_pow_template(&flag)
+            .ok_or_else(|| ValueError::py_err(format!("no PoW template of {:?}", flag)))?;
+        server.update_template(&flag, header, coinbase, txs);
+        Ok(())
+    }
+
+    fn get_stratum_result(&mut self, py: Python) -> PyResult<Option<PyBlock>> {
+        // non-blocking, return None if no winning share
+        let server = self
+            .stratum
+            .as_ref()
+            .ok_or_else(|| AssertionError::py_err("stratum server isn't started"))?;
+        let chain = self.chain.lock().unwrap();
+        if chain.best_chain.is_empty() {
+            return Err(AssertionError::py_err("best block is not found"));
+        }
+        let best_hash = chain.best_chain.first().unwrap().clone();
+        for (flag, result) in server.pop_found() {
+            // note: ignore share of old template
+            if let WorkerResult::PoW((_work, _coinbase, bytes)) = &result {
+                if BlockHeader::from_bytes(bytes.as_ref()).previous_hash != best_hash {
+                    continue;
+                }
+            }
+            if let Some((block, txs)) = self.gene.external_result(&chain, &flag, result) {
+                return Ok(Some(PyBlock::from_verifiable_block(py, block, txs)?));
+            }
+        }
+        Ok(None)
+    }
+
+    fn get_stratum_info(&self) -> Option<String> {
+        self.stratum.as_ref().map(|server| server.get_info())
+    }
+
+    fn stop_stratum(&mut self, py: Python) {
+        // release python's GIL and wait for clients disconnect
+        if let Some(mut server) = self.stratum.take() {
+            py.allow_threads(|| server.stop());
+        }
+    }
+
+    fn generate_work(&mut self, py: Python) -> PyResult<Option<PyBlock>> {
+        // return mined block with txs or None if not found work
+        if self.future.is_some() {
+            return Err(AssertionError::py_err("already thrown generating task"));
+        }
         self.tmp_info.replace(self.gene.get_worker_info());
 
         let mut future = self.gene.throw_task();
@@ -326,12 +575,53 @@ impl PyValidate {
             future.wait();
         });
 
+        self.future_to_block(py, future)
+    }
+
+    fn throw_generate_task(&mut self) -> PyResult<()> {
+        // non-blocking version of `generate_work`, poll by `is_generate_finished`
+        if self.future.is_some() {
+            return Err(AssertionError::py_err("already thrown generating task"));
+        }
+        self.tmp_info.replace(self.gene.get_worker_info());
+        self.future.replace(self.gene.throw_task());
+        Ok(())
+    }
+
+    fn is_generate_finished(&self) -> PyResult<bool> {
+        match &self.future {
+            Some(future) => Ok(future.is_finished()),
+            None => Err(AssertionError::py_err("no generating task is thrown")),
+        }
+    }
+
+    fn get_generate_result(&mut self, py: Python) -> PyResult<Option<PyBlock>> {
+        // wait for threads finish if not finished yet
+        let mut future = self
+            .future
+            .take()
+            .ok_or_else(|| AssertionError::py_err("no generating task is thrown"))?;
+
+        // release python's GIL and wait for threads finish
+        py.allow_threads(|| {
+            future.wait();
+        });
+
+        self.future_to_block(py, future)
+    }
+}
+
+impl PyValidate {
+    /// get mined block from finished future and return workers
+    fn future_to_block(&mut self, py: Python, future: GenerateFuture) -> PyResult<Option<PyBlock>> {
         let chain = self.chain.lock().unwrap();
+        let mined = self.gene.future_result(&chain, future);
+        self.tmp_info = None;
 
         // get mined block
-        let (_a, _b) = self.gene.future_result(&chain, future).unwrap();
-
-        // TODO: return full block?
-        unimplemented!()
+        match mined {
+            Some((block, txs)) => Ok(Some(PyBlock::from_verifiable_block(py, block, txs)?)),
+            None => Ok(None),
+        }
     }
 }
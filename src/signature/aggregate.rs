@@ -42,14 +42,11 @@ pub fn verify_aggregate(pk: &POINT, r: &SCALAR, s: &SCALAR, message: &[u8]) -> R
 
 /// generate 1 of 1 signature
 /// designed for PoS/PoC mining validator
+///
+/// nonce is RFC6979 with auxiliary randomness `aux`
 #[allow(non_snake_case)]
-pub fn sign_aggregate(sk: &SCALAR, message: &[u8]) -> Result<(SCALAR, SCALAR), Error> {
-    // calc: k = int(hash(bytes(d) || m)) mod n
-    let mut vec = Vec::with_capacity(32 + 32);
-    vec.extend_from_slice(sk.as_ref());
-    vec.extend_from_slice(message);
-    let hashed = Sha256::digest(&vec);
-    let mut k = SecretKey::from_slice(&hashed)?;
+pub fn sign_aggregate(sk: &SCALAR, message: &[u8], aux: &[u8]) -> Result<(SCALAR, SCALAR), Error> {
+    let mut k = rfc6979_nonce(sk, message, aux);
 
     // calc: R = kG = k * G
     let mut R = generator();
@@ -66,7 +63,7 @@ pub fn sign_aggregate(sk: &SCALAR, message: &[u8]) -> Result<(SCALAR, SCALAR), E
     raw_scalar_mul(&mut dG, sk.as_ref())?;
 
     // calc: e = int(hash(bytes(x(R)) || bytes(dG) || m)) mod n
-    vec.clear();
+    let mut vec = Vec::with_capacity(32 + 33 + message.len());
     vec.extend_from_slice(&R.serialize()[1..33]);
     vec.extend_from_slice(dG.serialize().as_ref());
     vec.extend_from_slice(message);
@@ -92,14 +89,16 @@ pub fn sign_aggregate(sk: &SCALAR, message: &[u8]) -> Result<(SCALAR, SCALAR), E
 #[cfg(test)]
 mod single_verify {
     use crate::signature::aggregate::sign_aggregate;
-    use crate::signature::{verify_signature, Signature};
+    use crate::signature::utils::*;
+    use crate::signature::{verify_signature, Signature, SCALAR};
     use secp256k1::Error;
 
-    /// params to signature object
+    /// params to signature object by RFC6979 nonce
+    /// note: nonce differs from the test vectors, verify the generated instead
     fn sign(sk: &[u8], pk: &[u8], msg: &[u8]) -> Signature {
         let mut tmp = [0u8; 32];
         tmp.clone_from_slice(sk);
-        let (r, s) = sign_aggregate(&tmp, msg).unwrap();
+        let (r, s) = sign_aggregate(&tmp, msg, b"").unwrap();
         let mut tmp = [0u8; 33];
         tmp.clone_from_slice(pk);
         Signature::SingleSig((tmp, r, s))
@@ -117,7 +116,7 @@ mod single_verify {
 
         let signature = Signature::new_single_sig(&pk, &r, &s).unwrap();
         assert_eq!(verify_signature(&signature, &msg), Ok(true));
-        assert_eq!(sign(&sk, &pk, &msg), signature);
+        assert_eq!(verify_signature(&sign(&sk, &pk, &msg), &msg), Ok(true));
     }
 
     #[test]
@@ -132,7 +131,7 @@ mod single_verify {
 
         let signature = Signature::new_single_sig(&pk, &r, &s).unwrap();
         assert_eq!(verify_signature(&signature, &msg), Ok(true));
-        assert_eq!(sign(&sk, &pk, &msg), signature);
+        assert_eq!(verify_signature(&sign(&sk, &pk, &msg), &msg), Ok(true));
     }
 
     #[test]
@@ -147,7 +146,7 @@ mod single_verify {
 
         let signature = Signature::new_single_sig(&pk, &r, &s).unwrap();
         assert_eq!(verify_signature(&signature, &msg), Ok(true));
-        assert_eq!(sign(&sk, &pk, &msg), signature);
+        assert_eq!(verify_signature(&sign(&sk, &pk, &msg), &msg), Ok(true));
     }
 
     #[test]
@@ -162,7 +161,7 @@ mod single_verify {
 
         let signature = Signature::new_single_sig(&pk, &r, &s).unwrap();
         assert_eq!(verify_signature(&signature, &msg), Ok(true));
-        assert_eq!(sign(&sk, &pk, &msg), signature);
+        assert_eq!(verify_signature(&sign(&sk, &pk, &msg), &msg), Ok(true));
     }
 
     #[test]
@@ -345,6 +344,82 @@ mod single_verify {
 
         assert!(Signature::new_single_sig(&pk, &r, &s).is_err());
     }
+
+    #[test]
+    fn rfc6979() {
+        // HMAC-SHA256 RFC4231 case 2
+        let mac = hmac_sha256(b"Jefe", &[b"what do ya want ", b"for nothing?"]);
+        let expected = "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843";
+        assert_eq!(hex::encode(mac), expected);
+
+        // secp256k1 SHA-256 nonce without additional data
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        let mut n_1 = N;
+        n_1[31] -= 1;
+        let vectors: [(SCALAR, &[u8], &str); 6] = [
+            (
+                one,
+                b"Satoshi Nakamoto",
+                "8f8a276c19f4149656b280621e358cce24f5f52542772691ee69063b74f15d15",
+            ),
+            (
+                one,
+                b"All those moments will be lost in time, like tears in rain. Time to die...",
+                "38aa22d72376b4dbc472e06c3ba403ee0a394da63fc58d88686c611aba98d6b3",
+            ),
+            (
+                n_1,
+                b"Satoshi Nakamoto",
+                "33a19b60e25fb6f4435af53a3d42d493644827367e6453928554f43e49aa6f90",
+            ),
+            (
+                to_scalar(
+                    &hex::decode("f8b8af8ce3c7cca5e300d33939540c10d45ce001b8f252bfbc57ba0342904181").unwrap(),
+                ),
+                b"Alan Turing",
+                "525a82b70e67874398067543fd84c83d30c175fdc45fdeee082fe13b1d7cfdf1",
+            ),
+            (
+                one,
+                b"Everything should be made as simple as possible, but not simpler.",
+                "ec633bd56a5774a0940cb97e27a9e4e51dc94af737596a0c5cbb3d30332d92a5",
+            ),
+            (
+                to_scalar(
+                    &hex::decode("e91671c46231f833a6406ccbea0e3e392c76c167bac1cb013f6f1013980455c2").unwrap(),
+                ),
+                b"There is a computer disease that anybody who works with computers knows about. \
+                It's a very serious disease and it interferes completely with the work. \
+                The trouble with computers is that you 'play' with them!",
+                "1f4b84c23a86a221d233f2521be018d9318639d5b8bbd6374a8a59232d16ad3d",
+            ),
+        ];
+        for (sk, msg, expected) in vectors.iter() {
+            let k = rfc6979_nonce(sk, msg, b"");
+            assert_eq!(hex::encode(&k[..]), *expected);
+        }
+
+        // aux changes the nonce, but signature is valid
+        let sk = [3u8; 32];
+        let msg = [7u8; 32];
+        let (r0, s0) = sign_aggregate(&sk, &msg, &[0u8; 32]).unwrap();
+        let (r1, s1) = sign_aggregate(&sk, &msg, &[1u8; 32]).unwrap();
+        assert_ne!(r0, r1);
+        let mut pk = generator();
+        raw_scalar_mul(&mut pk, &sk).unwrap();
+        for (r, s) in [(r0, s0), (r1, s1)].iter() {
+            let signature = Signature::SingleSig((pk.serialize(), *r, *s));
+            assert_eq!(verify_signature(&signature, &msg), Ok(true));
+        }
+
+        // same inputs give the same signature
+        let pk = pk.serialize();
+        let a = Signature::get_single_sign(&sk, &pk, &msg).unwrap();
+        let b = Signature::get_single_sign(&sk, &pk, &msg).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, Signature::SingleSig((pk, r0, s0)));
+    }
 }
 
 /// https://github.com/guggero/bip-schnorr/blob/master/test/test-vectors-mu-sig.json
@@ -0,0 +1,378 @@
+use crate::signature::field::Fe;
+use crate::signature::utils::*;
+use crate::signature::{verify_signature, Signature, POINT, SCALAR};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use secp256k1::{PublicKey, SecretKey};
+use sha2::{Digest, Sha256};
+
+/// batch a block's signatures, a failed batch costs the one by one check again (see `bench_batch`)
+const BATCH_MIN_SIZE: usize = 8;
+
+/// affine point (x, y)
+#[derive(Clone, Copy)]
+struct Affine {
+    x: Fe,
+    y: Fe,
+}
+
+impl Affine {
+    fn from_public_key(pk: &PublicKey) -> Affine {
+        let serialized = pk.serialize_uncompressed();
+        Affine {
+            x: Fe::from_bytes(&serialized[1..33]).unwrap(),
+            y: Fe::from_bytes(&serialized[33..65]).unwrap(),
+        }
+    }
+
+    /// lift x with the square y (jacobi symbol 1), none if not on curve
+    fn lift_x_square_y(r: &SCALAR) -> Option<Affine> {
+        let x = Fe::from_bytes(r)?;
+        let c = x.square() * x + Fe::from_u64(7);
+        c.sqrt().map(|y| Affine { x, y })
+    }
+
+    fn neg(&self) -> Affine {
+        Affine {
+            x: self.x,
+            y: -self.y,
+        }
+    }
+}
+
+/// jacobian point (X, Y, Z) = (X/Z^2, Y/Z^3), Z = 0 is infinity
+#[derive(Clone, Copy)]
+struct Jacobian {
+    x: Fe,
+    y: Fe,
+    z: Fe,
+}
+
+impl Jacobian {
+    const INFINITY: Jacobian = Jacobian {
+        x: Fe::ONE,
+        y: Fe::ONE,
+        z: Fe::ZERO,
+    };
+
+    fn is_infinity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    /// dbl-2009-l (a = 0)
+    fn double(&self) -> Jacobian {
+        if self.is_infinity() || self.y.is_zero() {
+            return Self::INFINITY;
+        }
+        let a = self.x.square();
+        let b = self.y.square();
+        let c = b.square();
+        let d = (self.x + b).square() - a - c;
+        let d = d + d;
+        let e = a + a + a;
+        let f = e.square();
+        let x = f - d - d;
+        let c8 = c + c;
+        let c8 = c8 + c8;
+        let c8 = c8 + c8;
+        let y = e * (d - x) - c8;
+        let z = self.y * self.z;
+        Jacobian { x, y, z: z + z }
+    }
+
+    /// madd-2007-bl
+    fn add_affine(&self, other: &Affine) -> Jacobian {
+        if self.is_infinity() {
+            return Jacobian {
+                x: other.x,
+                y: other.y,
+                z: Fe::ONE,
+            };
+        }
+        let z1z1 = self.z.square();
+        let u2 = other.x * z1z1;
+        let s2 = other.y * self.z * z1z1;
+        let h = u2 - self.x;
+        let r = s2 - self.y;
+        if h.is_zero() {
+            return if r.is_zero() {
+                self.double()
+            } else {
+                Self::INFINITY
+            };
+        }
+        let hh = h.square();
+        let i = hh + hh;
+        let i = i + i;
+        let j = h * i;
+        let r = r + r;
+        let v = self.x * i;
+        let x = r.square() - j - v - v;
+        let y1j = self.y * j;
+        let y = r * (v - x) - y1j - y1j;
+        let z = (self.z + h).square() - z1z1 - hh;
+        Jacobian { x, y, z }
+    }
+
+    /// add-2007-bl
+    fn add(&self, other: &Jacobian) -> Jacobian {
+        if self.is_infinity() {
+            return *other;
+        }
+        if other.is_infinity() {
+            return *self;
+        }
+        let z1z1 = self.z.square();
+        let z2z2 = other.z.square();
+        let u1 = self.x * z2z2;
+        let u2 = other.x * z1z1;
+        let s1 = self.y * other.z * z2z2;
+        let s2 = other.y * self.z * z1z1;
+        let h = u2 - u1;
+        let r = s2 - s1;
+        if h.is_zero() {
+            return if r.is_zero() {
+                self.double()
+            } else {
+                Self::INFINITY
+            };
+        }
+        let i = (h + h).square();
+        let j = h * i;
+        let r = r + r;
+        let v = u1 * i;
+        let x = r.square() - j - v - v;
+        let s1j = s1 * j;
+        let y = r * (v - x) - s1j - s1j;
+        let z = ((self.z + other.z).square() - z1z1 - z2z2) * h;
+        Jacobian { x, y, z }
+    }
+}
+
+/// `width` bits of big endian scalar from `offset` (LSB is 0)
+fn get_window(scalar: &SCALAR, offset: usize, width: usize) -> usize {
+    let mut out = 0usize;
+    for bit in (offset..offset + width).rev() {
+        out <<= 1;
+        if bit < 256 {
+            out |= ((scalar[31 - bit / 8] >> (bit % 8)) & 1) as usize;
+        }
+    }
+    out
+}
+
+/// Σ scalar_i * point_i by Pippenger's bucket method
+fn multi_scalar_mul(scalars: &[SCALAR], points: &[Affine]) -> Jacobian {
+    let width = match points.len() {
+        0..=15 => 3,
+        16..=127 => 5,
+        128..=1023 => 7,
+        _ => 9,
+    };
+    let mut total = Jacobian::INFINITY;
+    let mut buckets = vec![Jacobian::INFINITY; (1 << width) - 1];
+    for offset in (0..256).step_by(width).rev() {
+        for _ in 0..width {
+            total = total.double();
+        }
+        buckets.iter_mut().for_each(|bucket| *bucket = Jacobian::INFINITY);
+        for (scalar, point) in scalars.iter().zip(points.iter()) {
+            let window = get_window(scalar, offset, width);
+            if window != 0 {
+                buckets[window - 1] = buckets[window - 1].add_affine(point);
+            }
+        }
+        // calc: Σ k * bucket_k = Σ running sums
+        let mut running = Jacobian::INFINITY;
+        let mut sum = Jacobian::INFINITY;
+        for bucket in buckets.iter().rev() {
+            running = running.add(bucket);
+            sum = sum.add(&running);
+        }
+        total = total.add(&sum);
+    }
+    total
+}
+
+/// calc: e = int(hash(bytes(r) || pk || m)) mod n
+fn get_challenge(pk: &POINT, r: &SCALAR, message: &[u8]) -> Option<SecretKey> {
+    let mut vec = Vec::with_capacity(32 + 33 + message.len());
+    vec.extend_from_slice(r);
+    vec.extend_from_slice(pk);
+    vec.extend_from_slice(message);
+    SecretKey::from_slice(&Sha256::digest(&vec)).ok()
+}
+
+/// check: (Σ a_i * s_i) * G - Σ a_i * R_i - Σ a_i * e_i * P_i = 0
+///
+/// note: return false if any signature is wrong (or unlucky), ThresholdSig is checked one by one
+fn batch_check(items: &[(&Signature, &[u8])]) -> Option<bool> {
+    let mut scalars: Vec<SCALAR> = Vec::with_capacity(items.len() * 2 + 1);
+    let mut points: Vec<Affine> = Vec::with_capacity(items.len() * 2 + 1);
+    let mut sum_s: Option<SecretKey> = None;
+    for (index, (signature, message)) in items.iter().enumerate() {
+        let (pk, r, s) = match signature {
+            Signature::SingleSig((pk, r, s)) => (pk, r, s),
+            Signature::AggregateSig((pk, r, s)) => (pk, r, s),
+            Signature::ThresholdSig(_) => {
+                if verify_signature(signature, message) != Ok(true) {
+                    return Some(false);
+                }
+                continue;
+            },
+        };
+
+        // same as `verify_aggregate`, but R is lifted from r
+        if &N <= s {
+            return Some(false);
+        }
+        let point_r = Affine::lift_x_square_y(r)?;
+        let point_p = Affine::from_public_key(&PublicKey::from_slice(pk).ok()?);
+        let s = SecretKey::from_slice(s).ok()?;
+        let mut e = get_challenge(pk, r, message)?;
+
+        // a_0 = 1 and a_i is random
+        let a = if index == 0 {
+            None
+        } else {
+            let mut a = [0u8; 32];
+            OsRng.fill_bytes(&mut a);
+            Some(SecretKey::from_slice(&a).ok()?)
+        };
+        let mut a_s = s;
+        if let Some(a) = a.as_ref() {
+            a_s.mul_assign(&a[..]).ok()?;
+            e.mul_assign(&a[..]).ok()?;
+        }
+        sum_s = match sum_s {
+            Some(mut sum_s) => {
+                sum_s.add_assign(&a_s[..]).ok()?;
+                Some(sum_s)
+            },
+            None => Some(a_s),
+        };
+        scalars.push(a.map(|a| to_scalar(&a[..])).unwrap_or_else(|| {
+            let mut one = [0u8; 32];
+            one[31] = 1;
+            one
+        }));
+        points.push(point_r.neg());
+        scalars.push(to_scalar(&e[..]));
+        points.push(point_p.neg());
+    }
+    if let Some(sum_s) = sum_s {
+        scalars.push(to_scalar(&sum_s[..]));
+        points.push(Affine::from_public_key(&generator()));
+    }
+    Some(multi_scalar_mul(&scalars, &points).is_infinity())
+}
+
+/// verify signatures with its message at once
+///
+/// return index of the first wrong signature, checked one by one only if batch failed
+/// note: less than `BATCH_MIN_SIZE` signatures (a mempool tx) are checked one by one
+pub fn verify_batch(items: &[(&Signature, &[u8])]) -> Result<(), usize> {
+    if BATCH_MIN_SIZE <= items.len() && batch_check(items) == Some(true) {
+        return Ok(());
+    }
+    for (index, (signature, message)) in items.iter().enumerate() {
+        if verify_signature(signature, message) != Ok(true) {
+            return Err(index);
+        }
+    }
+    Ok(())
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod batch_verify {
+    use crate::signature::batch::*;
+    use crate::signature::Signature;
+    use std::time::Instant;
+
+    #[test]
+    fn vectors() {
+        let params = [
+            (
+                "0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "787A848E71043D280C50470E8E1532B2DD5D20EE912A45DBDD2BD1DFBF187EF6",
+                "7031A98831859DC34DFFEEDDA86831842CCD0079E1F92AF177F7F22CC1DCED05",
+            ),
+            (
+                "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+                "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+                "2A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D",
+                "1E51A22CCEC35599B8F266912281F8365FFC2D035A230434A1A64DC59F7013FD",
+            ),
+            (
+                "03FAC2114C2FBB091527EB7C64ECB11F8021CB45E8E7809D3C0938E4B8C0E5F84B",
+                "5E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C",
+                "00DA9B08172A9B6F0466A2DEFD817F2D7AB437E0D253CB5395A963866B3574BE",
+                "00880371D01766935B92D2AB4CD5C8A2A5837EC57FED7660773A05F0DE142380",
+            ),
+            (
+                "031B84C5567B126440995D3ED5AABA0565D71E1834604819FF9C17F5E9D5DD078F",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "52818579ACA59767E3291D91B76B637BEF062083284992F2D95F564CA6CB4E35",
+                "30B1DA849C8E8304ADC0CFE870660334B3CFC18E825EF1DB34CFAE3DFC5D8187",
+            ),
+        ];
+        let mut signatures = Vec::new();
+        let mut messages = Vec::new();
+        for (pk, msg, r, s) in params.iter() {
+            let pk = hex::decode(pk).unwrap();
+            let r = hex::decode(r).unwrap();
+            let s = hex::decode(s).unwrap();
+            signatures.push(Signature::new_single_sig(&pk, &r, &s).unwrap());
+            messages.push(hex::decode(msg).unwrap());
+        }
+        let items = signatures
+            .iter()
+            .zip(messages.iter())
+            .map(|(signature, msg)| (signature, msg.as_slice()))
+            .collect::<Vec<_>>();
+        assert_eq!(batch_check(&items), Some(true));
+        assert_eq!(verify_batch(&items), Ok(()));
+
+        // swap message of 2 and 3
+        let mut items = items;
+        let tmp = items[2].1;
+        items[2].1 = items[3].1;
+        items[3].1 = tmp;
+        assert_eq!(batch_check(&items), Some(false));
+        assert_eq!(verify_batch(&items), Err(2));
+    }
+
+    /// single and batch verification time by size
+    ///
+    /// note: run by `cargo test --release bench_batch -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_batch() {
+        let msg = b"block tx body bytes";
+        let mut signatures = Vec::with_capacity(2000);
+        let mut sk = [7u8; 32];
+        for i in 0..2000u16 {
+            sk[30..32].clone_from_slice(&i.to_be_bytes());
+            let mut pk = generator();
+            raw_scalar_mul(&mut pk, &sk).unwrap();
+            signatures.push(Signature::get_single_sign(&sk, &pk.serialize(), msg).unwrap());
+        }
+        for &size in [2, 4, 8, 16, 32, 64, 128, 2000].iter() {
+            let items = signatures[..size]
+                .iter()
+                .map(|signature| (signature, &msg[..]))
+                .collect::<Vec<_>>();
+            let start = Instant::now();
+            for (signature, msg) in items.iter() {
+                assert_eq!(verify_signature(signature, msg), Ok(true));
+            }
+            let single = start.elapsed();
+            let start = Instant::now();
+            assert_eq!(batch_check(&items), Some(true));
+            let batch = start.elapsed();
+            println!("{} signatures: single {:?} batch {:?}", size, single, batch);
+        }
+    }
+}
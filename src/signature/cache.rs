@@ -0,0 +1,162 @@
+use crate::signature::{signature_to_bytes, verify_batch, Signature};
+use sha2::{Digest, Sha256};
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// default number of cached signatures (about 32 bytes each)
+pub static SIGNATURE_CACHE_SIZE: usize = 100000;
+
+lazy_static! {
+    /// shared by mempool and block validation
+    static ref CACHE: SignatureCache = SignatureCache::new(SIGNATURE_CACHE_SIZE);
+}
+
+type CacheKey = [u8; 32];
+
+/// bounded cache of verified signatures, the oldest is removed first
+pub struct SignatureCache {
+    inner: Mutex<CacheInner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct CacheInner {
+    capacity: usize,
+    keys: HashSet<CacheKey>,
+    order: VecDeque<CacheKey>,
+}
+
+/// calc: key = hash(body hash || signature bytes)
+fn get_cache_key(hash: &[u8], signature: &Signature) -> CacheKey {
+    let mut vec = Vec::with_capacity(hash.len() + 33 + 33 + 32);
+    vec.extend_from_slice(hash);
+    signature_to_bytes(signature, &mut vec);
+    let mut key = [0u8; 32];
+    key.clone_from_slice(Sha256::digest(&vec).as_slice());
+    key
+}
+
+/// get the cache shared by mempool and block validation
+pub fn get_signature_cache() -> &'static SignatureCache {
+    &CACHE
+}
+
+impl SignatureCache {
+    pub fn new(capacity: usize) -> Self {
+        SignatureCache {
+            inner: Mutex::new(CacheInner {
+                capacity,
+                keys: HashSet::with_capacity(capacity.min(SIGNATURE_CACHE_SIZE)),
+                order: VecDeque::with_capacity(capacity.min(SIGNATURE_CACHE_SIZE)),
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// check the signature of the body is already verified
+    pub fn contains(&self, hash: &[u8], signature: &Signature) -> bool {
+        let key = get_cache_key(hash, signature);
+        let found = self.inner.lock().unwrap().keys.contains(&key);
+        if found {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        found
+    }
+
+    /// note: insert only verified signature
+    pub fn insert(&self, hash: &[u8], signature: &Signature) {
+        let key = get_cache_key(hash, signature);
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 || !inner.keys.insert(key) {
+            return;
+        }
+        inner.order.push_back(key);
+        while inner.capacity < inner.order.len() {
+            let old = inner.order.pop_front().unwrap();
+            inner.keys.remove(&old);
+        }
+    }
+
+    /// resize and remove the oldest if over
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.capacity = capacity;
+        while capacity < inner.order.len() {
+            let old = inner.order.pop_front().unwrap();
+            inner.keys.remove(&old);
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.keys.clear();
+        inner.order.clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    /// (hits, misses, length, capacity)
+    pub fn get_stats(&self) -> (u64, u64, usize, usize) {
+        let inner = self.inner.lock().unwrap();
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+            inner.order.len(),
+            inner.capacity,
+        )
+    }
+}
+
+/// `verify_batch` skipping cached signatures, items are (body hash, signature, message)
+///
+/// return index of the first wrong signature, verified signatures are cached on success
+pub fn verify_batch_cached(items: &[(&[u8], &Signature, &[u8])]) -> Result<(), usize> {
+    let cache = get_signature_cache();
+    let mut indexes = Vec::with_capacity(items.len());
+    let mut uncached = Vec::with_capacity(items.len());
+    for (index, (hash, signature, message)) in items.iter().enumerate() {
+        if !cache.contains(hash, signature) {
+            indexes.push(index);
+            uncached.push((*signature, *message));
+        }
+    }
+    verify_batch(&uncached).map_err(|position| indexes[position])?;
+    for &index in indexes.iter() {
+        let (hash, signature, _) = items[index];
+        cache.insert(hash, signature);
+    }
+    Ok(())
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod signature_cache {
+    use crate::signature::cache::*;
+    use crate::signature::Signature;
+
+    #[test]
+    fn bounded() {
+        let pk = hex::decode("0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798").unwrap();
+        let r = hex::decode("787A848E71043D280C50470E8E1532B2DD5D20EE912A45DBDD2BD1DFBF187EF6").unwrap();
+        let s = hex::decode("7031A98831859DC34DFFEEDDA86831842CCD0079E1F92AF177F7F22CC1DCED05").unwrap();
+        let signature = Signature::new_single_sig(&pk, &r, &s).unwrap();
+
+        let cache = SignatureCache::new(2);
+        assert!(!cache.contains(&[0u8; 32], &signature));
+        cache.insert(&[0u8; 32], &signature);
+        cache.insert(&[1u8; 32], &signature);
+        assert!(cache.contains(&[0u8; 32], &signature));
+        cache.insert(&[2u8; 32], &signature);
+        assert!(!cache.contains(&[0u8; 32], &signature));
+        assert!(cache.contains(&[2u8; 32], &signature));
+        assert_eq!(cache.get_stats(), (2, 2, 2, 2));
+        cache.set_capacity(1);
+        assert!(!cache.contains(&[1u8; 32], &signature));
+        cache.clear();
+        assert_eq!(cache.get_stats(), (0, 0, 0, 1));
+    }
+}
@@ -0,0 +1,301 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// 2^256 - P = 2^32 + 977
+const P_COMPLEMENT: u64 = 0x1_0000_03d1;
+
+/// P in little endian u64 limbs
+const P_LIMBS: [u64; 4] = [
+    0xffff_fffe_ffff_fc2f,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+];
+
+/// (P + 1) / 4 in big endian for square root
+const SQRT_EXP: [u8; 32] = [
+    0x3f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xbf, 0xff, 0xff, 0x0c,
+];
+
+/// (P - 1) / 2 in big endian for jacobi symbol
+const LEGENDRE_EXP: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 0xff, 0xfe, 0x17,
+];
+
+/// element of secp256k1 base field, little endian u64 limbs always less than P
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fe([u64; 4]);
+
+/// a + b + carry -> (sum, carry)
+#[inline]
+pub(super) fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+/// a - b - borrow -> (diff, borrow)
+#[inline]
+pub(super) fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = (a as u128).wrapping_sub(b as u128 + borrow as u128);
+    (t as u64, (t >> 127) as u64)
+}
+
+/// big endian 32 bytes to little endian u64 limbs
+pub(super) fn limbs_from_bytes(bytes: &[u8]) -> [u64; 4] {
+    assert_eq!(bytes.len(), 32);
+    let mut limbs = [0u64; 4];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let mut tmp = [0u8; 8];
+        tmp.clone_from_slice(&bytes[24 - 8 * i..32 - 8 * i]);
+        *limb = u64::from_be_bytes(tmp);
+    }
+    limbs
+}
+
+/// little endian u64 limbs to big endian 32 bytes
+pub(super) fn limbs_to_bytes(limbs: &[u64; 4]) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    for (i, limb) in limbs.iter().enumerate() {
+        bytes[24 - 8 * i..32 - 8 * i].clone_from_slice(&limb.to_be_bytes());
+    }
+    bytes
+}
+
+/// select `b` if flag is 1 else `a` without branch
+#[inline]
+pub(super) fn select_limbs(a: &[u64; 4], b: &[u64; 4], flag: u64) -> [u64; 4] {
+    let mask = flag.wrapping_neg();
+    let mut out = [0u64; 4];
+    for (o, (a, b)) in out.iter_mut().zip(a.iter().zip(b.iter())) {
+        *o = (a & !mask) | (b & mask);
+    }
+    out
+}
+
+impl Fe {
+    pub const ZERO: Fe = Fe([0, 0, 0, 0]);
+    pub const ONE: Fe = Fe([1, 0, 0, 0]);
+
+    pub fn from_u64(n: u64) -> Fe {
+        Fe([n, 0, 0, 0])
+    }
+
+    /// big endian 32 bytes, none if not less than P
+    pub fn from_bytes(bytes: &[u8]) -> Option<Fe> {
+        let limbs = limbs_from_bytes(bytes);
+        let (_, borrow) = Self::sub_p(&limbs);
+        if borrow == 1 {
+            Some(Fe(limbs))
+        } else {
+            None
+        }
+    }
+
+    /// big endian 32 bytes
+    pub fn to_bytes(self) -> [u8; 32] {
+        limbs_to_bytes(&self.0)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().fold(0, |acc, limb| acc | limb) == 0
+    }
+
+    /// compare all limbs without early return
+    pub fn ct_eq(&self, other: &Fe) -> bool {
+        let mut diff = 0;
+        for (a, b) in self.0.iter().zip(other.0.iter()) {
+            diff |= a ^ b;
+        }
+        diff == 0
+    }
+
+    /// limbs - P -> (diff, borrow)
+    #[inline]
+    fn sub_p(limbs: &[u64; 4]) -> ([u64; 4], u64) {
+        let mut out = [0u64; 4];
+        let mut borrow = 0;
+        for i in 0..4 {
+            let (d, b) = sbb(limbs[i], P_LIMBS[i], borrow);
+            out[i] = d;
+            borrow = b;
+        }
+        (out, borrow)
+    }
+
+    /// reduce `limbs + carry * 2^256` (carry is small) to less than P
+    #[inline]
+    fn reduce(limbs: [u64; 4], carry: u64) -> Fe {
+        // 2^256 = P_COMPLEMENT mod P
+        let v = carry as u128 * P_COMPLEMENT as u128;
+        let (l0, c) = adc(limbs[0], v as u64, 0);
+        let (l1, c) = adc(limbs[1], (v >> 64) as u64, c);
+        let (l2, c) = adc(limbs[2], 0, c);
+        let (l3, c) = adc(limbs[3], 0, c);
+        let (l0, c) = adc(l0, c * P_COMPLEMENT, 0);
+        let (l1, c) = adc(l1, 0, c);
+        let (l2, c) = adc(l2, 0, c);
+        let (l3, _) = adc(l3, 0, c);
+        let limbs = [l0, l1, l2, l3];
+        // subtract P if not less than P
+        let (sub, borrow) = Self::sub_p(&limbs);
+        Fe(select_limbs(&sub, &limbs, borrow))
+    }
+
+    /// add P back if borrowed: -2^256 + P = -P_COMPLEMENT
+    #[inline]
+    fn add_p_if_borrowed(limbs: [u64; 4], borrow: u64) -> Fe {
+        let (l0, b) = sbb(limbs[0], P_COMPLEMENT & borrow.wrapping_neg(), 0);
+        let (l1, b) = sbb(limbs[1], 0, b);
+        let (l2, b) = sbb(limbs[2], 0, b);
+        let (l3, _) = sbb(limbs[3], 0, b);
+        Fe([l0, l1, l2, l3])
+    }
+
+    pub fn square(&self) -> Fe {
+        *self * *self
+    }
+
+    /// self^exp, exp is big endian
+    ///
+    /// note: always multiply and select, the time depends only on exp length
+    pub fn pow(&self, exp: &[u8]) -> Fe {
+        let mut out = Fe::ONE;
+        for byte in exp.iter() {
+            for i in (0..8).rev() {
+                out = out.square();
+                let mul = out * *self;
+                out = Fe(select_limbs(&out.0, &mul.0, ((byte >> i) & 1) as u64));
+            }
+        }
+        out
+    }
+
+    /// square root with jacobi symbol 1, none if not exists
+    pub fn sqrt(&self) -> Option<Fe> {
+        let root = self.pow(&SQRT_EXP);
+        if root.square().ct_eq(self) {
+            Some(root)
+        } else {
+            None
+        }
+    }
+
+    /// jacobi symbol is 1, calc: self^((P-1)/2) = 1
+    pub fn is_square(&self) -> bool {
+        self.pow(&LEGENDRE_EXP).ct_eq(&Fe::ONE)
+    }
+}
+
+impl Add for Fe {
+    type Output = Fe;
+
+    fn add(self, other: Fe) -> Fe {
+        let mut limbs = [0u64; 4];
+        let mut carry = 0;
+        for (limb, (a, b)) in limbs.iter_mut().zip(self.0.iter().zip(other.0.iter())) {
+            let (s, c) = adc(*a, *b, carry);
+            *limb = s;
+            carry = c;
+        }
+        Fe::reduce(limbs, carry)
+    }
+}
+
+impl Neg for Fe {
+    type Output = Fe;
+
+    fn neg(self) -> Fe {
+        Fe::ZERO - self
+    }
+}
+
+impl Sub for Fe {
+    type Output = Fe;
+
+    fn sub(self, other: Fe) -> Fe {
+        let mut limbs = [0u64; 4];
+        let mut borrow = 0;
+        for (limb, (a, b)) in limbs.iter_mut().zip(self.0.iter().zip(other.0.iter())) {
+            let (d, b) = sbb(*a, *b, borrow);
+            *limb = d;
+            borrow = b;
+        }
+        Fe::add_p_if_borrowed(limbs, borrow)
+    }
+}
+
+impl Mul for Fe {
+    type Output = Fe;
+
+    fn mul(self, other: Fe) -> Fe {
+        // 512 bits product
+        let mut t = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let v = self.0[i] as u128 * other.0[j] as u128 + t[i + j] as u128 + carry;
+                t[i + j] = v as u64;
+                carry = v >> 64;
+            }
+            t[i + 4] = carry as u64;
+        }
+        // calc: low + high * P_COMPLEMENT
+        let mut limbs = [0u64; 4];
+        let mut carry = 0u128;
+        for i in 0..4 {
+            let v = t[i] as u128 + t[i + 4] as u128 * P_COMPLEMENT as u128 + carry;
+            limbs[i] = v as u64;
+            carry = v >> 64;
+        }
+        Fe::reduce(limbs, carry as u64)
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod field_ops {
+    use crate::signature::field::*;
+    use crate::signature::utils::P;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn arithmetic() {
+        let p = BigUint::from_bytes_be(&P);
+        assert!(Fe::from_bytes(&P).is_none());
+        let a_bytes =
+            hex::decode("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e").unwrap();
+        let b_bytes =
+            hex::decode("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798").unwrap();
+        let a = Fe::from_bytes(&a_bytes).unwrap();
+        let b = Fe::from_bytes(&b_bytes).unwrap();
+        let big_a = BigUint::from_bytes_be(&a_bytes);
+        let big_b = BigUint::from_bytes_be(&b_bytes);
+        let to_fe = |big: BigUint| {
+            let vec = big.to_bytes_be();
+            let mut bytes = [0u8; 32];
+            bytes[32 - vec.len()..32].clone_from_slice(&vec);
+            Fe::from_bytes(&bytes).unwrap()
+        };
+
+        assert_eq!(a + b, to_fe((&big_a + &big_b) % &p));
+        assert_eq!(b - a, to_fe((&big_b + &p - &big_a) % &p));
+        assert_eq!(a * b, to_fe((&big_a * &big_b) % &p));
+        assert_eq!(a.square(), to_fe((&big_a * &big_a) % &p));
+        assert_eq!(-a, Fe::ONE);
+        let root = b.square().sqrt().unwrap();
+        assert!(root == b || root == -b);
+        assert!(Fe::from_u64(3).sqrt().is_none());
+        assert_eq!(b.to_bytes().to_vec(), b_bytes);
+
+        // jacobi symbol
+        let exp = (&p - 1u32) / 2u32;
+        for fe in [a, b, a * b, a + b, Fe::from_u64(3), Fe::from_u64(4)].iter() {
+            let big = BigUint::from_bytes_be(&fe.to_bytes());
+            let expected = big.modpow(&exp, &p) == BigUint::from(1u32);
+            assert_eq!(fe.is_square(), expected);
+        }
+        assert!(b.square().is_square());
+        assert!(!Fe::ZERO.is_square());
+    }
+}
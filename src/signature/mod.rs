@@ -3,9 +3,25 @@ use crate::utils::sha256ripemd160;
 use std::fmt;
 
 mod aggregate;
+mod batch;
+mod cache;
+mod field;
+mod musig;
+mod scalar;
 mod threshold;
 mod utils;
 
+pub use batch::verify_batch;
+pub use cache::{get_signature_cache, verify_batch_cached, SignatureCache};
+pub use musig::*;
+pub use threshold::{
+    combine_threshold,
+    sign_threshold_partial,
+    verify_threshold_partial,
+    ThresholdKeyGen,
+    ThresholdShare,
+};
+
 // signature data type
 type POINT = [u8; 33];
 type SCALAR = [u8; 32];
@@ -119,10 +135,17 @@ impl Signature {
     }
 
     /// get 1 of 1 signature
+    /// deterministic, same inputs always give the same signature
     pub fn get_single_sign(sk: &[u8], pk: &POINT, msg: &[u8]) -> Result<Self, String> {
+        Signature::get_single_sign_with_aux(sk, pk, msg, &[0u8; 32])
+    }
+
+    /// get 1 of 1 signature with auxiliary randomness `aux` mixed to nonce
+    /// note: signature differs by aux, PartialTx cannot dedup it
+    pub fn get_single_sign_with_aux(sk: &[u8], pk: &POINT, msg: &[u8], aux: &[u8]) -> Result<Self, String> {
         let mut tmp = [0u8; 32];
         tmp.clone_from_slice(sk);
-        match aggregate::sign_aggregate(&tmp, msg) {
+        match aggregate::sign_aggregate(&tmp, msg, aux) {
             Ok((r, s)) => Ok(Signature::SingleSig((pk.clone(), r, s))),
             Err(err) => Err(err.to_string()),
         }
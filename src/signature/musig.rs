@@ -0,0 +1,472 @@
+use crate::signature::utils::*;
+use crate::signature::{Signature, POINT, SCALAR};
+use crate::utils::sha256ripemd160;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use secp256k1::{PublicKey, SecretKey};
+use sha2::{Digest, Sha256};
+use std::convert::TryInto;
+use zeroize::Zeroize;
+
+type Address = [u8; 21];
+
+/// format version of serialized session
+static SESSION_VERSION: u8 = 1;
+
+/// sort and check public keys, signers order is fixed by the sort
+pub fn musig_sort_keys(pubkeys: &[POINT]) -> Result<Vec<POINT>, String> {
+    if pubkeys.len() < 2 || 255 < pubkeys.len() {
+        return Err(format!("signers is 2 to 255 but {}", pubkeys.len()));
+    }
+    let mut sorted = pubkeys.to_vec();
+    sorted.sort_by(|a, b| a[..].cmp(&b[..]));
+    sorted.dedup_by(|a, b| slice_eq(&a[..], &b[..]));
+    if sorted.len() != pubkeys.len() {
+        return Err("duplicate public key found".to_owned());
+    }
+    for pk in sorted.iter() {
+        PublicKey::from_slice(pk).map_err(|err| format!("invalid public key: {}", err))?;
+    }
+    Ok(sorted)
+}
+
+/// calc: L = hash(P_1 || .. || P_n)
+fn hash_keys(sorted: &[POINT]) -> [u8; 32] {
+    let mut vec = Vec::with_capacity(33 * sorted.len());
+    sorted.iter().for_each(|pk| vec.extend_from_slice(pk));
+    Sha256::digest(&vec).as_slice().try_into().unwrap()
+}
+
+/// calc: a_i = int(hash(L || P_i)) mod n
+fn get_coefficient(hashed_keys: &[u8; 32], pk: &POINT) -> Result<SecretKey, String> {
+    let mut vec = Vec::with_capacity(32 + 33);
+    vec.extend_from_slice(hashed_keys);
+    vec.extend_from_slice(pk);
+    SecretKey::from_slice(&Sha256::digest(&vec)).map_err(|err| format!("coefficient: {}", err))
+}
+
+/// calc: apk = a_1 * P_1 + .. + a_n * P_n
+fn aggregate_sorted_keys(sorted: &[POINT]) -> Result<POINT, String> {
+    let hashed_keys = hash_keys(sorted);
+    let mut apk: Option<PublicKey> = None;
+    for pk in sorted.iter() {
+        let a = get_coefficient(&hashed_keys, pk)?;
+        let mut point = PublicKey::from_slice(pk).map_err(|err| err.to_string())?;
+        scalar_mul(&mut point, &a).map_err(|err| err.to_string())?;
+        apk = match apk {
+            Some(apk) => Some(apk.combine(&point).map_err(|err| err.to_string())?),
+            None => Some(point),
+        };
+    }
+    Ok(apk.unwrap().serialize())
+}
+
+/// aggregate public keys to `apk` of AggregateSig (order free)
+pub fn aggregate_public_keys(pubkeys: &[POINT]) -> Result<POINT, String> {
+    aggregate_sorted_keys(&musig_sort_keys(pubkeys)?)
+}
+
+/// n of n address owned by the public keys
+pub fn get_aggregate_address(ver: u8, pubkeys: &[POINT]) -> Result<Address, String> {
+    Ok(sha256ripemd160(ver, &aggregate_public_keys(pubkeys)?))
+}
+
+/// calc: commitment = hash(R_i)
+fn get_nonce_commitment(nonce: &POINT) -> [u8; 32] {
+    Sha256::digest(nonce).as_slice().try_into().unwrap()
+}
+
+/// one signer's state of n of n signing
+///
+/// rounds: exchange commitments -> reveal nonces -> exchange partial signatures -> combine
+/// note: the secret nonce isn't serialized, a restored session can combine but can't sign
+pub struct MuSigSession {
+    pubkeys: Vec<POINT>,
+    index: usize,
+    apk: POINT,
+    message: Vec<u8>,
+    secret_nonce: Option<SCALAR>,
+    commitments: Vec<Option<[u8; 32]>>,
+    nonces: Vec<Option<POINT>>,
+    partials: Vec<Option<SCALAR>>,
+}
+
+impl Drop for MuSigSession {
+    fn drop(&mut self) {
+        if let Some(k) = self.secret_nonce.as_mut() {
+            k.zeroize();
+        }
+    }
+}
+
+impl MuSigSession {
+    /// start a session with a random nonce
+    pub fn new(pubkeys: &[POINT], my_pk: &POINT, message: &[u8]) -> Result<Self, String> {
+        // calc: k = int(hash(rand || P_i || m)) mod n
+        let mut rand = [0u8; 32];
+        OsRng.fill_bytes(&mut rand);
+        let mut vec = Vec::with_capacity(32 + 33 + message.len());
+        vec.extend_from_slice(&rand);
+        vec.extend_from_slice(my_pk);
+        vec.extend_from_slice(message);
+        let mut k: SCALAR = Sha256::digest(&vec).as_slice().try_into().unwrap();
+        rand.zeroize();
+        vec.zeroize();
+        let session = Self::with_nonce(pubkeys, my_pk, message, &k);
+        k.zeroize();
+        session
+    }
+
+    /// note: never reuse a nonce with other message or signers
+    pub fn with_nonce(pubkeys: &[POINT], my_pk: &POINT, message: &[u8], k: &SCALAR) -> Result<Self, String> {
+        let pubkeys = musig_sort_keys(pubkeys)?;
+        let index = pubkeys
+            .iter()
+            .position(|pk| slice_eq(pk, my_pk))
+            .ok_or("my public key isn't in signers")?;
+        let apk = aggregate_sorted_keys(&pubkeys)?;
+
+        // calc: R_i = k * G
+        let mut nonce = generator();
+        raw_scalar_mul(&mut nonce, k).map_err(|err| format!("invalid nonce: {}", err))?;
+        let nonce = nonce.serialize();
+
+        let size = pubkeys.len();
+        let mut session = MuSigSession {
+            pubkeys,
+            index,
+            apk,
+            message: message.to_vec(),
+            secret_nonce: Some(*k),
+            commitments: vec![None; size],
+            nonces: vec![None; size],
+            partials: vec![None; size],
+        };
+        session.commitments[index] = Some(get_nonce_commitment(&nonce));
+        session.nonces[index] = Some(nonce);
+        Ok(session)
+    }
+
+    pub fn get_pubkeys(&self) -> &Vec<POINT> {
+        &self.pubkeys
+    }
+
+    pub fn get_apk(&self) -> &POINT {
+        &self.apk
+    }
+
+    pub fn get_address(&self, ver: u8) -> Address {
+        sha256ripemd160(ver, &self.apk)
+    }
+
+    pub fn get_message(&self) -> &[u8] {
+        self.message.as_slice()
+    }
+
+    fn get_signer_index(&self, pk: &POINT) -> Result<usize, String> {
+        self.pubkeys
+            .iter()
+            .position(|_pk| slice_eq(_pk, pk))
+            .ok_or_else(|| "unknown signer's public key".to_owned())
+    }
+
+    /// round 1: send my commitment to others
+    pub fn get_commitment(&self) -> [u8; 32] {
+        self.commitments[self.index].unwrap()
+    }
+
+    /// round 1: receive other's commitment
+    pub fn add_commitment(&mut self, pk: &POINT, commitment: &[u8; 32]) -> Result<(), String> {
+        let index = self.get_signer_index(pk)?;
+        match self.commitments[index] {
+            Some(ref _commitment) if _commitment == commitment => Ok(()),
+            Some(_) => Err("conflict with received commitment".to_owned()),
+            None => {
+                self.commitments[index] = Some(*commitment);
+                Ok(())
+            },
+        }
+    }
+
+    pub fn has_all_commitments(&self) -> bool {
+        self.commitments.iter().all(|commitment| commitment.is_some())
+    }
+
+    /// round 2: send my nonce after received all commitments
+    pub fn get_nonce(&self) -> Result<POINT, String> {
+        if self.has_all_commitments() {
+            Ok(self.nonces[self.index].unwrap())
+        } else {
+            Err("wait for all commitments before reveal nonce".to_owned())
+        }
+    }
+
+    /// round 2: receive other's nonce, it must match the commitment
+    pub fn add_nonce(&mut self, pk: &POINT, nonce: &POINT) -> Result<(), String> {
+        let index = self.get_signer_index(pk)?;
+        let commitment = self.commitments[index].ok_or("the signer's commitment isn't received")?;
+        if get_nonce_commitment(nonce) != commitment {
+            return Err("nonce doesn't match the commitment".to_owned());
+        }
+        PublicKey::from_slice(nonce).map_err(|err| format!("invalid nonce: {}", err))?;
+        self.nonces[index] = Some(*nonce);
+        Ok(())
+    }
+
+    pub fn has_all_nonces(&self) -> bool {
+        self.nonces.iter().all(|nonce| nonce.is_some())
+    }
+
+    /// calc: R = R_1 + .. + R_n and is R negated for jacobi
+    fn get_combined_nonce(&self) -> Result<(PublicKey, bool), String> {
+        let mut combined: Option<PublicKey> = None;
+        for nonce in self.nonces.iter() {
+            let nonce = nonce.ok_or("wait for all nonces")?;
+            let nonce = PublicKey::from_slice(&nonce).map_err(|err| err.to_string())?;
+            combined = match combined {
+                Some(combined) => Some(combined.combine(&nonce).map_err(|err| err.to_string())?),
+                None => Some(nonce),
+            };
+        }
+        let combined = combined.unwrap();
+        let negated = !is_jacobi(&combined);
+        Ok((combined, negated))
+    }
+
+    /// calc: e = int(hash(bytes(x(R)) || apk || m)) mod n
+    fn get_challenge(&self, combined: &PublicKey) -> Result<SecretKey, String> {
+        let mut vec = Vec::with_capacity(32 + 33 + self.message.len());
+        vec.extend_from_slice(&combined.serialize()[1..33]);
+        vec.extend_from_slice(&self.apk);
+        vec.extend_from_slice(&self.message);
+        SecretKey::from_slice(&Sha256::digest(&vec)).map_err(|err| format!("challenge: {}", err))
+    }
+
+    /// round 3: sign by my secret key, the nonce is consumed
+    pub fn sign_partial(&mut self, sk: &SCALAR) -> Result<SCALAR, String> {
+        let k = self
+            .secret_nonce
+            .ok_or("no secret nonce, it's already used or the session is restored")?;
+        let (combined, negated) = self.get_combined_nonce()?;
+
+        // check: sk * G = P_i
+        let mut pk = generator();
+        raw_scalar_mul(&mut pk, sk).map_err(|err| format!("invalid secret key: {}", err))?;
+        if !slice_eq(&pk.serialize(), &self.pubkeys[self.index]) {
+            return Err("secret key doesn't match my public key".to_owned());
+        }
+
+        // calc: s_i = k + e * a_i * sk mod n
+        let mut k = SecretKey::from_slice(&k).map_err(|err| err.to_string())?;
+        if negated {
+            // over write: k = n - k
+            k = inv_scalar(&k).map_err(|err| err.to_string())?;
+        }
+        let mut e = self.get_challenge(&combined)?;
+        let a = get_coefficient(&hash_keys(&self.pubkeys), &self.pubkeys[self.index])?;
+        e.mul_assign(&a[..]).map_err(|err| err.to_string())?;
+        e.mul_assign(sk).map_err(|err| err.to_string())?;
+        k.add_assign(&e[..]).map_err(|err| err.to_string())?;
+        let mut s: SCALAR = [0u8; 32];
+        s.clone_from_slice(&k[..]);
+
+        // remove the nonce for no reuse
+        self.secret_nonce.as_mut().unwrap().zeroize();
+        self.secret_nonce = None;
+        self.partials[self.index] = Some(s);
+        Ok(s)
+    }
+
+    /// round 3: receive and verify other's partial signature
+    #[allow(non_snake_case)]
+    pub fn add_partial(&mut self, pk: &POINT, s: &SCALAR) -> Result<(), String> {
+        let index = self.get_signer_index(pk)?;
+        let (combined, negated) = self.get_combined_nonce()?;
+
+        // calc: s_i * G - e * a_i * P_i = R_i (or -R_i)
+        let mut sG = generator();
+        raw_scalar_mul(&mut sG, s).map_err(|err| format!("invalid partial signature: {}", err))?;
+        let mut e = self.get_challenge(&combined)?;
+        let a = get_coefficient(&hash_keys(&self.pubkeys), pk)?;
+        e.mul_assign(&a[..]).map_err(|err| err.to_string())?;
+        let mut eP = PublicKey::from_slice(pk).map_err(|err| err.to_string())?;
+        scalar_mul(&mut eP, &e).map_err(|err| err.to_string())?;
+        let R = sub_point(&sG, &eP).map_err(|err| err.to_string())?.serialize();
+
+        let mut expected = self.nonces[index].unwrap();
+        if negated {
+            // flip prefix 0x02 <-> 0x03
+            expected[0] ^= 1;
+        }
+        if !slice_eq(&R, &expected) {
+            return Err("invalid partial signature".to_owned());
+        }
+        self.partials[index] = Some(*s);
+        Ok(())
+    }
+
+    pub fn has_all_partials(&self) -> bool {
+        self.partials.iter().all(|s| s.is_some())
+    }
+
+    /// combine all partial signatures to AggregateSig (apk, x(R), s_1 + .. + s_n)
+    pub fn combine(&self) -> Result<Signature, String> {
+        let (combined, _) = self.get_combined_nonce()?;
+        let mut s: Option<SecretKey> = None;
+        for partial in self.partials.iter() {
+            let partial = partial.ok_or("wait for all partial signatures")?;
+            s = match s {
+                Some(mut s) => {
+                    s.add_assign(&partial).map_err(|err| err.to_string())?;
+                    Some(s)
+                },
+                None => Some(SecretKey::from_slice(&partial).map_err(|err| err.to_string())?),
+            };
+        }
+        let s = s.unwrap();
+        Signature::new_aggregate_sig(&self.apk, &combined.serialize()[1..33], &s[..])
+            .map_err(|_| "failed to generate aggregate signature".to_owned())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // [ver u8][size u8][index u8][pubkeys][msg_len u32][msg]
+        // [flag + commitment, flag + nonce, flag + partial] * size
+        // note: secret nonce is never written for no replay by restored copies
+        let size = self.pubkeys.len();
+        let mut vec = Vec::with_capacity(3 + 33 * size + 4 + self.message.len() + 101 * size);
+        vec.push(SESSION_VERSION);
+        vec.push(size as u8);
+        vec.push(self.index as u8);
+        self.pubkeys.iter().for_each(|pk| vec.extend_from_slice(pk));
+        vec.extend_from_slice(&(self.message.len() as u32).to_le_bytes());
+        vec.extend_from_slice(&self.message);
+        for i in 0..size {
+            write_option(&mut vec, self.commitments[i].as_ref().map(|c| &c[..]));
+            write_option(&mut vec, self.nonces[i].as_ref().map(|r| &r[..]));
+            write_option(&mut vec, self.partials[i].as_ref().map(|s| &s[..]));
+        }
+        vec
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut pos = 0usize;
+        let header = read_slice(bytes, &mut pos, 3)?;
+        if header[0] != SESSION_VERSION {
+            return Err(format!("unknown session version {}", header[0]));
+        }
+        let size = header[1] as usize;
+        let index = header[2] as usize;
+        let mut pubkeys = Vec::with_capacity(size);
+        for _ in 0..size {
+            pubkeys.push(to_point(read_slice(bytes, &mut pos, 33)?));
+        }
+        let sorted = musig_sort_keys(&pubkeys)?;
+        if !sorted.iter().zip(pubkeys.iter()).all(|(a, b)| slice_eq(a, b)) || size <= index {
+            return Err("broken signers".to_owned());
+        }
+        let msg_len = u32::from_le_bytes(read_slice(bytes, &mut pos, 4)?.try_into().unwrap()) as usize;
+        let message = read_slice(bytes, &mut pos, msg_len)?.to_vec();
+        let mut commitments = Vec::with_capacity(size);
+        let mut nonces = Vec::with_capacity(size);
+        let mut partials = Vec::with_capacity(size);
+        for _ in 0..size {
+            commitments.push(read_option(bytes, &mut pos, 32)?.map(|c| c.try_into().unwrap()));
+            nonces.push(read_option(bytes, &mut pos, 33)?.map(to_point));
+            partials.push(read_option(bytes, &mut pos, 32)?.map(|s| s.try_into().unwrap()));
+        }
+        if pos != bytes.len() {
+            return Err(format!("not all bytes used {}/{}", pos, bytes.len()));
+        }
+        if commitments[index].is_none() || nonces[index].is_none() {
+            return Err("my nonce isn't found".to_owned());
+        }
+        let apk = aggregate_sorted_keys(&pubkeys)?;
+        Ok(MuSigSession {
+            pubkeys,
+            index,
+            apk,
+            message,
+            secret_nonce: None,
+            commitments,
+            nonces,
+            partials,
+        })
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod musig_sign {
+    use crate::signature::musig::*;
+    use crate::signature::utils::*;
+    use crate::signature::{verify_signature, POINT};
+
+    fn get_pk(sk: &[u8; 32]) -> POINT {
+        let mut pk = generator();
+        raw_scalar_mul(&mut pk, sk).unwrap();
+        pk.serialize()
+    }
+
+    #[test]
+    fn sign_3_of_3() {
+        let secrets = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        let pubkeys = secrets.iter().map(get_pk).collect::<Vec<POINT>>();
+        let msg = b"musig test message";
+        let mut sessions = pubkeys
+            .iter()
+            .map(|pk| MuSigSession::new(&pubkeys, pk, msg).unwrap())
+            .collect::<Vec<MuSigSession>>();
+        let apk = aggregate_public_keys(&pubkeys).unwrap();
+        assert!(sessions.iter().all(|session| slice_eq(session.get_apk(), &apk)));
+
+        // round 1
+        let commitments = sessions.iter().map(|s| s.get_commitment()).collect::<Vec<_>>();
+        assert!(sessions[0].get_nonce().is_err());
+        for session in sessions.iter_mut() {
+            for (pk, commitment) in pubkeys.iter().zip(commitments.iter()) {
+                session.add_commitment(pk, commitment).unwrap();
+            }
+        }
+
+        // serialize between rounds without secret nonce
+        let bytes = sessions[1].to_bytes();
+        let mut restored = MuSigSession::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.to_bytes(), bytes);
+
+        // round 2
+        let nonces = sessions
+            .iter()
+            .map(|s| s.get_nonce().unwrap())
+            .collect::<Vec<_>>();
+        for session in sessions.iter_mut() {
+            for (pk, nonce) in pubkeys.iter().zip(nonces.iter()) {
+                session.add_nonce(pk, nonce).unwrap();
+            }
+        }
+        assert!(sessions[0].add_nonce(&pubkeys[1], &nonces[2]).is_err());
+        for (pk, nonce) in pubkeys.iter().zip(nonces.iter()) {
+            restored.add_nonce(pk, nonce).unwrap();
+        }
+        assert!(restored.sign_partial(&secrets[1]).is_err());
+
+        // round 3
+        let partials = sessions
+            .iter_mut()
+            .zip(secrets.iter())
+            .map(|(session, sk)| session.sign_partial(sk).unwrap())
+            .collect::<Vec<_>>();
+        assert!(sessions[0].sign_partial(&secrets[0]).is_err());
+        assert!(sessions[0].add_partial(&pubkeys[1], &partials[2]).is_err());
+        for (pk, s) in pubkeys.iter().zip(partials.iter()) {
+            sessions[0].add_partial(pk, s).unwrap();
+            restored.add_partial(pk, s).unwrap();
+        }
+        let signature = sessions[0].combine().unwrap();
+        assert_eq!(verify_signature(&signature, msg), Ok(true));
+        assert_eq!(restored.combine().unwrap(), signature);
+        assert_eq!(
+            signature.get_address(0),
+            get_aggregate_address(0, &pubkeys).unwrap()
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use crate::signature::field::{adc, limbs_from_bytes, limbs_to_bytes, sbb, select_limbs};
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// N in little endian u64 limbs
+const N_LIMBS: [u64; 4] = [
+    0xbfd2_5e8c_d036_4141,
+    0xbaae_dce6_af48_a03b,
+    0xffff_ffff_ffff_fffe,
+    0xffff_ffff_ffff_ffff,
+];
+
+/// 2^256 - N in little endian u64 limbs
+const N_COMPLEMENT: [u64; 3] = [0x402d_a173_2fc9_bebf, 0x4551_2319_50b7_5fc4, 0x1];
+
+/// N - 2 in big endian for inversion
+const INVERT_EXP: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xba,
+    0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x3f,
+];
+
+/// element of secp256k1 scalar field, little endian u64 limbs always less than N
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scalar([u64; 4]);
+
+impl Scalar {
+    pub const ZERO: Scalar = Scalar([0, 0, 0, 0]);
+    pub const ONE: Scalar = Scalar([1, 0, 0, 0]);
+
+    pub fn from_u64(n: u64) -> Scalar {
+        Scalar([n, 0, 0, 0])
+    }
+
+    /// limbs - N -> (diff, borrow)
+    #[inline]
+    fn sub_n(limbs: &[u64; 4]) -> ([u64; 4], u64) {
+        let mut out = [0u64; 4];
+        let mut borrow = 0;
+        for (o, (a, n)) in out.iter_mut().zip(limbs.iter().zip(N_LIMBS.iter())) {
+            let (d, b) = sbb(*a, *n, borrow);
+            *o = d;
+            borrow = b;
+        }
+        (out, borrow)
+    }
+
+    /// reduce `limbs + carry * 2^256` (carry is 0 or 1) to less than N
+    #[inline]
+    fn reduce(limbs: [u64; 4], carry: u64) -> Scalar {
+        // limbs + carry * 2^256 < 2N, subtract N at most once
+        let (sub, borrow) = Self::sub_n(&limbs);
+        Scalar(select_limbs(&sub, &limbs, borrow & (carry ^ 1)))
+    }
+
+    /// calc: low + high * N_COMPLEMENT, 2^256 = N_COMPLEMENT mod N
+    #[inline]
+    fn fold(t: &[u64; 8]) -> [u64; 8] {
+        let mut out = [0u64; 8];
+        out[..4].copy_from_slice(&t[..4]);
+        for (i, high) in t[4..].iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, o) in out[i..].iter_mut().enumerate() {
+                let c = N_COMPLEMENT.get(j).copied().unwrap_or(0);
+                let v = *high as u128 * c as u128 + *o as u128 + carry;
+                *o = v as u64;
+                carry = v >> 64;
+            }
+        }
+        out
+    }
+
+    /// big endian 32 bytes, none if not less than N
+    pub fn from_bytes(bytes: &[u8]) -> Option<Scalar> {
+        let limbs = limbs_from_bytes(bytes);
+        let (_, borrow) = Self::sub_n(&limbs);
+        if borrow == 1 {
+            Some(Scalar(limbs))
+        } else {
+            None
+        }
+    }
+
+    /// big endian 32 bytes mod N (bits2octets of RFC6979)
+    pub fn from_bytes_reduced(bytes: &[u8]) -> Scalar {
+        let limbs = limbs_from_bytes(bytes);
+        // 2^256 < 2N, subtract N at most once
+        let (sub, borrow) = Self::sub_n(&limbs);
+        Scalar(select_limbs(&sub, &limbs, borrow))
+    }
+
+    /// big endian 32 bytes
+    pub fn to_bytes(self) -> [u8; 32] {
+        limbs_to_bytes(&self.0)
+    }
+
+    /// N - self if flag is true, without branch
+    pub fn negate_if(&self, flag: bool) -> Scalar {
+        let mut neg = [0u64; 4];
+        let mut borrow = 0;
+        for (o, (n, a)) in neg.iter_mut().zip(N_LIMBS.iter().zip(self.0.iter())) {
+            let (d, b) = sbb(*n, *a, borrow);
+            *o = d;
+            borrow = b;
+        }
+        // zero is kept zero, not N
+        let nonzero = self.0.iter().fold(0, |acc, limb| acc | limb);
+        let nonzero = (nonzero | nonzero.wrapping_neg()) >> 63;
+        let neg = select_limbs(&Scalar::ZERO.0, &neg, nonzero);
+        Scalar(select_limbs(&self.0, &neg, flag as u64))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().fold(0, |acc, limb| acc | limb) == 0
+    }
+
+    /// self^exp, exp is big endian
+    ///
+    /// note: always multiply and select, the time depends only on exp length
+    pub fn pow(&self, exp: &[u8]) -> Scalar {
+        let mut out = Scalar::ONE;
+        for byte in exp.iter() {
+            for i in (0..8).rev() {
+                out = out * out;
+                let mul = out * *self;
+                out = Scalar(select_limbs(&out.0, &mul.0, ((byte >> i) & 1) as u64));
+            }
+        }
+        out
+    }
+
+    /// calc: self^(N-2) = 1 / self, zero is kept zero
+    pub fn invert(&self) -> Scalar {
+        self.pow(&INVERT_EXP)
+    }
+}
+
+impl Add for Scalar {
+    type Output = Scalar;
+
+    fn add(self, other: Scalar) -> Scalar {
+        let mut limbs = [0u64; 4];
+        let mut carry = 0;
+        for (limb, (a, b)) in limbs.iter_mut().zip(self.0.iter().zip(other.0.iter())) {
+            let (s, c) = adc(*a, *b, carry);
+            *limb = s;
+            carry = c;
+        }
+        Scalar::reduce(limbs, carry)
+    }
+}
+
+impl Sub for Scalar {
+    type Output = Scalar;
+
+    fn sub(self, other: Scalar) -> Scalar {
+        self + -other
+    }
+}
+
+impl Mul for Scalar {
+    type Output = Scalar;
+
+    fn mul(self, other: Scalar) -> Scalar {
+        // 512 bits product
+        let mut t = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let v = self.0[i] as u128 * other.0[j] as u128 + t[i + j] as u128 + carry;
+                t[i + j] = v as u64;
+                carry = v >> 64;
+            }
+            t[i + 4] = carry as u64;
+        }
+        // fold high limbs 4 times: < 2^385, < 2^259, < 2^256 + 2^132, < 2^256
+        for _ in 0..4 {
+            t = Scalar::fold(&t);
+        }
+        Scalar::reduce([t[0], t[1], t[2], t[3]], 0)
+    }
+}
+
+impl Neg for Scalar {
+    type Output = Scalar;
+
+    fn neg(self) -> Scalar {
+        self.negate_if(true)
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod scalar_ops {
+    use crate::signature::scalar::*;
+    use crate::signature::utils::N;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn negate() {
+        assert!(Scalar::from_bytes(&N).is_none());
+        assert_eq!(Scalar::from_bytes_reduced(&N), Scalar::ZERO);
+        assert_eq!(-Scalar::ZERO, Scalar::ZERO);
+
+        // N - 1 = -1
+        let mut bytes = N;
+        bytes[31] -= 1;
+        let minus_one = Scalar::from_bytes(&bytes).unwrap();
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        assert_eq!((-minus_one).to_bytes(), one);
+        assert_eq!(minus_one.negate_if(false), minus_one);
+
+        // leading zero bytes are kept (N - k has high zero byte)
+        let k = hex::decode("ffffffffffffffffffffffffffffff00000000000000000000000000000000ff").unwrap();
+        let k = Scalar::from_bytes_reduced(&k);
+        let neg = -k;
+        assert_eq!(neg.to_bytes()[0], 0);
+        assert_eq!(-neg, k);
+        assert_ne!(neg, Scalar::ZERO);
+
+        // 2^256 - 1 mod N
+        let max = Scalar::from_bytes_reduced(&[0xff; 32]);
+        assert_eq!(
+            hex::encode(max.to_bytes()),
+            "000000000000000000000000000000014551231950b75fc4402da1732fc9bebe"
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        let n = BigUint::from_bytes_be(&N);
+        let a_bytes =
+            hex::decode("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140").unwrap();
+        let b_bytes =
+            hex::decode("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798").unwrap();
+        let a = Scalar::from_bytes(&a_bytes).unwrap();
+        let b = Scalar::from_bytes(&b_bytes).unwrap();
+        let big_a = BigUint::from_bytes_be(&a_bytes);
+        let big_b = BigUint::from_bytes_be(&b_bytes);
+        let to_scalar = |big: BigUint| {
+            let vec = big.to_bytes_be();
+            let mut bytes = [0u8; 32];
+            bytes[32 - vec.len()..32].clone_from_slice(&vec);
+            Scalar::from_bytes(&bytes).unwrap()
+        };
+
+        assert_eq!(a + b, to_scalar((&big_a + &big_b) % &n));
+        assert_eq!(b - a, to_scalar((&big_b + &n - &big_a) % &n));
+        assert_eq!(a * b, to_scalar((&big_a * &big_b) % &n));
+        assert_eq!(a * a, Scalar::ONE);
+        assert_eq!(a + Scalar::ONE, Scalar::ZERO);
+        assert_eq!(b.to_bytes().to_vec(), b_bytes);
+
+        // inversion
+        for x in [a, b, a * b, a + b, Scalar::from_u64(3)].iter() {
+            assert_eq!(*x * x.invert(), Scalar::ONE);
+        }
+        assert_eq!(Scalar::ZERO.invert(), Scalar::ZERO);
+        assert_eq!(a.invert(), a);
+    }
+}
@@ -1,7 +1,17 @@
+use crate::signature::scalar::Scalar;
 use crate::signature::utils::*;
-use crate::signature::{POINT, SCALAR};
+use crate::signature::{Signature, POINT, SCALAR};
+use crate::utils::sha256ripemd160;
+use rand::rngs::OsRng;
+use rand::RngCore;
 use secp256k1::{Error, PublicKey, SecretKey};
 use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+type Address = [u8; 21];
+
+/// format version of serialized key generation and share
+static THRESHOLD_VERSION: u8 = 1;
 
 /// Y: shared public key
 #[allow(non_snake_case)]
@@ -30,6 +40,579 @@ pub fn verify_threshold(y: &POINT, v: &POINT, sigma: &SCALAR, message: &[u8]) ->
     Ok(eY_plus_v == sigma_g)
 }
 
+/// random scalar in [1, N)
+fn random_scalar() -> SCALAR {
+    let mut scalar = [0u8; 32];
+    loop {
+        OsRng.fill_bytes(&mut scalar);
+        if SecretKey::from_slice(&scalar).is_ok() {
+            return scalar;
+        }
+    }
+}
+
+fn combine_points(a: Option<PublicKey>, b: &PublicKey) -> Result<Option<PublicKey>, String> {
+    match a {
+        Some(a) => Ok(Some(a.combine(b).map_err(|err| err.to_string())?)),
+        None => Ok(Some(*b)),
+    }
+}
+
+/// bytes to a scalar less than N
+fn from_scalar(slice: &[u8], name: &str) -> Result<Scalar, String> {
+    Scalar::from_bytes(slice).ok_or_else(|| format!("invalid {}", name))
+}
+
+/// nonzero scalar to bytes
+fn into_scalar(scalar: Scalar, name: &str) -> Result<SCALAR, String> {
+    if scalar.is_zero() {
+        Err(format!("{} is zero", name))
+    } else {
+        Ok(scalar.to_bytes())
+    }
+}
+
+/// 32 bytes to a scalar in [1, N)
+fn check_scalar(slice: &[u8], name: &str) -> Result<SCALAR, String> {
+    SecretKey::from_slice(slice).map_err(|err| format!("invalid {}: {}", name, err))?;
+    Ok(to_scalar(slice))
+}
+
+/// 33 bytes to a valid point
+fn check_point(slice: &[u8]) -> Result<POINT, String> {
+    PublicKey::from_slice(slice).map_err(|err| format!("invalid commitment: {}", err))?;
+    Ok(to_point(slice))
+}
+
+/// calc: f(j) = a_0 + a_1 * j + .. + a_t-1 * j^(t-1) mod n (Horner's method)
+fn eval_polynomial(coefficients: &[SCALAR], index: u8) -> Result<SCALAR, String> {
+    let j = Scalar::from_u64(index as u64);
+    let mut sum = Scalar::ZERO;
+    for a in coefficients.iter().rev() {
+        sum = sum * j + from_scalar(a, "coefficient")?;
+    }
+    into_scalar(sum, "share")
+}
+
+/// calc: f(j) * G = C_0 + C_1 * j + .. + C_t-1 * j^(t-1) (Horner's method)
+fn eval_commitments(commitments: &[POINT], index: u8) -> Result<PublicKey, String> {
+    let j = Scalar::from_u64(index as u64).to_bytes();
+    let mut sum: Option<PublicKey> = None;
+    for c in commitments.iter().rev() {
+        if let Some(sum) = sum.as_mut() {
+            raw_scalar_mul(sum, &j).map_err(|err| err.to_string())?;
+        }
+        let point = PublicKey::from_slice(c).map_err(|err| err.to_string())?;
+        sum = combine_points(sum, &point)?;
+    }
+    sum.ok_or_else(|| "no commitment".to_owned())
+}
+
+/// calc: lambda_j = Π k / (k - j) mod n for k in signers except j
+fn get_lagrange_coefficient(signers: &[u8], index: u8) -> Scalar {
+    let j = Scalar::from_u64(index as u64);
+    let mut num = Scalar::ONE;
+    let mut den = Scalar::ONE;
+    for &k in signers.iter().filter(|&&k| k != index) {
+        let k = Scalar::from_u64(k as u64);
+        num = num * k;
+        den = den * (k - j);
+    }
+    num * den.invert()
+}
+
+fn check_members(threshold: u8, index: u8, members: &[u8]) -> Result<Vec<u8>, String> {
+    let mut sorted = members.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    if sorted.len() != members.len() {
+        Err("duplicate member found".to_owned())
+    } else if sorted.contains(&0) {
+        Err("member index 0 is reserved for the shared secret".to_owned())
+    } else if threshold == 0 || members.len() < threshold as usize {
+        Err(format!("threshold is 1 to {} but {}", members.len(), threshold))
+    } else if !sorted.contains(&index) {
+        Err(format!("my index {} isn't in members", index))
+    } else {
+        Ok(sorted)
+    }
+}
+
+/// one member's state of distributed key generation (Pedersen DKG with Feldman VSS)
+///
+/// each member deals a random polynomial of degree `threshold - 1`,
+/// broadcasts the commitments and sends `f_i(j)` to member j privately
+/// note: a nonce generation among signers isn't serializable for no nonce reuse
+pub struct ThresholdKeyGen {
+    threshold: u8,
+    index: u8,
+    members: Vec<u8>,
+    /// generate a one-time nonce share, not a key share
+    is_nonce: bool,
+    coefficients: Vec<SCALAR>,
+    commitments: Vec<Option<Vec<POINT>>>,
+    shares: Vec<Option<SCALAR>>,
+}
+
+impl Drop for ThresholdKeyGen {
+    fn drop(&mut self) {
+        self.coefficients.iter_mut().for_each(|a| a.zeroize());
+        self.shares.iter_mut().flatten().for_each(|s| s.zeroize());
+    }
+}
+
+impl ThresholdKeyGen {
+    pub fn new(threshold: u8, index: u8, members: &[u8]) -> Result<Self, String> {
+        Self::generate(threshold, index, members, false)
+    }
+
+    /// run again among signers to generate a one-time nonce share
+    pub fn new_nonce(threshold: u8, index: u8, members: &[u8]) -> Result<Self, String> {
+        Self::generate(threshold, index, members, true)
+    }
+
+    fn generate(threshold: u8, index: u8, members: &[u8], is_nonce: bool) -> Result<Self, String> {
+        let members = check_members(threshold, index, members)?;
+        let coefficients = (0..threshold).map(|_| random_scalar()).collect::<Vec<SCALAR>>();
+
+        // calc: C_k = a_k * G
+        let mut own_commitments = Vec::with_capacity(coefficients.len());
+        for a in coefficients.iter() {
+            let mut point = generator();
+            raw_scalar_mul(&mut point, a).map_err(|err| err.to_string())?;
+            own_commitments.push(point.serialize());
+        }
+        let own_share = eval_polynomial(&coefficients, index)?;
+
+        let position = members.iter().position(|&j| j == index).unwrap();
+        let mut commitments = vec![None; members.len()];
+        let mut shares = vec![None; members.len()];
+        commitments[position] = Some(own_commitments);
+        shares[position] = Some(own_share);
+        Ok(ThresholdKeyGen {
+            threshold,
+            index,
+            members,
+            is_nonce,
+            coefficients,
+            commitments,
+            shares,
+        })
+    }
+
+    pub fn get_threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn get_index(&self) -> u8 {
+        self.index
+    }
+
+    pub fn get_members(&self) -> &Vec<u8> {
+        &self.members
+    }
+
+    pub fn is_nonce(&self) -> bool {
+        self.is_nonce
+    }
+
+    fn get_position(&self, index: u8) -> Result<usize, String> {
+        self.members
+            .iter()
+            .position(|&j| j == index)
+            .ok_or_else(|| format!("unknown member index {}", index))
+    }
+
+    /// broadcast my commitments to all members
+    pub fn get_commitments(&self) -> Vec<POINT> {
+        let position = self.get_position(self.index).unwrap();
+        self.commitments[position].clone().unwrap()
+    }
+
+    /// send `f_i(j)` to member j by a secure channel
+    pub fn get_share(&self, to: u8) -> Result<SCALAR, String> {
+        self.get_position(to)?;
+        eval_polynomial(&self.coefficients, to)
+    }
+
+    pub fn add_commitments(&mut self, from: u8, commitments: &[POINT]) -> Result<(), String> {
+        let position = self.get_position(from)?;
+        if commitments.len() != self.threshold as usize {
+            return Err(format!("commitments is {} points", self.threshold));
+        }
+        for c in commitments.iter() {
+            check_point(c)?;
+        }
+        match self.commitments[position] {
+            Some(ref _commitments) => {
+                if _commitments
+                    .iter()
+                    .zip(commitments.iter())
+                    .all(|(a, b)| slice_eq(a, b))
+                {
+                    Ok(())
+                } else {
+                    Err("conflict with received commitments".to_owned())
+                }
+            },
+            None => {
+                self.commitments[position] = Some(commitments.to_vec());
+                Ok(())
+            },
+        }
+    }
+
+    /// receive `f_i(my index)` and verify it by the dealer's commitments
+    pub fn add_share(&mut self, from: u8, share: &SCALAR) -> Result<(), String> {
+        let position = self.get_position(from)?;
+        let commitments = self.commitments[position]
+            .as_ref()
+            .ok_or("the dealer's commitments isn't received")?;
+
+        // check: s * G = C_0 + C_1 * j + ..
+        let mut point = generator();
+        check_scalar(share, "share")?;
+        raw_scalar_mul(&mut point, share).map_err(|err| format!("invalid share: {}", err))?;
+        if point != eval_commitments(commitments, self.index)? {
+            return Err(format!("share from {} doesn't match the commitments", from));
+        }
+        self.shares[position] = Some(*share);
+        Ok(())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.shares.iter().all(|share| share.is_some())
+    }
+
+    /// calc: x_j = Σ f_i(j) and C_k = Σ C_ik
+    pub fn finish(&self) -> Result<ThresholdShare, String> {
+        let mut secret = Scalar::ZERO;
+        for share in self.shares.iter() {
+            let share = share.as_ref().ok_or("wait for all shares")?;
+            secret = secret + from_scalar(share, "share")?;
+        }
+        let mut commitments = Vec::with_capacity(self.threshold as usize);
+        for k in 0..self.threshold as usize {
+            let mut sum: Option<PublicKey> = None;
+            for _commitments in self.commitments.iter() {
+                let c = _commitments.as_ref().ok_or("wait for all commitments")?;
+                let point = PublicKey::from_slice(&c[k]).map_err(|err| err.to_string())?;
+                sum = combine_points(sum, &point)?;
+            }
+            commitments.push(sum.unwrap().serialize());
+        }
+        Ok(ThresholdShare {
+            threshold: self.threshold,
+            index: self.index,
+            members: self.members.clone(),
+            is_nonce: self.is_nonce,
+            secret: Some(into_scalar(secret, "secret")?),
+            commitments,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        // [ver u8][threshold u8][index u8][size u8][members]
+        // [coefficients] [flag + commitments, flag + share] * size
+        if self.is_nonce {
+            return Err("nonce generation isn't serializable".to_owned());
+        }
+        let t = self.threshold as usize;
+        let size = self.members.len();
+        let mut vec = Vec::with_capacity(4 + size + 32 * t + (2 + 33 * t + 32) * size);
+        vec.extend_from_slice(&[THRESHOLD_VERSION, self.threshold, self.index, size as u8]);
+        vec.extend_from_slice(&self.members);
+        self.coefficients.iter().for_each(|a| vec.extend_from_slice(a));
+        for i in 0..size {
+            let commitments = self.commitments[i].as_ref().map(|c| c.concat());
+            write_option(&mut vec, commitments.as_deref());
+            write_option(&mut vec, self.shares[i].as_ref().map(|s| &s[..]));
+        }
+        Ok(vec)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut pos = 0usize;
+        let header = read_slice(bytes, &mut pos, 4)?;
+        if header[0] != THRESHOLD_VERSION {
+            return Err(format!("unknown key generation version {}", header[0]));
+        }
+        let (threshold, index, size) = (header[1], header[2], header[3] as usize);
+        let members = read_slice(bytes, &mut pos, size)?.to_vec();
+        if check_members(threshold, index, &members)? != members {
+            return Err("members isn't sorted".to_owned());
+        }
+        let mut coefficients = Vec::with_capacity(threshold as usize);
+        for _ in 0..threshold {
+            coefficients.push(check_scalar(read_slice(bytes, &mut pos, 32)?, "coefficient")?);
+        }
+        let mut commitments = Vec::with_capacity(size);
+        let mut shares = Vec::with_capacity(size);
+        for _ in 0..size {
+            let c = read_option(bytes, &mut pos, 33 * threshold as usize)?;
+            let c = c.map(|c| {
+                c.chunks(33)
+                    .map(check_point)
+                    .collect::<Result<Vec<POINT>, String>>()
+            });
+            commitments.push(c.transpose()?);
+            shares.push(match read_option(bytes, &mut pos, 32)? {
+                Some(share) => Some(check_scalar(share, "share")?),
+                None => None,
+            });
+        }
+        if pos != bytes.len() {
+            return Err(format!("not all bytes used {}/{}", pos, bytes.len()));
+        }
+
+        // check: my commitments and share are generated by the coefficients
+        let position = members.iter().position(|&j| j == index).unwrap();
+        let keygen = ThresholdKeyGen {
+            threshold,
+            index,
+            members,
+            is_nonce: false,
+            coefficients,
+            commitments,
+            shares,
+        };
+        let own_commitments = keygen.commitments[position]
+            .as_ref()
+            .ok_or("my commitments isn't found")?;
+        for (a, c) in keygen.coefficients.iter().zip(own_commitments.iter()) {
+            let mut point = generator();
+            raw_scalar_mul(&mut point, a).map_err(|err| err.to_string())?;
+            if !slice_eq(&point.serialize(), c) {
+                return Err("my commitments doesn't match the coefficients".to_owned());
+            }
+        }
+        match keygen.shares[position] {
+            Some(ref share) if *share == keygen.get_share(index)? => Ok(keygen),
+            Some(_) => Err("my share doesn't match the coefficients".to_owned()),
+            None => Err("my share isn't found".to_owned()),
+        }
+    }
+}
+
+/// a member's share of the shared key (or one-time nonce)
+pub struct ThresholdShare {
+    threshold: u8,
+    index: u8,
+    members: Vec<u8>,
+    /// one-time nonce share, the secret isn't serialized
+    is_nonce: bool,
+    /// none if public only or the nonce is used
+    secret: Option<SCALAR>,
+    /// C_0 is the shared public key
+    commitments: Vec<POINT>,
+}
+
+impl Drop for ThresholdShare {
+    fn drop(&mut self) {
+        if let Some(secret) = self.secret.as_mut() {
+            secret.zeroize();
+        }
+    }
+}
+
+impl ThresholdShare {
+    pub fn get_threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn get_index(&self) -> u8 {
+        self.index
+    }
+
+    pub fn get_members(&self) -> &Vec<u8> {
+        &self.members
+    }
+
+    pub fn is_nonce(&self) -> bool {
+        self.is_nonce
+    }
+
+    pub fn has_secret(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    /// shared public key Y (or nonce V)
+    pub fn get_public_key(&self) -> &POINT {
+        &self.commitments[0]
+    }
+
+    pub fn get_address(&self, ver: u8) -> Address {
+        sha256ripemd160(ver, &self.commitments[0])
+    }
+
+    /// calc: Y_j = x_j * G by the commitments
+    pub fn get_public_share(&self, index: u8) -> Result<POINT, String> {
+        if !self.members.contains(&index) {
+            return Err(format!("unknown member index {}", index));
+        }
+        Ok(eval_commitments(&self.commitments, index)?.serialize())
+    }
+
+    /// share without the secret for a combiner
+    pub fn to_public(&self) -> Self {
+        ThresholdShare {
+            threshold: self.threshold,
+            index: self.index,
+            members: self.members.clone(),
+            is_nonce: self.is_nonce,
+            secret: None,
+            commitments: self.commitments.clone(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // [ver u8][threshold u8][index u8][size u8][members][is_nonce u8][flag + secret][commitments]
+        // note: nonce's secret is never written for no replay by restored copies
+        let size = self.members.len();
+        let mut vec = Vec::with_capacity(4 + size + 1 + 33 + 33 * self.commitments.len());
+        vec.extend_from_slice(&[THRESHOLD_VERSION, self.threshold, self.index, size as u8]);
+        vec.extend_from_slice(&self.members);
+        vec.push(self.is_nonce as u8);
+        let secret = if self.is_nonce { None } else { self.secret.as_ref() };
+        write_option(&mut vec, secret.map(|s| &s[..]));
+        self.commitments.iter().for_each(|c| vec.extend_from_slice(c));
+        vec
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut pos = 0usize;
+        let header = read_slice(bytes, &mut pos, 4)?;
+        if header[0] != THRESHOLD_VERSION {
+            return Err(format!("unknown share version {}", header[0]));
+        }
+        let (threshold, index, size) = (header[1], header[2], header[3] as usize);
+        let members = read_slice(bytes, &mut pos, size)?.to_vec();
+        if check_members(threshold, index, &members)? != members {
+            return Err("members isn't sorted".to_owned());
+        }
+        let is_nonce = match read_slice(bytes, &mut pos, 1)?[0] {
+            0 => false,
+            1 => true,
+            flag => return Err(format!("unknown nonce flag {}", flag)),
+        };
+        let secret = match read_option(bytes, &mut pos, 32)? {
+            Some(_) if is_nonce => return Err("nonce's secret is found".to_owned()),
+            Some(secret) => Some(check_scalar(secret, "secret")?),
+            None => None,
+        };
+        let mut commitments = Vec::with_capacity(threshold as usize);
+        for _ in 0..threshold {
+            commitments.push(check_point(read_slice(bytes, &mut pos, 33)?)?);
+        }
+        if pos != bytes.len() {
+            return Err(format!("not all bytes used {}/{}", pos, bytes.len()));
+        }
+        Ok(ThresholdShare {
+            threshold,
+            index,
+            members,
+            is_nonce,
+            secret,
+            commitments,
+        })
+    }
+}
+
+/// calc: e = int(hash(V || Y || m)) mod n
+fn get_challenge(y: &POINT, v: &POINT, message: &[u8]) -> Result<Scalar, String> {
+    let mut vec = Vec::with_capacity(33 + 33 + message.len());
+    vec.extend_from_slice(v);
+    vec.extend_from_slice(y);
+    vec.extend_from_slice(message);
+    from_scalar(&Sha256::digest(&vec), "challenge")
+}
+
+fn check_nonce(key: &ThresholdShare, nonce: &ThresholdShare) -> Result<(), String> {
+    if key.is_nonce || !nonce.is_nonce {
+        Err("sign by a key share and a nonce share".to_owned())
+    } else if key.threshold != nonce.threshold {
+        Err("nonce's threshold is different from key's".to_owned())
+    } else if key.index != nonce.index {
+        Err("nonce's index is different from key's".to_owned())
+    } else if !nonce.members.iter().all(|j| key.members.contains(j)) {
+        Err("nonce's members isn't key's members".to_owned())
+    } else {
+        Ok(())
+    }
+}
+
+/// calc: gamma_j = v_j + e * x_j mod n, the nonce is consumed
+pub fn sign_threshold_partial(
+    key: &ThresholdShare,
+    nonce: &mut ThresholdShare,
+    message: &[u8],
+) -> Result<SCALAR, String> {
+    check_nonce(key, nonce)?;
+    let x = from_scalar(key.secret.as_ref().ok_or("key share has no secret")?, "key share")?;
+    let e = get_challenge(key.get_public_key(), nonce.get_public_key(), message)?;
+    let mut v = nonce.secret.take().ok_or("nonce is already used")?;
+    let gamma = from_scalar(&v, "nonce").map(|v| v + e * x);
+    v.zeroize();
+    into_scalar(gamma?, "partial signature")
+}
+
+/// check: gamma_j * G = V_j + e * Y_j
+pub fn verify_threshold_partial(
+    key: &ThresholdShare,
+    nonce: &ThresholdShare,
+    index: u8,
+    gamma: &SCALAR,
+    message: &[u8],
+) -> Result<bool, String> {
+    if !nonce.members.contains(&index) {
+        return Err(format!("{} isn't a signer", index));
+    }
+    let e = get_challenge(key.get_public_key(), nonce.get_public_key(), message)?;
+    let mut gamma_g = generator();
+    raw_scalar_mul(&mut gamma_g, gamma).map_err(|err| format!("invalid partial signature: {}", err))?;
+    let mut e_y = eval_commitments(&key.commitments, index)?;
+    raw_scalar_mul(&mut e_y, &e.to_bytes()).map_err(|err| err.to_string())?;
+    let v = eval_commitments(&nonce.commitments, index)?;
+    Ok(e_y.combine(&v).map_err(|err| err.to_string())? == gamma_g)
+}
+
+/// calc: sigma = Σ lambda_j * gamma_j by `threshold` verified partial signatures
+pub fn combine_threshold(
+    key: &ThresholdShare,
+    nonce: &ThresholdShare,
+    partials: &[(u8, SCALAR)],
+    message: &[u8],
+) -> Result<Signature, String> {
+    let mut signers: Vec<u8> = Vec::with_capacity(key.threshold as usize);
+    let mut gammas: Vec<&SCALAR> = Vec::with_capacity(key.threshold as usize);
+    for (index, gamma) in partials.iter() {
+        if signers.len() == key.threshold as usize {
+            break;
+        }
+        if signers.contains(index) {
+            continue;
+        }
+        if !verify_threshold_partial(key, nonce, *index, gamma, message)? {
+            return Err(format!("invalid partial signature from {}", index));
+        }
+        signers.push(*index);
+        gammas.push(gamma);
+    }
+    if signers.len() < key.threshold as usize {
+        return Err(format!(
+            "need {} partial signatures but {}",
+            key.threshold,
+            signers.len()
+        ));
+    }
+    let mut sigma = Scalar::ZERO;
+    for (&index, gamma) in signers.iter().zip(gammas.iter()) {
+        sigma = sigma + get_lagrange_coefficient(&signers, index) * from_scalar(*gamma, "partial signature")?;
+    }
+    Signature::new_threshold_sig(key.get_public_key(), nonce.get_public_key(), &sigma.to_bytes())
+        .map_err(|_| "failed to generate threshold signature".to_owned())
+}
+
 #[cfg(test)]
 mod threshold_verify {
     use crate::signature::{verify_signature, Signature};
@@ -46,3 +629,117 @@ mod threshold_verify {
         assert_eq!(verify_signature(&signature, &msg), Ok(true));
     }
 }
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod threshold_sign {
+    use crate::signature::threshold::*;
+    use crate::signature::verify_signature;
+
+    /// run key (or nonce) generation among members and return each member's share
+    fn generate(threshold: u8, members: &[u8], is_nonce: bool) -> Vec<ThresholdShare> {
+        let mut rounds = members
+            .iter()
+            .map(|&j| {
+                if is_nonce {
+                    ThresholdKeyGen::new_nonce(threshold, j, members).unwrap()
+                } else {
+                    ThresholdKeyGen::new(threshold, j, members).unwrap()
+                }
+            })
+            .collect::<Vec<ThresholdKeyGen>>();
+        for i in 0..rounds.len() {
+            let commitments = rounds[i].get_commitments();
+            let shares = members
+                .iter()
+                .map(|&j| rounds[i].get_share(j).unwrap())
+                .collect::<Vec<_>>();
+            for (round, share) in rounds.iter_mut().zip(shares.iter()) {
+                round.add_commitments(members[i], &commitments).unwrap();
+                round.add_share(members[i], share).unwrap();
+            }
+        }
+        // serialize between rounds
+        if is_nonce {
+            assert!(rounds[0].to_bytes().is_err());
+        } else {
+            let bytes = rounds[0].to_bytes().unwrap();
+            rounds[0] = ThresholdKeyGen::from_bytes(&bytes).unwrap();
+            assert_eq!(rounds[0].to_bytes().unwrap(), bytes);
+        }
+        rounds.iter().map(|round| round.finish().unwrap()).collect()
+    }
+
+    #[test]
+    fn sign_2_of_3() {
+        let keys = generate(2, &[1, 2, 3], false);
+        let y = *keys[0].get_public_key();
+        assert!(keys.iter().all(|key| key.get_public_key()[..] == y[..]));
+        let bytes = keys[2].to_bytes();
+        let key3 = ThresholdShare::from_bytes(&bytes).unwrap();
+        assert_eq!(key3.to_bytes(), bytes);
+
+        // signers are 1 and 3
+        let msg = b"threshold test message";
+        let mut nonces = generate(2, &[1, 3], true);
+        assert!(sign_threshold_partial(&nonces[0], &mut keys[0].to_public(), msg).is_err());
+        let restored = ThresholdShare::from_bytes(&nonces[0].to_bytes()).unwrap();
+        assert!(restored.is_nonce() && !restored.has_secret());
+        let gamma1 = sign_threshold_partial(&keys[0], &mut nonces[0], msg).unwrap();
+        let gamma3 = sign_threshold_partial(&key3, &mut nonces[1], msg).unwrap();
+        assert!(sign_threshold_partial(&keys[0], &mut nonces[0], msg).is_err());
+        assert_eq!(
+            verify_threshold_partial(&keys[1], &nonces[0], 3, &gamma1, msg),
+            Ok(false)
+        );
+
+        // combine by a member without secret
+        let combiner = keys[1].to_public();
+        assert!(combine_threshold(&combiner, &nonces[0], &[(1, gamma1)], msg).is_err());
+        let partials = [(3, gamma3), (1, gamma1)];
+        let signature = combine_threshold(&combiner, &nonces[0], &partials, msg).unwrap();
+        assert_eq!(verify_signature(&signature, msg), Ok(true));
+        assert_eq!(signature.get_address(0), keys[0].get_address(0));
+    }
+
+    #[test]
+    fn restore_key_generation() {
+        let mut keygen = ThresholdKeyGen::new(2, 1, &[1, 2, 3]).unwrap();
+        let other = ThresholdKeyGen::new(2, 2, &[1, 2, 3]).unwrap();
+        keygen.add_commitments(2, &other.get_commitments()).unwrap();
+        keygen.add_share(2, &other.get_share(1).unwrap()).unwrap();
+        let bytes = keygen.to_bytes().unwrap();
+        assert!(ThresholdKeyGen::from_bytes(&bytes).is_ok());
+
+        // [header 4][members 3][coefficients 32 * 2][flag + commitments 1 + 66, flag + share 1 + 32] * 3
+        let commitment_pos = 4 + 3 + 64 + 1;
+        let share_pos = commitment_pos + 66 + 1;
+        let coefficient_pos = 4 + 3;
+
+        // my commitments and share are required
+        let mut broken = bytes.clone();
+        broken[share_pos - 1] = 0;
+        broken.drain(share_pos..share_pos + 32);
+        assert!(ThresholdKeyGen::from_bytes(&broken).is_err());
+        let mut broken = bytes.clone();
+        broken[commitment_pos - 1] = 0;
+        broken.drain(commitment_pos..commitment_pos + 66);
+        assert!(ThresholdKeyGen::from_bytes(&broken).is_err());
+
+        // commitment is a point, share and coefficient are less than N
+        let mut broken = bytes.clone();
+        broken[commitment_pos] = 5;
+        assert!(ThresholdKeyGen::from_bytes(&broken).is_err());
+        let mut broken = bytes.clone();
+        broken[share_pos..share_pos + 32].copy_from_slice(&[255u8; 32]);
+        assert!(ThresholdKeyGen::from_bytes(&broken).is_err());
+        let mut broken = bytes.clone();
+        broken[coefficient_pos..coefficient_pos + 32].copy_from_slice(&[255u8; 32]);
+        assert!(ThresholdKeyGen::from_bytes(&broken).is_err());
+
+        // share doesn't match my coefficients
+        let mut broken = bytes;
+        broken[share_pos + 31] ^= 1;
+        assert!(ThresholdKeyGen::from_bytes(&broken).is_err());
+    }
+}
@@ -1,10 +1,11 @@
-use crate::signature::SCALAR;
-use num_bigint::BigUint;
-use num_traits::identities::One;
+use crate::signature::field::Fe;
+use crate::signature::scalar::Scalar;
+use crate::signature::{POINT, SCALAR};
 use secp256k1::constants::{GENERATOR_X, GENERATOR_Y};
 use secp256k1::{Error, PublicKey, Secp256k1, SecretKey, VerifyOnly};
-use std::ops::{Add, Div, Sub};
+use sha2::{Digest, Sha256};
 use std::sync::Once;
+use zeroize::Zeroize;
 
 static mut CONTEXT: Option<Secp256k1<VerifyOnly>> = None;
 
@@ -47,30 +48,17 @@ pub fn raw_scalar_mul(point: &mut PublicKey, fe: &[u8]) -> Result<(), Error> {
 
 /// POINT - POINT
 pub fn sub_point(point: &PublicKey, other: &PublicKey) -> Result<PublicKey, Error> {
-    let p = BigUint::from_bytes_be(&P);
-    let serialized_pk = PublicKey::serialize_uncompressed(other);
-    let x = BigUint::from_bytes_be(&serialized_pk[1..1 + 32]);
-    let y = BigUint::from_bytes_be(&serialized_pk[33..33 + 32]);
-    let minus_y = if p < y { y.sub(&p) } else { p.sub(&y) };
-
-    let mut tmp = [0u8; 1 + 32 + 32];
-    tmp[0] = 4;
-
-    let x_vec = x.to_bytes_be();
-    (&mut tmp[1 + 32 - x_vec.len()..1 + 32]).clone_from_slice(x_vec.as_slice());
-
-    let y_vec = minus_y.to_bytes_be();
-    (&mut tmp[1 + 64 - y_vec.len()..1 + 64]).clone_from_slice(y_vec.as_slice());
-
-    let minus_point = PublicKey::from_slice(&tmp)?;
+    let mut serialized_pk = PublicKey::serialize_uncompressed(other);
+    let y = Fe::from_bytes(&serialized_pk[33..33 + 32]).ok_or(Error::InvalidPublicKey)?;
+    serialized_pk[33..33 + 32].clone_from_slice(&(-y).to_bytes());
+    let minus_point = PublicKey::from_slice(&serialized_pk)?;
     point.combine(&minus_point)
 }
 
 /// invert secret key (N - sk)
 pub fn inv_scalar(secret: &SecretKey) -> Result<SecretKey, Error> {
-    let sk = BigUint::from_bytes_be(&secret[..]);
-    let n = BigUint::from_bytes_be(N.as_ref());
-    SecretKey::from_slice(&n.sub(sk).to_bytes_be())
+    let sk = Scalar::from_bytes(&secret[..]).ok_or(Error::InvalidSecretKey)?;
+    SecretKey::from_slice(&(-sk).to_bytes())
 }
 
 /// https://github.com/sipa/bips/blob/bip-schnorr/bip-schnorr.mediawiki#verification
@@ -79,26 +67,72 @@ pub fn inv_scalar(secret: &SecretKey) -> Result<SecretKey, Error> {
 ///     Let y = c^(p+1)/4 mod p
 ///     Fail if c ≠ y^2 mod p
 pub fn has_square_y(r: &SCALAR) -> bool {
-    let r = BigUint::from_bytes_be(r);
-    let p = BigUint::from_bytes_be(&P);
-    let mut c = r.modpow(&BigUint::from(3u32), &p).add(7u32);
-    if p < c {
-        c = c.div(&p); // for adding 7
+    match Fe::from_bytes(r) {
+        Some(r) => (r.square() * r + Fe::from_u64(7)).sqrt().is_some(),
+        None => false,
     }
-    let e = p.clone().add(1u32).div(4u32);
-    let y = c.clone().modpow(&e, &p);
-    let out = y.modpow(&BigUint::from(2u32), &p);
-    out == c
 }
 
 /// Jacobian Coordinates
-///    let j = x^(p-1)/2 mod p
+///    let j = y^(p-1)/2 mod p
 ///    fail if j ≠ 1
 pub fn is_jacobi(point: &PublicKey) -> bool {
-    let y = BigUint::from_bytes_be(&point.serialize_uncompressed()[33..33 + 32]);
-    let p = BigUint::from_bytes_be(&P);
-    let j = y.modpow(&p.clone().sub(1u32).div(2u32), &p);
-    j.is_one()
+    match Fe::from_bytes(&point.serialize_uncompressed()[33..33 + 32]) {
+        Some(y) => y.is_square(),
+        None => false,
+    }
+}
+
+/// HMAC-SHA256 of concatenated data
+pub fn hmac_sha256(key: &[u8], data: &[&[u8]]) -> SCALAR {
+    let mut block = [0u8; 64];
+    if 64 < key.len() {
+        block[..32].clone_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].clone_from_slice(key);
+    }
+    let mut pad = [0u8; 64];
+    for (pad, b) in pad.iter_mut().zip(block.iter()) {
+        *pad = b ^ 0x36;
+    }
+    let mut inner = Sha256::new();
+    inner.input(&pad[..]);
+    for item in data.iter() {
+        inner.input(item);
+    }
+    for (pad, b) in pad.iter_mut().zip(block.iter()) {
+        *pad = b ^ 0x5c;
+    }
+    let mut outer = Sha256::new();
+    outer.input(&pad[..]);
+    outer.input(inner.result());
+    block.zeroize();
+    pad.zeroize();
+    to_scalar(outer.result().as_slice())
+}
+
+/// https://tools.ietf.org/html/rfc6979#section-3.2
+/// deterministic nonce by HMAC-DRBG, `aux` is additional data k' (section 3.6)
+pub fn rfc6979_nonce(sk: &SCALAR, message: &[u8], aux: &[u8]) -> SecretKey {
+    // calc: h1 = bits2octets(hash(m))
+    let h1 = Scalar::from_bytes_reduced(&Sha256::digest(message)).to_bytes();
+    let mut v = [1u8; 32];
+    let mut k = [0u8; 32];
+    k = hmac_sha256(&k, &[&v, &[0], sk, &h1, aux]);
+    v = hmac_sha256(&k, &[&v]);
+    k = hmac_sha256(&k, &[&v, &[1], sk, &h1, aux]);
+    v = hmac_sha256(&k, &[&v]);
+    loop {
+        v = hmac_sha256(&k, &[&v]);
+        if let Ok(nonce) = SecretKey::from_slice(&v) {
+            k.zeroize();
+            v.zeroize();
+            return nonce;
+        }
+        // out of range, retry
+        k = hmac_sha256(&k, &[&v, &[0]]);
+        v = hmac_sha256(&k, &[&v]);
+    }
 }
 
 #[inline]
@@ -106,3 +140,45 @@ pub fn slice_eq(a: &[u8], b: &[u8]) -> bool {
     assert_eq!(a.len(), b.len());
     a.iter().zip(b.iter()).all(|(a, b)| a == b)
 }
+
+/// copy 33 bytes slice to POINT
+pub fn to_point(slice: &[u8]) -> POINT {
+    let mut point = [0u8; 33];
+    point.clone_from_slice(slice);
+    point
+}
+
+/// copy 32 bytes slice to SCALAR
+pub fn to_scalar(slice: &[u8]) -> SCALAR {
+    let mut scalar = [0u8; 32];
+    scalar.clone_from_slice(slice);
+    scalar
+}
+
+/// option item with 1 byte flag
+pub fn write_option(vec: &mut Vec<u8>, item: Option<&[u8]>) {
+    match item {
+        Some(item) => {
+            vec.push(1);
+            vec.extend_from_slice(item);
+        },
+        None => vec.push(0),
+    }
+}
+
+pub fn read_slice<'a>(bytes: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8], String> {
+    if bytes.len() < *pos + len {
+        return Err(format!("too short bytes {} < {}", bytes.len(), *pos + len));
+    }
+    let slice = &bytes[*pos..*pos + len];
+    *pos += len;
+    Ok(slice)
+}
+
+pub fn read_option<'a>(bytes: &'a [u8], pos: &mut usize, len: usize) -> Result<Option<&'a [u8]>, String> {
+    match read_slice(bytes, pos, 1)?[0] {
+        0 => Ok(None),
+        1 => Ok(Some(read_slice(bytes, pos, len)?)),
+        flag => Err(format!("unknown option flag {}", flag)),
+    }
+}
@@ -0,0 +1,53 @@
+use crate::tx::TxOutput;
+
+type Address = [u8; 21];
+
+/// address version of fund-lock output (normal address is version 0)
+pub const LOCK_ADDR_VER: u8 = 1;
+
+/// lock output can be staked by FlkPos after the height passed
+pub const LOCK_MATURE_HEIGHT: u32 = 20;
+
+/// lock output can be unlocked (used by normal tx) after the height passed
+pub const LOCK_UNLOCK_HEIGHT: u32 = 10080;
+
+/// fund-lock output is a output to lock version address
+///
+/// note: lock address have same identifier with owner's address
+pub fn is_lock_output(output: &TxOutput) -> bool {
+    output.0[0] == LOCK_ADDR_VER
+}
+
+/// convert owner's address to lock address
+pub fn to_lock_addr(addr: &Address) -> Address {
+    let mut lock = addr.clone();
+    lock[0] = LOCK_ADDR_VER;
+    lock
+}
+
+/// get the address required to sign for the output's address
+pub fn get_owner_addr(addr: &Address) -> Address {
+    let mut owner = addr.clone();
+    if owner[0] == LOCK_ADDR_VER {
+        owner[0] = 0;
+    }
+    owner
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod lock {
+    use crate::tx::*;
+
+    #[test]
+    fn lock_addr() {
+        let owner = [0u8, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5];
+        let lock = to_lock_addr(&owner);
+        assert_eq!(lock[0], LOCK_ADDR_VER);
+        assert_eq!(lock[1..21], owner[1..21]);
+        assert!(is_lock_output(&TxOutput(lock, 0, 100)));
+        assert!(!is_lock_output(&TxOutput(owner, 0, 100)));
+        assert_eq!(get_owner_addr(&lock), owner);
+        assert_eq!(get_owner_addr(&owner), owner);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::utils::*;
+use std::fmt;
+
+type Address = [u8; 21];
+
+/// coin params defined by the issuer
+#[derive(Clone, PartialEq, Debug)]
+pub struct MintParams {
+    /// owner of the coin, mint tx require the signature
+    pub owner: Address,
+    pub name: String,
+    pub unit: String,
+    pub digit: u8,
+    pub description: String,
+}
+
+/// coin setting flags, once disabled cannot enable again
+#[derive(Clone, PartialEq, Debug)]
+pub struct MintSetting {
+    /// allow additional issue after
+    pub additional_issue: bool,
+    /// allow params change after
+    pub change_params: bool,
+}
+
+impl MintSetting {
+    fn to_int(&self) -> u8 {
+        (self.additional_issue as u8) | ((self.change_params as u8) << 1)
+    }
+
+    fn from_int(int: u8) -> Result<Self, String> {
+        if 0b11 < int {
+            return Err(format!("unknown mint setting flag {}", int));
+        }
+        Ok(MintSetting {
+            additional_issue: int & 0b01 != 0,
+            change_params: int & 0b10 != 0,
+        })
+    }
+}
+
+/// mint tx's message (TxMessage::Byte)
+#[derive(Clone, PartialEq)]
+pub struct MintCoin {
+    pub coin_id: u32,
+    /// params update (required at first mint)
+    pub params: Option<MintParams>,
+    /// setting update (required at first mint)
+    pub setting: Option<MintSetting>,
+}
+
+impl fmt::Debug for MintCoin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entry(&"coin_id", &self.coin_id)
+            .entry(&"params", &self.params)
+            .entry(&"setting", &self.setting)
+            .finish()
+    }
+}
+
+impl MintCoin {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        // [coin_id u32][params flag u8]([owner 21b][digit u8][name ?][unit ?][description ?])
+        // [setting flag u8]([setting u8])
+        // note: string is [length u16][utf8 ?]
+        let mut vec = Vec::with_capacity(4 + 1 + 21 + 1 + 2 * 3 + 1 + 1);
+        vec.extend_from_slice(&u32_to_bytes(self.coin_id));
+        match self.params.as_ref() {
+            Some(params) => {
+                vec.push(1);
+                vec.extend_from_slice(&params.owner);
+                vec.push(params.digit);
+                for string in &[&params.name, &params.unit, &params.description] {
+                    if 0xffff < string.len() {
+                        return Err(format!("too long string {} > 65535 bytes", string.len()));
+                    }
+                    vec.extend_from_slice(&(string.len() as u16).to_le_bytes());
+                    vec.extend_from_slice(string.as_bytes());
+                }
+            },
+            None => vec.push(0),
+        }
+        match self.setting.as_ref() {
+            Some(setting) => {
+                vec.push(1);
+                vec.push(setting.to_int());
+            },
+            None => vec.push(0),
+        }
+        Ok(vec)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        fn read_string(bytes: &[u8], pos: &mut usize) -> Result<String, String> {
+            if bytes.len() < *pos + 2 {
+                return Err("too short to read string length".to_owned());
+            }
+            let len = u16::from_le_bytes([bytes[*pos], bytes[*pos + 1]]) as usize;
+            *pos += 2;
+            if bytes.len() < *pos + len {
+                return Err("too short to read string".to_owned());
+            }
+            let string =
+                String::from_utf8(bytes[*pos..*pos + len].to_vec()).map_err(|err| err.to_string())?;
+            *pos += len;
+            Ok(string)
+        }
+
+        if bytes.len() < 4 + 1 {
+            return Err("too short mint message".to_owned());
+        }
+        let coin_id = bytes_to_u32(&bytes[0..4]);
+        let mut pos = 4;
+
+        // params
+        let params = match bytes[pos] {
+            0 => {
+                pos += 1;
+                None
+            },
+            1 => {
+                pos += 1;
+                if bytes.len() < pos + 21 + 1 {
+                    return Err("too short mint params".to_owned());
+                }
+                let mut owner = [0u8; 21];
+                owner.clone_from_slice(&bytes[pos..pos + 21]);
+                pos += 21;
+                let digit = bytes[pos];
+                pos += 1;
+                let name = read_string(bytes, &mut pos)?;
+                let unit = read_string(bytes, &mut pos)?;
+                let description = read_string(bytes, &mut pos)?;
+                Some(MintParams {
+                    owner,
+                    name,
+                    unit,
+                    digit,
+                    description,
+                })
+            },
+            flag => return Err(format!("unknown mint params flag {}", flag)),
+        };
+
+        // setting
+        let setting = match bytes.get(pos) {
+            Some(0) => {
+                pos += 1;
+                None
+            },
+            Some(1) => match bytes.get(pos + 1) {
+                Some(int) => {
+                    pos += 2;
+                    Some(MintSetting::from_int(*int)?)
+                },
+                None => return Err("too short mint setting".to_owned()),
+            },
+            Some(flag) => return Err(format!("unknown mint setting flag {}", flag)),
+            None => return Err("too short mint setting flag".to_owned()),
+        };
+
+        if pos != bytes.len() {
+            return Err(format!("wrong mint message length {}!={}", pos, bytes.len()));
+        }
+        Ok(MintCoin {
+            coin_id,
+            params,
+            setting,
+        })
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod mint {
+    use crate::tx::*;
+
+    #[test]
+    fn encode_decode() {
+        let coin = MintCoin {
+            coin_id: 5,
+            params: Some(MintParams {
+                owner: [3u8; 21],
+                name: "PyCoin".to_owned(),
+                unit: "PC".to_owned(),
+                digit: 8,
+                description: "example coin".to_owned(),
+            }),
+            setting: Some(MintSetting {
+                additional_issue: true,
+                change_params: false,
+            }),
+        };
+        let bytes = coin.to_bytes().unwrap();
+        assert_eq!(MintCoin::from_bytes(&bytes), Ok(coin.clone()));
+
+        // too long
+        let mut long = coin;
+        long.params.as_mut().unwrap().description = "a".repeat(0x10000);
+        assert!(long.to_bytes().is_err());
+
+        let coin = MintCoin {
+            coin_id: 5,
+            params: None,
+            setting: None,
+        };
+        let bytes = coin.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 4 + 1 + 1);
+        assert_eq!(MintCoin::from_bytes(&bytes), Ok(coin));
+
+        // broken
+        assert!(MintCoin::from_bytes(&bytes[0..5]).is_err());
+        assert!(MintCoin::from_bytes(&[0, 0, 0, 5, 2, 0]).is_err());
+    }
+}
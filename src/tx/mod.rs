@@ -1,7 +1,13 @@
 mod accessory;
+mod lock;
+mod mint;
+mod partial;
 mod transactions;
 mod utils;
 
 pub use accessory::*;
+pub use lock::*;
+pub use mint::*;
+pub use partial::*;
 pub use transactions::*;
 pub use utils::*;
@@ -0,0 +1,363 @@
+use crate::signature::*;
+use crate::tx::*;
+use crate::utils::*;
+use bigint::U256;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// format version of partially signed tx
+static PARTIAL_VERSION: u8 = 1;
+
+type Address = [u8; 21];
+
+/// derivation path `m/44'/CoinType'/account'/is_inner/index`
+pub type KeyPath = (u32, u32, u32);
+
+/// partially signed tx passed between signers (PSBT-like)
+///
+/// `inputs_cache` and `paths` are aligned with `body.inputs`
+#[derive(Clone, PartialEq)]
+pub struct PartialTx {
+    pub body: TxBody,
+    pub inputs_cache: Vec<Option<TxOutput>>,
+    pub paths: Vec<Option<KeyPath>>,
+    pub signature: Vec<Signature>,
+}
+
+impl fmt::Debug for PartialTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entry(&"type", &"partial")
+            .entry(&"hash", &hex::encode(&self.body.hash()))
+            .entry(&"body", &self.body)
+            .entry(&"sign", &self.signature)
+            .entry(&"inputs_cache", &self.inputs_cache)
+            .entry(&"paths", &self.paths)
+            .finish()
+    }
+}
+
+/// JSON format, binary is hex string
+#[derive(Serialize, Deserialize)]
+struct PartialTxJson {
+    version: u8,
+    body: String,
+    inputs: Vec<PartialInputJson>,
+    signature: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PartialInputJson {
+    cache: Option<String>,
+    path: Option<KeyPath>,
+}
+
+impl PartialTx {
+    pub fn new(body: TxBody) -> Self {
+        let len = body.inputs.len();
+        PartialTx {
+            body,
+            inputs_cache: vec![None; len],
+            paths: vec![None; len],
+            signature: vec![],
+        }
+    }
+
+    pub fn from_manual(tx: TxManual) -> Result<Self, String> {
+        let mut partial = PartialTx::new(tx.body);
+        if let Some(inputs_cache) = tx.inputs_cache {
+            if inputs_cache.len() != partial.body.inputs.len() {
+                return Err("inputs_cache length is not same with inputs".to_owned());
+            }
+            partial.inputs_cache = inputs_cache.into_iter().map(Some).collect();
+        }
+        if let Some(signature) = tx.signature {
+            partial.signature = signature;
+        }
+        Ok(partial)
+    }
+
+    pub fn hash(&self) -> U256 {
+        U256::from(self.body.hash().as_slice())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // [version u8][body_len u32][body bytes]
+        // [flags u8, cache 33b?, path 12b?]..(same length with inputs)
+        // [sign_len u32][signature bytes]..
+        let body = self.body.to_bytes();
+        let mut vec = Vec::with_capacity(1 + 4 + body.len() + self.paths.len() * (1 + 33 + 12));
+        vec.push(PARTIAL_VERSION);
+        vec.extend_from_slice(&u32_to_bytes(body.len() as u32));
+        vec.extend_from_slice(&body);
+        for (cache, path) in self.inputs_cache.iter().zip(self.paths.iter()) {
+            // note: flags is has_cache(0b01) and has_path(0b10)
+            let flags = cache.as_ref().map(|_| 0b01).unwrap_or(0) | path.as_ref().map(|_| 0b10).unwrap_or(0);
+            vec.push(flags);
+            if let Some(cache) = cache {
+                vec.extend_from_slice(&cache.to_bytes());
+            }
+            if let Some((account_id, is_inner, index)) = path {
+                vec.extend_from_slice(&u32_to_bytes(*account_id));
+                vec.extend_from_slice(&u32_to_bytes(*is_inner));
+                vec.extend_from_slice(&u32_to_bytes(*index));
+            }
+        }
+        vec.extend_from_slice(&u32_to_bytes(self.signature.len() as u32));
+        for signature in self.signature.iter() {
+            signature_to_bytes(signature, &mut vec);
+        }
+        vec
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        // [version u8][body_len u32][body bytes]
+        // [flags u8, cache 33b?, path 12b?]..(same length with inputs)
+        // [sign_len u32][signature bytes]..
+        let read = |pos: usize, len: usize| {
+            bytes
+                .get(pos..pos + len)
+                .ok_or(format!("partial tx is too short at {}", pos))
+        };
+        if read(0, 1)?[0] != PARTIAL_VERSION {
+            return Err(format!("unknown partial tx version {}", bytes[0]));
+        }
+        let body_len = bytes_to_u32(read(1, 4)?) as usize;
+        let mut partial = PartialTx::new(TxBody::from_bytes(read(5, body_len)?)?);
+        let mut pos = 5 + body_len;
+        for i in 0..partial.body.inputs.len() {
+            let flags = read(pos, 1)?[0];
+            pos += 1;
+            if flags & 0b01 != 0 {
+                partial.inputs_cache[i] = Some(TxOutput::from_bytes(read(pos, 33)?)?);
+                pos += 33;
+            }
+            if flags & 0b10 != 0 {
+                let path = read(pos, 12)?;
+                partial.paths[i] = Some((
+                    bytes_to_u32(&path[0..4]),
+                    bytes_to_u32(&path[4..8]),
+                    bytes_to_u32(&path[8..12]),
+                ));
+                pos += 12;
+            }
+        }
+        let sign_len = bytes_to_u32(read(pos, 4)?);
+        pos += 4;
+        for _ in 0..sign_len {
+            read(pos, 1)?;
+            let signature = bytes_to_signature(&bytes[pos..])?;
+            pos += get_signature_size(&signature);
+            partial.signature.push(signature);
+        }
+        if pos != bytes.len() {
+            return Err(format!("partial tx decode failed {}!={}", bytes.len(), pos));
+        }
+        Ok(partial)
+    }
+
+    pub fn to_json(&self) -> String {
+        let inputs = self
+            .inputs_cache
+            .iter()
+            .zip(self.paths.iter())
+            .map(|(cache, path)| PartialInputJson {
+                cache: cache.as_ref().map(|cache| hex::encode(&cache.to_bytes()[..])),
+                path: path.clone(),
+            })
+            .collect();
+        let signature = self
+            .signature
+            .iter()
+            .map(|signature| {
+                let mut vec = Vec::with_capacity(get_signature_size(signature));
+                signature_to_bytes(signature, &mut vec);
+                hex::encode(&vec)
+            })
+            .collect();
+        let json = PartialTxJson {
+            version: PARTIAL_VERSION,
+            body: hex::encode(&self.body.to_bytes()),
+            inputs,
+            signature,
+        };
+        serde_json::to_string(&json).unwrap()
+    }
+
+    pub fn from_json(string: &str) -> Result<Self, String> {
+        let json: PartialTxJson = serde_json::from_str(string).map_err(|err| err.to_string())?;
+        if json.version != PARTIAL_VERSION {
+            return Err(format!("unknown partial tx version {}", json.version));
+        }
+        let decode = |string: &str| hex::decode(string).map_err(|err| err.to_string());
+        let mut partial = PartialTx::new(TxBody::from_bytes(&decode(&json.body)?)?);
+        if json.inputs.len() != partial.body.inputs.len() {
+            return Err("inputs metadata length is not same with inputs".to_owned());
+        }
+        for (i, input) in json.inputs.into_iter().enumerate() {
+            if let Some(cache) = input.cache {
+                partial.inputs_cache[i] = Some(TxOutput::from_bytes(&decode(&cache)?)?);
+            }
+            partial.paths[i] = input.path;
+        }
+        for signature in json.signature.iter() {
+            let signature = decode(signature)?;
+            if signature.is_empty() {
+                return Err("empty signature".to_owned());
+            }
+            partial.signature.push(bytes_to_signature(&signature)?);
+        }
+        Ok(partial)
+    }
+
+    /// add another signer's cache, paths and signatures of the same body
+    pub fn merge(&mut self, other: &PartialTx) -> Result<(), String> {
+        if self.body != other.body {
+            return Err(format!(
+                "cannot merge different tx {} and {}",
+                hex::encode(&self.body.hash()),
+                hex::encode(&other.body.hash())
+            ));
+        }
+        for i in 0..self.body.inputs.len() {
+            match (&self.inputs_cache[i], &other.inputs_cache[i]) {
+                (None, Some(cache)) => self.inputs_cache[i] = Some(cache.clone()),
+                (Some(a), Some(b)) if a != b => return Err(format!("inputs_cache {} conflict", i)),
+                _ => (),
+            }
+            if self.paths[i].is_none() {
+                self.paths[i] = other.paths[i];
+            }
+        }
+        for signature in other.signature.iter() {
+            if !self.signature.contains(signature) {
+                self.signature.push(signature.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// owner addresses which signature is not collected yet, unknown if inputs_cache is missing
+    pub fn get_missing_signers(&self) -> Vec<Address> {
+        let signed = self
+            .signature
+            .iter()
+            .map(|signature| signature.get_address(0))
+            .collect::<Vec<Address>>();
+        let mut missing: Vec<Address> = Vec::new();
+        for cache in self.inputs_cache.iter().filter_map(|cache| cache.as_ref()) {
+            let owner = get_owner_addr(&cache.0);
+            if !signed.contains(&owner) && !missing.contains(&owner) {
+                missing.push(owner);
+            }
+        }
+        missing
+    }
+
+    /// `inputs - outputs` of coinId 0, None if inputs_cache is missing
+    pub fn get_fee(&self) -> Option<i64> {
+        let mut fee = 0i64;
+        for cache in self.inputs_cache.iter() {
+            match cache {
+                Some(cache) if cache.1 == 0 => fee += cache.2 as i64,
+                Some(_) => (),
+                None => return None,
+            }
+        }
+        for output in self.body.outputs.iter().filter(|output| output.1 == 0) {
+            fee -= output.2 as i64;
+        }
+        Some(fee)
+    }
+
+    /// all inputs_cache collected & all owners signed correctly
+    pub fn is_complete(&self) -> bool {
+        self.inputs_cache.iter().all(|cache| cache.is_some()) && self.get_missing_signers().is_empty()
+    }
+
+    pub fn finalize(self) -> Result<TxVerifiable, String> {
+        if let Some(index) = self.inputs_cache.iter().position(|cache| cache.is_none()) {
+            return Err(format!("inputs_cache {} is missing", index));
+        }
+        let missing = self.get_missing_signers();
+        if let Some(addr) = missing.first() {
+            return Err(format!(
+                "not signed by {} and {} more",
+                hex::encode(&addr[..]),
+                missing.len() - 1
+            ));
+        }
+        let binary = self.body.to_bytes();
+        for (index, signature) in self.signature.iter().enumerate() {
+            match verify_signature(signature, &binary) {
+                Ok(true) => (),
+                _ => return Err(format!("signature {} is invalid", index)),
+            }
+        }
+        Ok(TxVerifiable {
+            hash: self.hash(),
+            body: self.body,
+            signature: self.signature,
+            inputs_cache: self
+                .inputs_cache
+                .into_iter()
+                .map(|cache| cache.unwrap())
+                .collect(),
+        })
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod partial {
+    use crate::signature::Signature;
+    use crate::tx::*;
+    use bigint::U256;
+
+    #[test]
+    fn encode_merge_finalize() {
+        let sk = [7u8; 32];
+        let pk = secp256k1::PublicKey::from_secret_key(
+            &secp256k1::Secp256k1::new(),
+            &secp256k1::SecretKey::from_slice(&sk).unwrap(),
+        )
+        .serialize();
+        let owner = crate::utils::sha256ripemd160(0, &pk);
+
+        let mut body = TxBody::new(0, TxType::Transfer, 0, 10800, 100, 10, TxMessage::Nothing);
+        body.inputs.push(TxInput(U256::from(1u32), 0));
+        body.inputs.push(TxInput(U256::from(2u32), 1));
+        body.outputs.push(TxOutput([0u8; 21], 0, 9000));
+
+        // creator knows first input
+        let mut a = PartialTx::new(body.clone());
+        a.inputs_cache[0] = Some(TxOutput(owner, 0, 5000));
+        a.paths[0] = Some((0, 1, 3));
+        let a = PartialTx::from_bytes(&a.to_bytes()).unwrap();
+        assert_eq!(a.get_fee(), None);
+        assert!(a.clone().finalize().is_err());
+
+        // signer knows second input and signs
+        let mut b = PartialTx::new(body.clone());
+        b.inputs_cache[1] = Some(TxOutput(owner, 0, 5000));
+        b.signature
+            .push(Signature::get_single_sign(&sk, &pk, &body.to_bytes()).unwrap());
+        let b = PartialTx::from_json(&b.to_json()).unwrap();
+
+        // merge
+        let mut merged = a.clone();
+        merged.merge(&b).unwrap();
+        merged.merge(&b).unwrap();
+        assert_eq!(merged.signature.len(), 1);
+        assert_eq!(merged.paths[0], Some((0, 1, 3)));
+        assert_eq!(merged.get_fee(), Some(1000));
+        assert!(merged.is_complete());
+        let tx = merged.finalize().unwrap();
+        assert_eq!(tx.inputs_cache.len(), 2);
+
+        // different body
+        let mut other = PartialTx::new(body.clone());
+        other.body.gas_amount = 11;
+        assert!(a.clone().merge(&other).is_err());
+    }
+}
@@ -131,10 +131,68 @@ pub fn calc_merkleroot_hash(mut hashs: Vec<U256>) -> U256 {
     hashs.pop().unwrap()
 }
 
+/// calculate merkle branch (sibling hashs from bottom to top) of the index
+///
+/// note: siblings don't depend on the index's hash, it can be a dummy
+/// panic if index is out of hashs
+pub fn merkle_branch(mut hashs: Vec<U256>, mut index: usize) -> Vec<U256> {
+    assert!(index < hashs.len());
+    let mut buf = [0u8; 64];
+    let mut branch = Vec::new();
+    while 1 < hashs.len() {
+        // note: same odd-leaf duplication rule with `calc_merkleroot_hash`
+        if hashs.len() % 2 == 1 {
+            let last = hashs.last().unwrap().clone();
+            hashs.push(last);
+        }
+        branch.push(hashs[index ^ 1].clone());
+        let mut new_hashs = Vec::with_capacity(hashs.len() / 2);
+        for i in 0..(hashs.len() / 2) {
+            hashs[i * 2].to_big_endian(&mut buf[0..32]);
+            hashs[i * 2 + 1].to_big_endian(&mut buf[32..64]);
+            let hash = sha256double(buf.as_ref());
+            new_hashs.push(U256::from(hash.as_slice()));
+        }
+        hashs = new_hashs;
+        index /= 2;
+    }
+    branch
+}
+
+/// fold the hash of the index with its merkle branch up to the root
+///
+/// note: reject duplicated odd node as right child, it proves a phantom index (CVE-2012-2459)
+pub fn fold_merkle_branch(hash: &U256, branch: &[U256], mut index: usize) -> Option<U256> {
+    let mut buf = [0u8; 64];
+    let mut hash = *hash;
+    for sibling in branch.iter() {
+        if index % 2 == 1 && sibling == &hash {
+            return None;
+        }
+        if index % 2 == 0 {
+            hash.to_big_endian(&mut buf[0..32]);
+            sibling.to_big_endian(&mut buf[32..64]);
+        } else {
+            sibling.to_big_endian(&mut buf[0..32]);
+            hash.to_big_endian(&mut buf[32..64]);
+        }
+        hash = U256::from(sha256double(buf.as_ref()).as_slice());
+        index /= 2;
+    }
+    Some(hash)
+}
+
+/// verify the txhash is included by the merkleroot with branch of `merkle_branch`
+pub fn verify_merkle_branch(root: &U256, txhash: &U256, branch: &[U256], index: usize) -> bool {
+    // note: index overflow means wrong depth branch
+    let depth_ok = index.checked_shr(branch.len() as u32).unwrap_or(0) == 0;
+    depth_ok && fold_merkle_branch(txhash, branch, index).as_ref() == Some(root)
+}
+
 #[allow(unused_imports)]
 #[cfg(test)]
 mod utils {
-    use crate::utils::calc_merkleroot_hash;
+    use crate::utils::{calc_merkleroot_hash, merkle_branch, sha256double, verify_merkle_branch};
     use bigint::U256;
 
     /// for test case only
@@ -159,4 +217,46 @@ mod utils {
             hex_to_u256_reversed("f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766");
         assert_eq!(calc_merkleroot_hash(hashs), merkleroot);
     }
+
+    #[test]
+    fn test_merkle_branch() {
+        // fold coinbase's branch to merkleroot
+        let hashs: Vec<U256> = (1..6u32).map(U256::from).collect();
+        let branch = merkle_branch(hashs.clone(), 0);
+        assert_eq!(branch.len(), 3);
+        let mut buf = [0u8; 64];
+        let mut hash = hashs[0].clone();
+        for sibling in branch {
+            hash.to_big_endian(&mut buf[0..32]);
+            sibling.to_big_endian(&mut buf[32..64]);
+            hash = U256::from(sha256double(buf.as_ref()).as_slice());
+        }
+        assert_eq!(hash, calc_merkleroot_hash(hashs));
+    }
+
+    #[test]
+    fn test_verify_merkle_branch() {
+        // odd leaf is duplicated at every level
+        let hashs: Vec<U256> = (1..8u32).map(U256::from).collect();
+        let root = calc_merkleroot_hash(hashs.clone());
+        for (index, hash) in hashs.iter().enumerate() {
+            let branch = merkle_branch(hashs.clone(), index);
+            assert!(verify_merkle_branch(&root, hash, &branch, index));
+            // note: duplicated last leaf is only valid on the left side
+            assert!(!verify_merkle_branch(&root, hash, &branch, index ^ 1));
+        }
+
+        // duplicated node at upper level
+        let hashs: Vec<U256> = (1..6u32).map(U256::from).collect();
+        let root = calc_merkleroot_hash(hashs.clone());
+        let branch = merkle_branch(hashs.clone(), 4);
+        assert!(verify_merkle_branch(&root, &hashs[4], &branch, 4));
+        assert!(!verify_merkle_branch(&root, &hashs[4], &branch, 5));
+        assert!(!verify_merkle_branch(&root, &hashs[4], &branch, 6));
+        assert!(!verify_merkle_branch(&root, &hashs[4], &branch, 7));
+        let branch = merkle_branch(hashs.clone(), 2);
+        assert!(!verify_merkle_branch(&root, &hashs[3], &branch, 2));
+        // single tx block's root is the txhash
+        assert!(verify_merkle_branch(&hashs[0], &hashs[0], &[], 0));
+    }
 }